@@ -1,47 +1,283 @@
 use clap::Parser;
-use mpegts_inspector::inspector::{Options, run, AnalysisMode};
+use mpegts_inspector::inspector::{Options, run, run_file_to_writer, run_reader_to_writer, scan, AnalysisMode, BitrateClock, FpsSource, ManualProgram, StreamTemplate, Verdict};
 
 #[derive(Parser)]
 struct Opt {
-    /// UDP socket to bind + listen (IPv4)
+    /// UDP socket to bind + listen (IPv4 or IPv6, e.g. [ff3e::1%2]:1234 to join on interface index 2)
     #[clap(long, default_value = "239.1.1.2:1234")]
     addr: String,
 
+    /// Read TS from a file or pipe instead of UDP ("-" for stdin); .gz/.zst files are decompressed
+    #[clap(long)]
+    input: Option<String>,
+
+    /// Local interface IPv4 address to join IPv4 multicast on (default: kernel choice);
+    /// IPv6 groups take the interface index as the --addr scope id instead
+    #[clap(long)]
+    interface: Option<std::net::IpAddr>,
+
+    /// UDP receive buffer size in bytes (default 4 MiB)
+    #[clap(long)]
+    recv_buffer: Option<usize>,
+
+    /// Bind 0.0.0.0 (or ::) on the --addr port (unicast to a non-local address, e.g. behind NAT).
+    /// Datagrams to any local address on that port are accepted; use --source to filter
+    #[clap(long, default_value_t = false)]
+    bind_any: bool,
+
+    /// Only accept datagrams from this source address
+    #[clap(long)]
+    source: Option<std::net::IpAddr>,
+
     /// Refresh interval for the JSON snapshot
     #[clap(long, default_value_t = 2)]
     refresh: u64,
 
+    /// Stop after this many seconds and exit with the final verdict
+    /// (0 = pass, 2 = Priority 1 errors, 3 = Priority 2 errors only)
+    #[clap(long)]
+    duration: Option<u64>,
+
     /// Disable TR 101 290 analysis (faster, fewer counters)
     #[clap(long, default_value_t = false)]
     no_analysis: bool,
 
-    /// TR 101 290 priority level (1, 12, or all). Only used when analysis is enabled.
+    /// TR 101 290 priority level (1, 12, 3, or all). Only used when analysis is enabled.
     #[clap(long, default_value = "12")]
     tr101_priority: String,
+
+    /// One-line status summary updated in place instead of JSON reports
+    #[clap(long, alias = "oneline", default_value_t = false)]
+    compact: bool,
+
+    /// Append-only log of PSI version changes, programs added/removed and first
+    /// Priority 1 errors instead of JSON reports
+    #[clap(long, default_value_t = false, conflicts_with = "compact")]
+    events: bool,
+
+    /// JSON report layout: "json" (pretty-printed) or "jsonl" (one compact object per line)
+    #[clap(long, default_value = "json", conflicts_with_all = ["compact", "events"])]
+    format: String,
+
+    /// Only inspect these program numbers (comma-separated)
+    #[clap(long, value_delimiter = ',')]
+    program: Option<Vec<u16>>,
+
+    /// Only inspect these elementary PIDs (comma-separated)
+    #[clap(long, value_delimiter = ',')]
+    pid: Option<Vec<u16>>,
+
+    /// Count null packets (PID 0x1FFF) towards the mux bitrate
+    #[clap(long, default_value_t = false)]
+    include_null: bool,
+
+    /// Always report the codec-signaled fps; the PTS estimate only fills in when none is signaled
+    #[clap(long, default_value_t = false)]
+    signaled_fps: bool,
+
+    /// Replace the signaled fps with the PTS estimate when they differ by more than this
+    #[clap(long, default_value_t = 2.0)]
+    fps_tolerance: f32,
+
+    /// Bitrate time base: "pcr" (stream time) or "wall" (arrival time).
+    /// Default: pcr for --input, wall for UDP
+    #[clap(long)]
+    bitrate_clock: Option<String>,
+
+    /// Also report right away on PSI version changes and first Priority 1 errors,
+    /// at most once per this many milliseconds
+    #[clap(long)]
+    event_debounce_ms: Option<u64>,
+
+    /// Scan these addresses instead of monitoring --addr: comma-separated `ip:port` or
+    /// `first-last:port` ranges, e.g. 239.1.1.1-239.1.1.20:1234. Prints one JSON line per address
+    #[clap(long, conflicts_with = "input")]
+    scan: Option<String>,
+
+    /// How long to listen on each scanned address
+    #[clap(long, default_value_t = 2000)]
+    dwell_ms: u64,
+
+    /// Expected stream lineup of a program as `program=counts`, counts being a number and
+    /// class letter each (v video, a audio, s subtitle, d data), e.g. 1=1v2a1s; repeatable.
+    /// Classes left out are not checked
+    #[clap(long)]
+    expect_streams: Vec<String>,
+
+    /// Declare a program the PSI lacks as `program=pid:stream_type,...`, numbers decimal or
+    /// 0x-prefixed, e.g. 1=0x100:0x1b,0x101:0x0f; repeatable. The first PID carries the PCR
+    #[clap(long)]
+    manual_pmt: Vec<String>,
+}
+
+/// Parse an --expect-streams entry into its program number and template
+fn stream_template(spec: &str) -> anyhow::Result<(u16, StreamTemplate)> {
+    let (program, counts) = spec
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("expected 'program=counts' in '{spec}'"))?;
+    let mut template = StreamTemplate::default();
+    let mut digits = String::new();
+    for c in counts.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let count = Some(std::mem::take(&mut digits).parse()?);
+        match c {
+            'v' => template.video = count,
+            'a' => template.audio = count,
+            's' => template.subtitle = count,
+            'd' => template.data = count,
+            _ => anyhow::bail!("unknown stream class '{c}' in '{spec}'; use v, a, s or d"),
+        }
+    }
+    if !digits.is_empty() {
+        anyhow::bail!("count without a stream class in '{spec}'");
+    }
+    Ok((program.parse()?, template))
+}
+
+/// Parse a --manual-pmt entry into its program number and declaration
+fn manual_program(spec: &str) -> anyhow::Result<(u16, ManualProgram)> {
+    let (program, streams) = spec
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("expected 'program=pid:stream_type,...' in '{spec}'"))?;
+    let streams = streams
+        .split(',')
+        .map(|stream| {
+            let (pid, stream_type) = stream
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("expected 'pid:stream_type' in '{stream}'"))?;
+            let pid = number(pid)?;
+            if pid >= 0x1FFF {
+                anyhow::bail!("PID {pid:#x} in '{spec}' is not an elementary PID");
+            }
+            Ok((pid, u8::try_from(number(stream_type)?)?))
+        })
+        .collect::<anyhow::Result<Vec<(u16, u8)>>>()?;
+    let pcr_pid = streams[0].0; // split always yields at least one entry
+    Ok((number(program)?, ManualProgram { pcr_pid, streams }))
+}
+
+/// Decimal or 0x-prefixed hexadecimal number
+fn number(s: &str) -> anyhow::Result<u16> {
+    Ok(match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u16::from_str_radix(hex, 16)?,
+        None => s.parse()?,
+    })
+}
+
+/// Expand a --scan list into socket addresses
+fn scan_addrs(spec: &str) -> anyhow::Result<Vec<std::net::SocketAddr>> {
+    let mut addrs = Vec::new();
+    for entry in spec.split(',') {
+        let (ips, port) = entry
+            .rsplit_once(':')
+            .ok_or_else(|| anyhow::anyhow!("missing port in scan address '{entry}'"))?;
+        let port: u16 = port.parse()?;
+        let (first, last) = ips.split_once('-').unwrap_or((ips, ips));
+        let (first, last): (std::net::Ipv4Addr, std::net::Ipv4Addr) = (first.parse()?, last.parse()?);
+        if first > last {
+            anyhow::bail!("scan range '{ips}' ends before it starts");
+        }
+        addrs.extend((u32::from(first)..=u32::from(last)).map(|ip| std::net::SocketAddr::from((std::net::Ipv4Addr::from(ip), port))));
+    }
+    Ok(addrs)
 }
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
     let opt = Opt::parse();
 
+    // Library diagnostics go to stderr, apart from the reports on stdout; RUST_LOG overrides the level
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_ansi(std::io::IsTerminal::is_terminal(&std::io::stderr()))
+        .with_env_filter(
+            tracing_subscriber::EnvFilter::try_from_default_env().unwrap_or_else(|_| "warn".into()),
+        )
+        .init();
+
     let analysis_mode = if opt.no_analysis {
         None
     } else {
         match opt.tr101_priority.as_str() {
             "1" => Some(AnalysisMode::Tr101Priority1),
             "12" => Some(AnalysisMode::Tr101Priority12),
+            "3" => Some(AnalysisMode::Tr101Priority3),
             "all" => Some(AnalysisMode::Tr101),
             _ => {
-                eprintln!("Invalid TR 101 priority level: '{}'. Use '1', '12', or 'all'", opt.tr101_priority);
+                eprintln!("Invalid TR 101 priority level: '{}'. Use '1', '12', '3', or 'all'", opt.tr101_priority);
                 std::process::exit(1);
             }
         }
     };
 
-    run(Options {
+    let bitrate_clock = match opt.bitrate_clock.as_deref() {
+        None => None,
+        Some("pcr") => Some(BitrateClock::Pcr),
+        Some("wall") => Some(BitrateClock::Wallclock),
+        Some(other) => {
+            eprintln!("Invalid bitrate clock: '{other}'. Use 'pcr' or 'wall'");
+            std::process::exit(1);
+        }
+    };
+
+    let jsonl = match opt.format.as_str() {
+        "json" => false,
+        "jsonl" => true,
+        other => {
+            eprintln!("Invalid format: '{other}'. Use 'json' or 'jsonl'");
+            std::process::exit(1);
+        }
+    };
+
+    let opts = Options {
         addr: opt.addr.parse()?,
+        interface: opt.interface,
+        recv_buffer_bytes: opt.recv_buffer,
+        bind_any: opt.bind_any,
+        source_filter: opt.source,
         refresh_secs: opt.refresh,
+        duration_secs: opt.duration,
         analysis_mode,
-    })
-    .await
+        compact: opt.compact,
+        events: opt.events,
+        jsonl,
+        program_filter: opt.program,
+        pid_filter: opt.pid,
+        include_null_in_mux: opt.include_null,
+        program_listener: None,
+        si_table_listener: None,
+        fps_source: if opt.signaled_fps { FpsSource::Signaled } else { FpsSource::PtsOverride(opt.fps_tolerance) },
+        bitrate_clock,
+        event_debounce: opt.event_debounce_ms.map(std::time::Duration::from_millis),
+        error_capture: None,
+        codec_overrides: Default::default(),
+        expected_streams: opt.expect_streams.iter().map(|spec| stream_template(spec)).collect::<anyhow::Result<_>>()?,
+        manual_programs: opt.manual_pmt.iter().map(|spec| manual_program(spec)).collect::<anyhow::Result<_>>()?,
+    };
+
+    if let Some(spec) = &opt.scan {
+        scan(scan_addrs(spec)?, opts, std::time::Duration::from_millis(opt.dwell_ms), |result| {
+            println!("{}", serde_json::to_string(&result).unwrap_or_default());
+        })
+        .await?;
+        return Ok(());
+    }
+
+    let verdict = match opt.input.as_deref() {
+        Some("-") => run_reader_to_writer(tokio::io::stdin(), opts, std::io::stdout()).await?,
+        Some(path) => run_file_to_writer(path, opts, std::io::stdout()).await?,
+        None => run(opts).await?,
+    };
+
+    if opt.duration.is_some() {
+        match verdict {
+            Verdict::Pass => {}
+            Verdict::Fail => std::process::exit(2),
+            Verdict::Warn => std::process::exit(3),
+        }
+    }
+    Ok(())
 }
\ No newline at end of file
@@ -2,6 +2,12 @@
 
 /// MPEG-TS packet constants
 pub const TS_PACKET_SIZE: usize = 188;
+pub const TS_PACKET_SIZE_RS: usize = 204; // 188 + 16 bytes Reed-Solomon parity
+pub const DEFAULT_NIT_PID: u16 = 0x0010; // Used until the PAT declares a program 0 network_PID
+pub const NULL_PID: u16 = 0x1FFF;
+/// First pmt_map key of a manually declared program; above the 13-bit PID range, so no PMT
+/// on the wire can land there
+pub const MANUAL_PMT_KEY_BASE: u16 = 0x2000;
 pub const TS_SYNC_BYTE: u8 = 0x47;
 
 /// PES packet constants
@@ -11,17 +17,18 @@ pub const PES_START_CODE: [u8; 3] = [0x00, 0x00, 0x01];
 pub const PCR_CLOCK_HZ: f64 = 27_000_000.0; // 27 MHz
 pub const PCR_REPETITION_MS: u64 = 100; // TR 101 290: Maximum 100ms between PCR
 pub const PCR_WRAP_THRESHOLD: u64 = (1u64 << 33) * 300; // PCR wrap-around point
+pub const PCR_DISCONTINUITY_MS: u64 = 100; // TR 101 290: max PCR step without discontinuity_indicator
 
 /// PTS constants
 pub const PTS_CLOCK_HZ: u64 = 90_000; // 90 kHz
 pub const PTS_WRAP_THRESHOLD: u64 = 1u64 << 33; // 33-bit PTS counter
 pub const MAX_PTS_JUMP_SECONDS: u64 = 60; // Maximum allowed PTS jump in seconds (increased for ad insertion/splicing)
 pub const MAX_PTS_JUMP: u64 = PTS_CLOCK_HZ * MAX_PTS_JUMP_SECONDS;
+pub const MAX_PTS_PCR_DELAY_TICKS: i64 = PTS_CLOCK_HZ as i64; // PTS may lead PCR by at most 1 s (DVB T-STD)
 
 /// TR 101 290 timeout constants (in milliseconds)
 pub const PAT_TIMEOUT_MS: u64 = 500;   // 500ms
 pub const PMT_TIMEOUT_MS: u64 = 500;   // 500ms
-pub const CAT_TIMEOUT_MS: u64 = 2000;  // 2s
 pub const NIT_TIMEOUT_MS: u64 = 2000;  // 2s
 pub const SDT_TIMEOUT_MS: u64 = 2000;  // 2s
 pub const EIT_TIMEOUT_MS: u64 = 2000;  // 2s
@@ -30,7 +37,15 @@ pub const TDT_TIMEOUT_MS: u64 = 2000;  // 2s
 /// TR 101 290 thresholds
 pub const NULL_RATE_THRESHOLD: f64 = 0.2; // 20% null packet rate threshold
 pub const SYNC_LOSS_THRESHOLD: u64 = 5;   // Consecutive sync losses before error
+pub const SYNC_RECOVERY_THRESHOLD: u64 = 5; // Consecutive good sync bytes that end a sync loss
 pub const STREAM_TIMEOUT_SECONDS: u64 = 30; // Stream inactivity timeout
+pub const DANGLING_PID_GRACE_SECONDS: u64 = 5; // PMT-declared PID may stay silent this long
+pub const SCRAMBLING_WINDOW_SECONDS: u64 = 2; // Majority of payload packets over this window decides scrambled/clear
+
+/// Network constants
+pub const DEFAULT_RECV_BUFFER_BYTES: usize = 4 * 1024 * 1024; // 4 MiB UDP receive buffer
+pub const MISALIGNED_DATAGRAM_WARN_COUNT: u64 = 100; // Consecutive non-packet-multiple datagrams before warning
+pub const TRANSPORT_WINDOW_SECONDS: f64 = 1.0; // Datagram arrival rate/jitter are measured over this window
 
 /// System PIDs that are always allowed
 pub const SYSTEM_PIDS: &[u16] = &[
@@ -45,6 +60,11 @@ pub const SYSTEM_PIDS: &[u16] = &[
 ];
 
 /// FPS calculation constants
-pub const MIN_PTS_SAMPLES_FOR_FPS: usize = 3;
 pub const MAX_PTS_DELTA_SECONDS: u64 = 1; // Maximum delta between PTS samples
-pub const MAX_PTS_DELTA_TICKS: u64 = PTS_CLOCK_HZ * MAX_PTS_DELTA_SECONDS;
\ No newline at end of file
+pub const MAX_PTS_DELTA_TICKS: u64 = PTS_CLOCK_HZ * MAX_PTS_DELTA_SECONDS;
+pub const DEFAULT_FPS_PTS_TOLERANCE: f32 = 2.0; // PTS estimate replaces signaled fps beyond this difference
+pub const FRAME_RATE_WINDOW_TICKS: u64 = PTS_CLOCK_HZ * 2; // measured_fps counts frames over this much stream time
+pub const VIDEO_HEADER_SEARCH_BYTES: usize = 4096; // Start of each PES kept while looking for the SPS / sequence header
+
+/// GOP tracking constants
+pub const KEYFRAME_TIMEOUT_SECONDS: u64 = 10; // No IDR for this long → keyframe_overdue
\ No newline at end of file
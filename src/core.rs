@@ -1,53 +1,438 @@
 //! Core inspection functionality using the new modular architecture
 
+use std::collections::HashMap;
+use std::io::Write;
+use std::net::SocketAddr;
 use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncRead, AsyncReadExt, BufReader};
 use tokio::net::UdpSocket;
+use tokio::sync::broadcast::error::RecvError;
 
-use crate::types::{Options, InspectorReport, AnalysisMode, AnalysisCommand};
-use crate::network::create_udp_socket;
+use crate::types::{Options, InspectorReport, HitlessReport, ScanResult, AnalysisMode, AnalysisCommand, BitrateClock, Verdict};
+use crate::network::{create_udp_socket, leave_multicast, socket_drops};
 use crate::processor::PacketProcessor;
 use crate::report::Reporter;
+use crate::rtp::{parse_rtp, HitlessMerger};
+use crate::constants::{STREAM_TIMEOUT_SECONDS, TS_PACKET_SIZE};
 
-/// Main entry point for UDP socket-based inspection
-pub async fn run(opts: Options) -> anyhow::Result<()> {
-    let socket = create_udp_socket(&opts.addr.to_string())?;
+/// Packet processor configured from `opts` (TR-101 state and program/PID filters)
+fn processor_for(opts: &Options) -> PacketProcessor {
+    let enable_tr101 = opts.analysis_mode.and_then(AnalysisMode::tr101_priority).is_some();
+    let mut processor = PacketProcessor::new(enable_tr101);
+    processor.program_filter = opts.program_filter.clone();
+    processor.pid_filter = opts.pid_filter.clone();
+    processor.include_null_in_mux = opts.include_null_in_mux;
+    processor.program_listener = opts.program_listener.clone();
+    processor.si_table_listener = opts.si_table_listener.clone();
+    processor.fps_source = opts.fps_source;
+    processor.stats_manager.bitrate_clock = opts.bitrate_clock.unwrap_or_default();
+    processor.error_capture_limit = opts.error_capture.unwrap_or(0);
+    processor.codec_overrides = opts.codec_overrides.clone();
+    processor.expected_streams = opts.expected_streams.clone();
+    processor.add_manual_programs(&opts.manual_programs);
+    processor
+}
+
+/// Main entry point for UDP socket-based inspection, JSON reports go to `out`.
+/// Returns the verdict of the final report once `opts.duration_secs` has elapsed.
+#[tracing::instrument(name = "udp", skip_all, fields(addr = %opts.addr))]
+pub async fn run<W: Write>(opts: Options, out: &mut W) -> anyhow::Result<Verdict> {
+    let socket = create_udp_socket(&opts.addr.to_string(), opts.interface, opts.recv_buffer_bytes, opts.bind_any)?;
     let sock = UdpSocket::from_std(socket.into())?;
 
-    let enable_tr101 = matches!(opts.analysis_mode, Some(AnalysisMode::Tr101) | Some(AnalysisMode::Tr101Priority1) | Some(AnalysisMode::Tr101Priority12));
-    let mut processor = PacketProcessor::new(enable_tr101);
+    let mut processor = processor_for(&opts);
     let mut buf = [0u8; 2048];
     let mut last_print = Instant::now();
+    let mut last_event = None;
+    let mut previous = None;
+    let deadline = deadline_for(&opts);
 
     loop {
-        let n = sock.recv(&mut buf).await?;
+        let n = tokio::select! {
+            n = recv_datagram(&sock, &mut buf, opts.source_filter) => n?,
+            _ = until(deadline) => break,
+        };
         if n == 0 {
             continue;
         }
 
-        // Process TS packets (188 B aligned)
-        for chunk in buf[..n].chunks_exact(188) {
-            if chunk[0] != 0x47 {
-                continue; // bad sync
+        processor.record_arrival(n);
+        processor.process_datagram(&buf[..n], opts.analysis_mode);
+
+        // Generate periodic reports, and event-driven ones in between
+        if event_due(&mut processor, &opts, &mut last_event) || last_print.elapsed() >= Duration::from_secs(opts.refresh_secs) {
+            processor.take_event(); // this report covers whatever is pending
+            processor.cleanup_old_streams(STREAM_TIMEOUT_SECONDS);
+            processor.update_tr101_rates();
+            processor.socket_drops = socket_drops(&sock);
+            write_report(&processor, &opts, &mut previous, out)?;
+            last_print = Instant::now();
+        }
+    }
+
+    processor.update_tr101_rates();
+    processor.socket_drops = socket_drops(&sock);
+    write_report(&processor, &opts, &mut previous, out)?;
+    if opts.compact {
+        writeln!(out)?; // finish the in-place status line
+    }
+    Ok(final_verdict(&processor, &opts))
+}
+
+/// When `opts.duration_secs` runs out, measured from now
+fn deadline_for(opts: &Options) -> Option<tokio::time::Instant> {
+    opts.duration_secs.map(|secs| tokio::time::Instant::now() + Duration::from_secs(secs))
+}
+
+/// Whether an extra report is due now: `opts.event_debounce` is set, `processor` has a
+/// pending PSI change or first Priority 1 error, and the last event report is at least
+/// the debounce interval old. An event inside the interval stays pending until it ends.
+fn event_due(processor: &mut PacketProcessor, opts: &Options, last_event: &mut Option<Instant>) -> bool {
+    let Some(debounce) = opts.event_debounce else { return false };
+    if last_event.is_some_and(|t| t.elapsed() < debounce) || !processor.take_event() {
+        return false;
+    }
+    *last_event = Some(Instant::now());
+    true
+}
+
+/// Resolves at `deadline`, or never without one
+async fn until(deadline: Option<tokio::time::Instant>) {
+    match deadline {
+        Some(deadline) => tokio::time::sleep_until(deadline).await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Verdict of the report `processor` would produce now
+fn final_verdict(processor: &PacketProcessor, opts: &Options) -> Verdict {
+    Reporter::create_report(processor, processor.get_tr101_metrics(), opts.analysis_mode).verdict
+}
+
+/// Write one JSON report (on a single line with `opts.jsonl`), the compact status line
+/// when `opts.compact` is set, or the event lines since the previous report when
+/// `opts.events` is set
+fn write_report<W: Write>(
+    processor: &PacketProcessor,
+    opts: &Options,
+    previous: &mut Option<InspectorReport>,
+    out: &mut W,
+) -> std::io::Result<()> {
+    if opts.compact {
+        let report = Reporter::create_report(processor, processor.get_tr101_metrics(), opts.analysis_mode);
+        let line = Reporter::generate_status_line(&report, previous.as_ref().map(|r| &r.tr101_metrics));
+        // Carriage return + erase-to-end-of-line so the line updates in place
+        write!(out, "\r{line}\x1b[K")?;
+        *previous = Some(report);
+    } else if opts.events {
+        let report = Reporter::create_report(processor, processor.get_tr101_metrics(), opts.analysis_mode);
+        for line in Reporter::generate_event_lines(&report, previous.as_ref()) {
+            writeln!(out, "{line}")?;
+        }
+        *previous = Some(report);
+    } else {
+        let json = Reporter::generate_json_report(
+            processor,
+            processor.get_tr101_metrics(),
+            opts.analysis_mode,
+            !opts.jsonl,
+        );
+        writeln!(out, "{json}")?;
+    }
+    out.flush()
+}
+
+/// Inspect a TS byte stream from any reader (stdin, pipe, TCP, file). Reports are
+/// produced every `opts.refresh_secs` and once more at end of stream.
+/// `opts.addr`, `opts.interface` and `opts.recv_buffer_bytes` are ignored.
+pub async fn run_from_reader<R, F>(reader: R, opts: Options, callback: &mut F) -> anyhow::Result<()>
+where
+    R: AsyncRead + Unpin,
+    F: FnMut(InspectorReport) + Send,
+{
+    drive_reader(reader, &opts, |processor| {
+        callback(Reporter::create_report(processor, processor.get_tr101_metrics(), opts.analysis_mode));
+        Ok(())
+    })
+    .await?;
+    Ok(())
+}
+
+/// Same as [`run_from_reader`], but reports are written to `out` like [`run`].
+/// Returns the verdict of the final report.
+pub async fn run_reader_to_writer<R, W>(reader: R, opts: Options, out: &mut W) -> anyhow::Result<Verdict>
+where
+    R: AsyncRead + Unpin,
+    W: Write,
+{
+    let mut previous = None;
+    let verdict = drive_reader(reader, &opts, |processor| Ok(write_report(processor, &opts, &mut previous, out)?)).await?;
+    if opts.compact {
+        writeln!(out)?; // finish the in-place status line
+    }
+    Ok(verdict)
+}
+
+/// Open a capture file for [`run_from_reader`]. gzip and zstd files (recognised by their
+/// magic bytes) are decompressed on the fly; a `.gz`/`.zst` name without the magic is an error.
+pub async fn open_file(path: &std::path::Path) -> anyhow::Result<Box<dyn AsyncRead + Unpin + Send>> {
+    use async_compression::tokio::bufread::{GzipDecoder, ZstdDecoder};
+
+    let file = tokio::fs::File::open(path)
+        .await
+        .map_err(|e| anyhow::anyhow!("cannot open {}: {e}", path.display()))?;
+    let mut reader = BufReader::new(file);
+    let head = reader.fill_buf().await?;
+
+    if head.starts_with(&[0x1F, 0x8B]) {
+        let mut decoder = GzipDecoder::new(reader);
+        decoder.multiple_members(true); // concatenated captures
+        return Ok(Box::new(decoder));
+    }
+    if head.starts_with(&[0x28, 0xB5, 0x2F, 0xFD]) {
+        let mut decoder = ZstdDecoder::new(reader);
+        decoder.multiple_members(true);
+        return Ok(Box::new(decoder));
+    }
+    if let Some(ext) = path.extension().and_then(|e| e.to_str()).filter(|e| matches!(*e, "gz" | "zst")) {
+        anyhow::bail!("{} has a .{ext} extension but is not {ext}-compressed", path.display());
+    }
+    Ok(Box::new(reader))
+}
+
+/// Read until EOF or `opts.duration_secs`, feeding the processor and calling `emit`
+/// on the report cadence and at the end. Returns the final verdict.
+async fn drive_reader<R, F>(mut reader: R, opts: &Options, mut emit: F) -> anyhow::Result<Verdict>
+where
+    R: AsyncRead + Unpin,
+    F: FnMut(&PacketProcessor) -> anyhow::Result<()>,
+{
+    let mut processor = processor_for(opts);
+    // Files and pipes are read as fast as possible, so only stream time gives real bitrates
+    processor.stats_manager.bitrate_clock = opts.bitrate_clock.unwrap_or(BitrateClock::Pcr);
+    let mut buf = vec![0u8; TS_PACKET_SIZE * 7 * 16];
+    let mut last_print = Instant::now();
+    let mut last_event = None;
+    let deadline = deadline_for(opts);
+
+    loop {
+        let n = tokio::select! {
+            n = reader.read(&mut buf) => n?,
+            _ = until(deadline) => break,
+        };
+        if n == 0 {
+            break; // end of stream
+        }
+
+        // process_buffer carries partial packets over between reads
+        processor.process_buffer(&buf[..n], opts.analysis_mode);
+
+        if event_due(&mut processor, opts, &mut last_event) || last_print.elapsed() >= Duration::from_secs(opts.refresh_secs) {
+            processor.take_event();
+            processor.cleanup_old_streams(STREAM_TIMEOUT_SECONDS);
+            processor.update_tr101_rates();
+            emit(&processor)?;
+            last_print = Instant::now();
+        }
+    }
+
+    processor.update_tr101_rates();
+    emit(&processor)?;
+    Ok(final_verdict(&processor, opts))
+}
+
+/// Inspect several UDP inputs at once. Each address gets its own receive task and
+/// packet processor; reports for all inputs are produced together on one cadence.
+/// `opts.addr` is ignored in favour of `addrs`.
+#[tracing::instrument(name = "multi", skip_all, fields(inputs = addrs.len()))]
+pub async fn run_multi<F>(addrs: Vec<SocketAddr>, opts: Options, callback: &mut F) -> anyhow::Result<()>
+where
+    F: FnMut(HashMap<SocketAddr, InspectorReport>) + Send,
+{
+    if addrs.is_empty() {
+        anyhow::bail!("no input addresses given");
+    }
+
+    let mut rx = spawn_receivers(&addrs, &opts)?;
+    let mut processors: Vec<PacketProcessor> = addrs.iter().map(|_| processor_for(&opts)).collect();
+    let mut last_print = Instant::now();
+    let mut last_event = None;
+
+    while let Some((idx, res)) = rx.recv().await {
+        let buf = res.map_err(|e| anyhow::anyhow!("receive on {} failed: {e}", addrs[idx]))?;
+
+        processors[idx].record_arrival(buf.len());
+        processors[idx].process_datagram(&buf, opts.analysis_mode);
+
+        // An event on any input reports all of them
+        if event_due(&mut processors[idx], &opts, &mut last_event) || last_print.elapsed() >= Duration::from_secs(opts.refresh_secs) {
+            let mut reports = HashMap::with_capacity(addrs.len());
+            for (addr, processor) in addrs.iter().zip(processors.iter_mut()) {
+                processor.take_event();
+                processor.cleanup_old_streams(STREAM_TIMEOUT_SECONDS);
+                processor.update_tr101_rates();
+                let report = Reporter::create_report(
+                    processor,
+                    processor.get_tr101_metrics(),
+                    opts.analysis_mode,
+                );
+                reports.insert(*addr, report);
             }
-            processor.process_packet(chunk, opts.analysis_mode);
+            callback(reports);
+            last_print = Instant::now();
         }
+    }
+    Ok(())
+}
+
+/// Merge redundant RTP inputs (SMPTE 2022-7) and inspect the merged stream.
+/// Per-leg sequence loss and unrecoverable merged loss are reported alongside
+/// the usual report; `reorder_depth` bounds the merge buffer (and its latency).
+/// Once `opts.duration_secs` elapses or every input ends, the buffer is flushed
+/// into a final report.
+#[tracing::instrument(name = "hitless", skip_all, fields(legs = addrs.len(), reorder_depth))]
+pub async fn run_hitless<F>(
+    addrs: Vec<SocketAddr>,
+    opts: Options,
+    reorder_depth: usize,
+    callback: &mut F,
+) -> anyhow::Result<()>
+where
+    F: FnMut(HitlessReport) + Send,
+{
+    if addrs.len() < 2 {
+        anyhow::bail!("hitless merge needs at least two input addresses");
+    }
 
-        // Generate periodic reports
-        if last_print.elapsed() >= Duration::from_secs(opts.refresh_secs) {
-            processor.cleanup_old_streams(30);
+    let mut rx = spawn_receivers(&addrs, &opts)?;
+    let mut processor = processor_for(&opts);
+    let mut merger = HitlessMerger::new(addrs.len(), reorder_depth);
+    let mut last_print = Instant::now();
+    let mut last_event = None;
+    let deadline = deadline_for(&opts);
 
-            let json = Reporter::generate_json_report(
-                &processor,
-                processor.get_tr101_metrics(),
-                opts.analysis_mode,
-            );
-            println!("{json}");
+    loop {
+        let (idx, res) = tokio::select! {
+            received = rx.recv() => match received {
+                Some(received) => received,
+                None => break,
+            },
+            _ = until(deadline) => break,
+        };
+        let buf = res.map_err(|e| anyhow::anyhow!("receive on {} failed: {e}", addrs[idx]))?;
+        let Some(pkt) = parse_rtp(&buf) else {
+            continue; // not RTP
+        };
+
+        for payload in merger.push(idx, pkt) {
+            processor.process_datagram(&payload, opts.analysis_mode);
+        }
+
+        if event_due(&mut processor, &opts, &mut last_event) || last_print.elapsed() >= Duration::from_secs(opts.refresh_secs) {
+            processor.take_event();
+            processor.cleanup_old_streams(STREAM_TIMEOUT_SECONDS);
+            processor.update_tr101_rates();
+            callback(HitlessReport {
+                legs: merger.leg_stats(&addrs),
+                merged_loss: merger.merged_loss(),
+                report: Reporter::create_report(
+                    &processor,
+                    processor.get_tr101_metrics(),
+                    opts.analysis_mode,
+                ),
+            });
             last_print = Instant::now();
         }
     }
+
+    // The last reorder_depth datagrams are still buffered
+    for payload in merger.flush() {
+        processor.process_datagram(&payload, opts.analysis_mode);
+    }
+    processor.update_tr101_rates();
+    callback(HitlessReport {
+        legs: merger.leg_stats(&addrs),
+        merged_loss: merger.merged_loss(),
+        report: Reporter::create_report(&processor, processor.get_tr101_metrics(), opts.analysis_mode),
+    });
+    Ok(())
+}
+
+/// Listen on each address in turn for `dwell`, leave its group and summarise what arrived
+pub async fn run_scan<F>(addrs: Vec<SocketAddr>, opts: Options, dwell: Duration, callback: &mut F) -> anyhow::Result<()>
+where
+    F: FnMut(ScanResult) + Send,
+{
+    for addr in addrs {
+        let socket = create_udp_socket(&addr.to_string(), opts.interface, opts.recv_buffer_bytes, opts.bind_any)?;
+        let sock = UdpSocket::from_std(socket.into())?;
+        tracing::debug!(%addr, ?dwell, "scanning");
+        let mut processor = processor_for(&opts);
+        let mut buf = [0u8; 2048];
+        let deadline = tokio::time::Instant::now() + dwell;
+
+        loop {
+            let n = tokio::select! {
+                n = recv_datagram(&sock, &mut buf, opts.source_filter) => n?,
+                _ = tokio::time::sleep_until(deadline) => break,
+            };
+            processor.process_datagram(&buf[..n], opts.analysis_mode);
+        }
+        leave_multicast(&sock, addr, opts.interface)?;
+
+        let report = Reporter::create_report(&processor, processor.get_tr101_metrics(), opts.analysis_mode);
+        let active = !report.pids.is_empty();
+        callback(ScanResult {
+            addr,
+            active,
+            programs: report.programs.iter().map(|p| p.program_number).collect(),
+            mux_bitrate_kbps: if active { report.mux_bitrate_kbps } else { 0.0 },
+        });
+    }
+    Ok(())
 }
 
-/// Broadcast receiver-based inspection with structured data callback
+/// Receive the next datagram, skipping any not sent from `source_filter`
+async fn recv_datagram(sock: &UdpSocket, buf: &mut [u8], source_filter: Option<std::net::IpAddr>) -> std::io::Result<usize> {
+    loop {
+        let (n, from) = sock
+            .recv_from(buf)
+            .await
+            .inspect_err(|e| tracing::error!(error = %e, local = ?sock.local_addr().ok(), "UDP receive failed"))?;
+        if source_filter.is_none_or(|source| source == from.ip()) {
+            return Ok(n);
+        }
+    }
+}
+
+/// Spawn one receive task per address, funnelling datagrams tagged with the input index
+fn spawn_receivers(
+    addrs: &[SocketAddr],
+    opts: &Options,
+) -> anyhow::Result<tokio::sync::mpsc::Receiver<(usize, std::io::Result<Vec<u8>>)>> {
+    let (tx, rx) = tokio::sync::mpsc::channel(1024);
+    for (idx, addr) in addrs.iter().enumerate() {
+        let socket = create_udp_socket(&addr.to_string(), opts.interface, opts.recv_buffer_bytes, opts.bind_any)?;
+        let sock = UdpSocket::from_std(socket.into())?;
+        let tx = tx.clone();
+        let source_filter = opts.source_filter;
+        tokio::spawn(async move {
+            let mut buf = [0u8; 2048];
+            loop {
+                let res = recv_datagram(&sock, &mut buf, source_filter).await.map(|n| buf[..n].to_vec());
+                let failed = res.is_err();
+                if tx.send((idx, res)).await.is_err() || failed {
+                    break;
+                }
+            }
+        });
+    }
+    Ok(rx)
+}
+
+/// Broadcast receiver-based inspection with structured data callback. A lagging
+/// receiver counts the skipped messages as `dropped_datagrams` and carries on;
+/// inspection ends when every sender is gone.
 pub async fn run_broadcast<F>(
     rx: &mut tokio::sync::broadcast::Receiver<Vec<u8>>,
     refresh_secs: u64,
@@ -59,20 +444,24 @@ where
 {
     let analysis_mode = if analysis { Some(AnalysisMode::Tr101Priority12) } else { Some(AnalysisMode::Mux) };
     let mut processor = PacketProcessor::new(analysis);
+    processor.dropped_datagrams = Some(0);
     let mut last_print = Instant::now();
 
     loop {
-        let buf = rx.recv().await?;
-
-        for chunk in buf.chunks_exact(188) {
-            if chunk[0] != 0x47 {
+        let buf = match rx.recv().await {
+            Ok(buf) => buf,
+            Err(RecvError::Lagged(n)) => {
+                *processor.dropped_datagrams.get_or_insert(0) += n;
                 continue;
             }
-            processor.process_packet(chunk, analysis_mode);
-        }
+            Err(RecvError::Closed) => return Ok(()),
+        };
+
+        processor.process_datagram(&buf, analysis_mode);
 
         if last_print.elapsed() >= Duration::from_secs(refresh_secs) {
-            processor.cleanup_old_streams(30);
+            processor.cleanup_old_streams(STREAM_TIMEOUT_SECONDS);
+            processor.update_tr101_rates();
 
             let report = Reporter::create_report(
                 &processor,
@@ -85,40 +474,44 @@ where
     }
 }
 
-/// Advanced broadcast inspection with runtime analysis control
-pub async fn run_broadcast_with_control(
+/// Advanced broadcast inspection with runtime analysis control, JSON reports go to `out`.
+/// Channel lag is handled as in [`run_broadcast`].
+pub async fn run_broadcast_with_control<W: Write>(
     rx: &mut tokio::sync::broadcast::Receiver<Vec<u8>>,
     control_rx: &mut tokio::sync::broadcast::Receiver<AnalysisCommand>,
     refresh_secs: u64,
     initial_mode: Option<AnalysisMode>,
+    out: &mut W,
 ) -> anyhow::Result<()> {
-    let mut processor = PacketProcessor::new(matches!(initial_mode, Some(AnalysisMode::Tr101)));
+    let mut processor = PacketProcessor::new(initial_mode.and_then(AnalysisMode::tr101_priority).is_some());
+    processor.dropped_datagrams = Some(0);
     let mut current_mode = initial_mode;
     let mut last_print = Instant::now();
+    let started = Instant::now();
 
     loop {
         tokio::select! {
             // Handle TS packet data
             buf_result = rx.recv() => {
-                let buf = buf_result?;
-                for chunk in buf.chunks_exact(188) {
-                    if chunk[0] != 0x47 {
+                let buf = match buf_result {
+                    Ok(buf) => buf,
+                    Err(RecvError::Lagged(n)) => {
+                        *processor.dropped_datagrams.get_or_insert(0) += n;
                         continue;
                     }
+                    Err(RecvError::Closed) => return Ok(()),
+                };
 
-                    // Process packet based on current analysis mode
-                    match current_mode {
-                        Some(AnalysisMode::None) => {
-                            // Skip all analysis except basic packet counting
-                            continue;
-                        },
-                        Some(mode) => {
-                            processor.process_packet(chunk, Some(mode));
-                        },
-                        None => {
-                            // Analysis stopped, just consume packets
-                            continue;
-                        }
+                // Process packets based on current analysis mode
+                match current_mode {
+                    Some(AnalysisMode::None) => {
+                        // Skip all analysis except basic packet counting
+                    },
+                    Some(mode) => {
+                        processor.process_datagram(&buf, Some(mode));
+                    },
+                    None => {
+                        // Analysis stopped, just consume packets
                     }
                 }
             }
@@ -127,20 +520,28 @@ pub async fn run_broadcast_with_control(
             cmd_result = control_rx.recv() => {
                 match cmd_result? {
                     AnalysisCommand::Start(mode) => {
+                        // Datagrams skipped while stopped leave the carried-over bytes stale
+                        if current_mode.is_none_or(|m| m == AnalysisMode::None) {
+                            processor.discard_partial_packet();
+                        }
                         current_mode = Some(mode);
                         processor.set_analysis_mode(Some(mode));
-                        eprintln!("Analysis mode changed to: {:?}", mode);
+                        tracing::info!(?mode, "analysis mode changed");
                     },
                     AnalysisCommand::Stop => {
+                        processor.discard_partial_packet();
                         current_mode = None;
-                        eprintln!("Analysis stopped");
+                        tracing::info!("analysis stopped");
                     },
-                    AnalysisCommand::GetStatus => {
+                    AnalysisCommand::GetStatus(reply) => {
                         let status = crate::types::AnalysisStatus {
                             current_mode,
                             is_running: current_mode.is_some(),
+                            uptime: started.elapsed(),
+                            packets_processed: processor.total_bytes_processed / TS_PACKET_SIZE as u64,
                         };
-                        eprintln!("Analysis status: {:?}", status);
+                        // Never block inspection on a slow or departed caller
+                        let _ = reply.try_send(status);
                     }
                 }
             }
@@ -148,14 +549,17 @@ pub async fn run_broadcast_with_control(
 
         // Generate reports at specified intervals
         if current_mode.is_some() && last_print.elapsed() >= Duration::from_secs(refresh_secs) {
-            processor.cleanup_old_streams(30);
+            processor.cleanup_old_streams(STREAM_TIMEOUT_SECONDS);
+            processor.update_tr101_rates();
 
             let json = Reporter::generate_json_report(
                 &processor,
                 processor.get_tr101_metrics(),
                 current_mode,
+                true,
             );
-            println!("{json}");
+            writeln!(out, "{json}")?;
+            out.flush()?;
             last_print = Instant::now();
         }
     }
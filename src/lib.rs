@@ -15,22 +15,148 @@ mod processor;
 mod psi;
 mod tr101;
 mod si_cache;
+mod rtp;
 
 // Public API module
 pub mod inspector {
     // Re-export public types
     pub use crate::types::{
-        VideoInfo, AudioInfo, SubtitleInfo, CodecInfo, StreamInfo,
-        ProgramInfo, InspectorReport, AnalysisMode, AnalysisCommand,
-        AnalysisStatus, Options
+        VideoInfo, AudioInfo, SubtitleInfo, SubtitlePage, LogicalChannel, EpgCoverage, CodecInfo, StreamInfo, SmoothingBuffer,
+        PesTimestampInfo, PcrIntervalInfo, LastPcrInfo, TransportStats, ProgramInfo, PidInfo, InspectorReport, AnalysisMode, AnalysisCommand,
+        AnalysisStatus, Options, CodecHint, FpsSource, BitrateClock, ProgramListener, SiTable, SiTableListener, RtpLegStats, HitlessReport, ScanResult, ScramblingState, Verdict,
+        SiTables, ReportDiff, ReportChange, ErrorPacket, PidCollision, PidCollisionKind, StreamClass, StreamTemplate, StreamCountMismatch, ManualProgram, stream_type_name
     };
+    pub use crate::psi::{PatSection, PmtSection};
+    pub use crate::psi::pat::PatEntry;
+    pub use crate::psi::pmt::StreamInfo as PmtStreamInfo;
+    pub use crate::psi::sdt::{SdtSection, Service as SdtService};
+    pub use crate::psi::nit::{NitSection, Transport as NitTransport};
+    pub use crate::psi::cat::CatSection;
+    pub use crate::psi::eit::{EitPfSection, EitScheduleSection, EitEvent};
+    pub use crate::psi::descriptor::Component;
+    pub use crate::psi::descriptor::Descriptor;
+    pub use crate::parsers::{inspect_packet, PacketInfo};
 
-    /// Async entry-point; returns when stopped (Ctrl-C or socket error)
-    pub async fn run(opts: Options) -> anyhow::Result<()> {
-        crate::core::run(opts).await
+    /// Async entry-point; returns when stopped (Ctrl-C or socket error), or with the
+    /// verdict of the final report once `opts.duration_secs` has elapsed
+    pub async fn run(opts: Options) -> anyhow::Result<Verdict> {
+        crate::core::run(opts, &mut std::io::stdout()).await
+    }
+
+    /// Same as [`run`], but JSON reports are written to `writer` instead of stdout
+    pub async fn run_to_writer<W: std::io::Write>(opts: Options, mut writer: W) -> anyhow::Result<Verdict> {
+        crate::core::run(opts, &mut writer).await
+    }
+
+    /// One-line OK/WARN/ERROR summary of `report`; counters are compared against
+    /// `previous` (the metrics of the last report shown) to decide the status
+    pub fn status_line(report: &InspectorReport, previous: Option<&crate::tr101::Tr101Metrics>) -> String {
+        crate::report::Reporter::generate_status_line(report, previous)
+    }
+
+    /// Pass/Warn/Fail verdict of `report` (also carried in `report.verdict`)
+    pub fn verdict(report: &InspectorReport) -> Verdict {
+        crate::report::Reporter::verdict(report)
+    }
+
+    /// Lines for what changed since `previous` (PAT/PMT version changes, programs
+    /// added/removed, Priority 1 counters leaving zero), each prefixed with a timestamp
+    pub fn event_lines(report: &InspectorReport, previous: Option<&InspectorReport>) -> Vec<String> {
+        crate::report::Reporter::generate_event_lines(report, previous)
+    }
+
+    /// Inspect a TS byte stream from any async reader (stdin, pipe, TCP socket, file).
+    /// Alignment and partial packets across reads are handled internally; reports
+    /// arrive every `opts.refresh_secs` and once more when the reader hits EOF
+    /// (or `opts.duration_secs` elapses).
+    pub async fn run_from_reader<R, F>(reader: R, opts: Options, mut callback: F) -> anyhow::Result<()>
+    where
+        R: tokio::io::AsyncRead + Unpin,
+        F: FnMut(InspectorReport) + Send,
+    {
+        crate::core::run_from_reader(reader, opts, &mut callback).await
+    }
+
+    /// Same as [`run_from_reader`], but JSON reports (or the compact status line)
+    /// are written to `writer` as in [`run`]; returns the final report's verdict
+    pub async fn run_reader_to_writer<R, W>(reader: R, opts: Options, mut writer: W) -> anyhow::Result<Verdict>
+    where
+        R: tokio::io::AsyncRead + Unpin,
+        W: std::io::Write,
+    {
+        crate::core::run_reader_to_writer(reader, opts, &mut writer).await
+    }
+
+    /// Inspect a capture file like [`run_from_reader`]; `.gz` and `.zst` captures are
+    /// decompressed on the fly (detected from their magic bytes)
+    pub async fn run_from_file<P, F>(path: P, opts: Options, mut callback: F) -> anyhow::Result<()>
+    where
+        P: AsRef<std::path::Path>,
+        F: FnMut(InspectorReport) + Send,
+    {
+        let reader = crate::core::open_file(path.as_ref()).await?;
+        crate::core::run_from_reader(reader, opts, &mut callback).await
+    }
+
+    /// Same as [`run_from_file`], but reports are written to `writer` as in
+    /// [`run_reader_to_writer`]; returns the final report's verdict
+    pub async fn run_file_to_writer<P, W>(path: P, opts: Options, mut writer: W) -> anyhow::Result<Verdict>
+    where
+        P: AsRef<std::path::Path>,
+        W: std::io::Write,
+    {
+        let reader = crate::core::open_file(path.as_ref()).await?;
+        crate::core::run_reader_to_writer(reader, opts, &mut writer).await
+    }
+
+    /// Monitor several UDP inputs (e.g. SMPTE 2022-7 redundant feeds) with one report
+    /// per input, keyed by address. `opts.addr` is ignored; `opts.refresh_secs` and
+    /// `opts.analysis_mode` apply to every input.
+    pub async fn run_multi<F>(
+        addrs: Vec<std::net::SocketAddr>,
+        opts: Options,
+        mut callback: F,
+    ) -> anyhow::Result<()>
+    where
+        F: FnMut(std::collections::HashMap<std::net::SocketAddr, InspectorReport>) + Send,
+    {
+        crate::core::run_multi(addrs, opts, &mut callback).await
+    }
+
+    /// Hitless (SMPTE 2022-7) monitoring of redundant RTP inputs: datagrams from all
+    /// `addrs` are merged by RTP sequence number through a reorder buffer of
+    /// `reorder_depth` packets, which adds that many packet intervals of latency.
+    /// Reports carry per-leg `rtp_packet_loss` and the unrecoverable `merged_loss`.
+    pub async fn run_hitless<F>(
+        addrs: Vec<std::net::SocketAddr>,
+        opts: Options,
+        reorder_depth: usize,
+        mut callback: F,
+    ) -> anyhow::Result<()>
+    where
+        F: FnMut(HitlessReport) + Send,
+    {
+        crate::core::run_hitless(addrs, opts, reorder_depth, &mut callback).await
+    }
+
+    /// Channel scan: join each of `addrs` in turn for `dwell`, then leave it and report
+    /// whether TS traffic was present with a short program summary. `opts.addr` is
+    /// ignored; the other socket options apply to every address.
+    pub async fn scan<F>(
+        addrs: Vec<std::net::SocketAddr>,
+        opts: Options,
+        dwell: std::time::Duration,
+        mut callback: F,
+    ) -> anyhow::Result<()>
+    where
+        F: FnMut(ScanResult) + Send,
+    {
+        crate::core::run_scan(addrs, opts, dwell, &mut callback).await
     }
 
     /// Entry-point that reads TS packets from a broadcast channel and provides structured data via callback.
+    /// Messages missed because the receiver lagged are counted in `dropped_datagrams`;
+    /// returns once all senders have been dropped.
     pub async fn run_from_broadcast<F>(
         mut rx: tokio::sync::broadcast::Receiver<Vec<u8>>,
         refresh_secs: u64,
@@ -50,7 +176,18 @@ pub mod inspector {
         refresh_secs: u64,
         initial_mode: Option<AnalysisMode>,
     ) -> anyhow::Result<()> {
-        crate::core::run_broadcast_with_control(&mut rx, &mut control_rx, refresh_secs, initial_mode).await
+        crate::core::run_broadcast_with_control(&mut rx, &mut control_rx, refresh_secs, initial_mode, &mut std::io::stdout()).await
+    }
+
+    /// Same as [`run_from_broadcast_with_control`], but JSON reports are written to `writer`
+    pub async fn run_from_broadcast_with_control_to_writer<W: std::io::Write>(
+        mut rx: tokio::sync::broadcast::Receiver<Vec<u8>>,
+        mut control_rx: tokio::sync::broadcast::Receiver<AnalysisCommand>,
+        refresh_secs: u64,
+        initial_mode: Option<AnalysisMode>,
+        mut writer: W,
+    ) -> anyhow::Result<()> {
+        crate::core::run_broadcast_with_control(&mut rx, &mut control_rx, refresh_secs, initial_mode, &mut writer).await
     }
 }
 
@@ -58,4 +195,4 @@ pub mod inspector {
 mod core;
 
 // Re-export TR101 for backwards compatibility
-pub use tr101::Tr101Metrics;
+pub use tr101::{SyncLossEpisode, Tr101Metrics, Tr101Rates, VersionChange};
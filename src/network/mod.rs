@@ -1,25 +1,136 @@
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr, SocketAddrV6};
 use socket2::{Domain, Protocol, Socket, Type};
+use crate::constants::DEFAULT_RECV_BUFFER_BYTES;
 
 /// Creates and configures a UDP socket for TS packet reception
-/// Handles both unicast and multicast addresses
-pub fn create_udp_socket(addr: &str) -> anyhow::Result<Socket> {
+/// Handles both unicast and multicast addresses, IPv4 and IPv6. IPv4 groups are joined
+/// on `interface` (by its IPv4 address), IPv6 groups on the interface index given as
+/// the address' scope id (`[ff3e::1%2]:1234`); the kernel default when neither is set.
+/// The receive buffer is sized to `recv_buffer_bytes` (DEFAULT_RECV_BUFFER_BYTES when None).
+/// With `bind_any` the socket binds the wildcard address (0.0.0.0 or ::) on the same port,
+/// e.g. for unicast sent to a public address behind NAT; a multicast `addr` is still joined.
+/// Datagrams are not filtered by destination then: any sent to the port arrive, also
+/// those of other multicast groups joined on it.
+pub fn create_udp_socket(addr: &str, interface: Option<IpAddr>, recv_buffer_bytes: Option<usize>, bind_any: bool) -> anyhow::Result<Socket> {
     let sock_addr: SocketAddr = addr.parse()?;
-    let ip = match sock_addr.ip() {
-        IpAddr::V4(v4) => v4,
-        _ => anyhow::bail!("only IPv4 is supported"),
-    };
+    let ip = sock_addr.ip();
 
-    let socket = Socket::new(Domain::IPV4, Type::DGRAM, Some(Protocol::UDP))?;
+    let socket = Socket::new(Domain::for_address(sock_addr), Type::DGRAM, Some(Protocol::UDP))?;
     socket.set_reuse_address(true)?;
-    socket.bind(&sock_addr.into())?;
+
+    // Default OS buffers overflow at high bitrates, showing up as phantom CC errors
+    let requested = recv_buffer_bytes.unwrap_or(DEFAULT_RECV_BUFFER_BYTES);
+    socket.set_recv_buffer_size(requested)?;
+    let actual = socket.recv_buffer_size()?;
+    if actual < requested {
+        tracing::warn!(
+            actual, requested,
+            "UDP receive buffer clamped by the OS; raise net.core.rmem_max to avoid drops at high bitrate"
+        );
+    }
+    let bind_addr = if bind_any {
+        let any = if ip.is_ipv4() { IpAddr::V4(Ipv4Addr::UNSPECIFIED) } else { IpAddr::V6(Ipv6Addr::UNSPECIFIED) };
+        SocketAddr::new(any, sock_addr.port())
+    } else {
+        sock_addr
+    };
+    if let Err(e) = socket.bind(&bind_addr.into()) {
+        if e.kind() == std::io::ErrorKind::AddrNotAvailable && !ip.is_multicast() {
+            anyhow::bail!("{ip} is not a local address ({e}); bind the wildcard address instead (--bind-any)");
+        }
+        return Err(e.into());
+    }
 
     // Join multicast group if the address is multicast
-    if ip.is_multicast() {
-        let iface = Ipv4Addr::UNSPECIFIED; // default interface
-        socket.join_multicast_v4(&ip, &iface)?;
+    match sock_addr {
+        SocketAddr::V4(v4) if v4.ip().is_multicast() => socket.join_multicast_v4(v4.ip(), &multicast_interface(interface)?)?,
+        SocketAddr::V6(v6) if v6.ip().is_multicast() => socket.join_multicast_v6(v6.ip(), multicast_interface_index(&v6, interface)?)?,
+        _ => {}
     }
 
     socket.set_nonblocking(true)?;
     Ok(socket)
-}
\ No newline at end of file
+}
+
+/// Leave the multicast group `addr` joined by [`create_udp_socket`] with the same
+/// `interface`; nothing to do for unicast addresses
+pub fn leave_multicast(sock: &tokio::net::UdpSocket, addr: SocketAddr, interface: Option<IpAddr>) -> anyhow::Result<()> {
+    match addr {
+        SocketAddr::V4(v4) if v4.ip().is_multicast() => sock.leave_multicast_v4(*v4.ip(), multicast_interface(interface)?)?,
+        SocketAddr::V6(v6) if v6.ip().is_multicast() => sock.leave_multicast_v6(v6.ip(), multicast_interface_index(&v6, interface)?)?,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// IPv4 interface address to join/leave multicast groups on (kernel default if None)
+fn multicast_interface(interface: Option<IpAddr>) -> anyhow::Result<Ipv4Addr> {
+    match interface {
+        Some(IpAddr::V4(v4)) => Ok(v4),
+        Some(IpAddr::V6(_)) => anyhow::bail!("multicast interface for an IPv4 group must be an IPv4 address"),
+        None => Ok(Ipv4Addr::UNSPECIFIED), // default interface
+    }
+}
+
+/// Interface index to join/leave an IPv6 group on: the scope id of `addr` (0, the
+/// kernel default, when absent). An interface address can't name it.
+fn multicast_interface_index(addr: &SocketAddrV6, interface: Option<IpAddr>) -> anyhow::Result<u32> {
+    if interface.is_some() {
+        anyhow::bail!("--interface only applies to IPv4 groups; give the interface index as the scope id, e.g. [{}%2]:{}", addr.ip(), addr.port());
+    }
+    Ok(addr.scope_id())
+}
+
+/// Datagrams the kernel dropped on this socket (receive buffer overflow).
+/// Read from the `drops` column of /proc/net/udp{,6}, matched by socket inode.
+#[cfg(target_os = "linux")]
+pub fn socket_drops(sock: &tokio::net::UdpSocket) -> Option<u64> {
+    use std::os::fd::AsRawFd;
+    use std::os::unix::fs::MetadataExt;
+
+    let inode = std::fs::metadata(format!("/proc/self/fd/{}", sock.as_raw_fd())).ok()?.ino();
+    ["/proc/net/udp", "/proc/net/udp6"].iter().find_map(|path| {
+        let table = std::fs::read_to_string(path).ok()?;
+        table.lines().skip(1).find_map(|line| {
+            // sl local rem st tx:rx tr:tm retrnsmt uid timeout inode ref pointer drops
+            let cols: Vec<&str> = line.split_whitespace().collect();
+            if cols.get(9)?.parse::<u64>().ok()? != inode {
+                return None;
+            }
+            cols.get(12)?.parse().ok()
+        })
+    })
+}
+
+/// Socket drop counters are only available on Linux
+#[cfg(not(target_os = "linux"))]
+pub fn socket_drops(_sock: &tokio::net::UdpSocket) -> Option<u64> {
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ipv6_unicast_receives() {
+        let socket = create_udp_socket("[::1]:0", None, None, false).unwrap();
+        let local = socket.local_addr().unwrap().as_socket().unwrap();
+        assert!(local.is_ipv6());
+
+        let sender = std::net::UdpSocket::bind("[::1]:0").unwrap();
+        sender.send_to(&[0x47; 188], local).unwrap();
+        socket.set_nonblocking(false).unwrap();
+        let mut buf = [std::mem::MaybeUninit::new(0u8); 2048];
+        assert_eq!(socket.recv(&mut buf).unwrap(), 188);
+    }
+
+    #[test]
+    fn ipv6_group_joins_on_the_scope_id() {
+        let addr: SocketAddrV6 = "[ff3e::1%2]:1234".parse().unwrap();
+        assert_eq!(multicast_interface_index(&addr, None).unwrap(), 2);
+        let addr: SocketAddrV6 = "[ff3e::1]:1234".parse().unwrap();
+        assert_eq!(multicast_interface_index(&addr, None).unwrap(), 0);
+        assert!(multicast_interface_index(&addr, Some("192.168.1.10".parse().unwrap())).is_err());
+    }
+}
@@ -1,5 +1,6 @@
 //! Audio codec parsers
 
+use crate::psi::descriptor::Ac3Descriptor;
 use crate::types::AudioInfo;
 
 /// Parse first ADTS header in the payload
@@ -28,6 +29,7 @@ pub fn parse_aac_adts(data: &[u8]) -> Option<AudioInfo> {
                 profile: Some("LC".to_string()),
                 sample_rate: Some(sample_rate),
                 channels: Some(channel_cfg),
+                ..Default::default()
             });
         }
     }
@@ -45,12 +47,13 @@ pub fn parse_mp2(data: &[u8]) -> Option<AudioInfo> {
             // Parse MPEG Audio header
             let version = (header[1] >> 3) & 0x03;
             let layer = (header[1] >> 1) & 0x03;
-            let _bitrate_index = (header[2] >> 4) & 0x0F;
+            let bitrate_index = (header[2] >> 4) & 0x0F;
             let sample_rate_index = (header[2] >> 2) & 0x03;
             let channel_mode = (header[3] >> 6) & 0x03;
 
             // We're specifically looking for Layer II (MP2)
             if layer != 0x02 { continue; }  // Layer II = 0x02
+            if bitrate_index == 0x0F { continue; }  // reserved: not a real header
 
             let sample_rate = match (version, sample_rate_index) {
                 (0x03, 0x00) => 44100,  // MPEG-1, 44.1kHz
@@ -86,60 +89,250 @@ pub fn parse_mp2(data: &[u8]) -> Option<AudioInfo> {
                 profile: None,
                 sample_rate: Some(sample_rate),
                 channels: Some(channels),
+                // None for free format (index 0)
+                declared_bitrate_kbps: mpeg_audio_bitrate_kbps(version, layer, bitrate_index),
+                ..Default::default()
             });
         }
     }
     None
 }
 
-/// Parse AC-3 sync frame header
+/// Parse AC-3 sync frame header and the leading bsi() fields
 pub fn parse_ac3(data: &[u8]) -> Option<AudioInfo> {
     // AC-3 sync frame starts with 0x0B77
-    for i in 0..data.len().saturating_sub(5) {
+    for i in 0..data.len().saturating_sub(7) {
         if data[i] == 0x0B && data[i + 1] == 0x77 {
-            // Basic AC-3 frame found
-            if i + 4 < data.len() {
-                let fscod = (data[i + 4] >> 6) & 0x03;
-                let acmod = (data[i + 6] >> 5) & 0x07;
-
-                let sample_rate = match fscod {
-                    0x00 => 48000,
-                    0x01 => 44100,
-                    0x02 => 32000,
-                    _ => 0,
-                };
-
-                let channels = match acmod {
-                    0x00 => 2, // 1+1 (dual mono)
-                    0x01 => 1, // 1/0 (mono)
-                    0x02 => 2, // 2/0 (stereo)
-                    0x03 => 3, // 3/0
-                    0x04 => 3, // 2/1
-                    0x05 => 4, // 3/1
-                    0x06 => 4, // 2/2
-                    0x07 => 5, // 3/2
-                    _ => 2,
-                };
-
-                // Check for LFE channel
-                let lfe = if acmod == 0x01 { // mono doesn't use lfeon bit
-                    false
-                } else {
-                    (data[i + 6] >> 4) & 0x01 == 1
-                };
+            // bsid sits in the same place in both syntaxes; 11-16 is E-AC-3
+            if data[i + 5] >> 3 > 10 {
+                match parse_eac3(&data[i..]) {
+                    Some(info) => return Some(info),
+                    None => continue,
+                }
+            }
 
-                return Some(AudioInfo {
-                    codec: "AC-3".to_string(),
-                    profile: None,
-                    sample_rate: Some(sample_rate),
-                    channels: Some(channels + if lfe { 1 } else { 0 }),
-                });
+            // syncinfo: syncword(16) crc1(16) fscod(2) frmsizecod(6)
+            let fscod = (data[i + 4] >> 6) & 0x03;
+
+            let sample_rate = match fscod {
+                0x00 => 48000,
+                0x01 => 44100,
+                0x02 => 32000,
+                _ => 0,
+            };
+
+            // bsi: bsid(5) bsmod(3) acmod(3), then fields that depend on acmod
+            let mut bit_offset = 40 + 5;
+            let bsmod = get_bits(data, i, bit_offset, 3) as u8;
+            bit_offset += 3;
+            let acmod = get_bits(data, i, bit_offset, 3) as u8;
+            bit_offset += 3;
+
+            if acmod & 0x01 != 0 && acmod != 0x01 {
+                bit_offset += 2; // cmixlev (three front channels)
+            }
+            if acmod & 0x04 != 0 {
+                bit_offset += 2; // surmixlev (surround channel present)
             }
+            if acmod == 0x02 {
+                bit_offset += 2; // dsurmod (2/0 mode)
+            }
+
+            let lfe = get_bit(data, i, bit_offset);
+            bit_offset += 1;
+
+            // Need the full dialnorm field
+            if i + (bit_offset + 5).div_ceil(8) > data.len() {
+                return None;
+            }
+            let dialnorm = get_bits(data, i, bit_offset, 5) as i8;
+
+            let channels = match acmod {
+                0x00 => 2, // 1+1 (dual mono)
+                0x01 => 1, // 1/0 (mono)
+                0x02 => 2, // 2/0 (stereo)
+                0x03 => 3, // 3/0
+                0x04 => 3, // 2/1
+                0x05 => 4, // 3/1
+                0x06 => 4, // 2/2
+                0x07 => 5, // 3/2
+                _ => 2,
+            };
+
+            return Some(AudioInfo {
+                codec: "AC-3".to_string(),
+                profile: None,
+                sample_rate: Some(sample_rate),
+                channels: Some(channels + if lfe { 1 } else { 0 }),
+                // dialnorm 0 is reserved and decoders treat it as -31 dB
+                dialnorm: Some(if dialnorm == 0 { -31 } else { -dialnorm }),
+                service_type: Some(ac3_service_type(bsmod, acmod).to_string()),
+                declared_bitrate_kbps: None,
+                atmos: false,
+            });
         }
     }
     None
 }
 
+/// Parse the bsi() of an E-AC-3 sync frame starting at `frame[0]` (ETSI TS 102 366
+/// Annex E). Dolby Atmos is detected best-effort from flag_ec3_extension_type_a, the
+/// JOC marker in the first addbsi byte; dependent substreams are ignored.
+fn parse_eac3(frame: &[u8]) -> Option<AudioInfo> {
+    let mut pos = 16; // after the syncword
+    let mut read = |bits: usize| {
+        let v = get_bits(frame, 0, pos, bits);
+        pos += bits;
+        v
+    };
+
+    let strmtyp = read(2);
+    if strmtyp == 3 {
+        return None; // reserved
+    }
+    read(3 + 11); // substreamid, frmsiz
+    let fscod = read(2);
+    let (sample_rate, numblks) = if fscod == 3 {
+        ([24000, 22050, 16000].get(read(2) as usize).copied()?, 6)
+    } else {
+        ([48000, 44100, 32000][fscod as usize], [1, 2, 3, 6][read(2) as usize])
+    };
+    let acmod = read(3) as u8;
+    let lfeon = read(1) != 0;
+    if !(11..=16).contains(&read(5)) {
+        return None; // bsid
+    }
+    let programs = if acmod == 0 { 2 } else { 1 }; // dual mono repeats per-program fields
+    let dialnorm = read(5) as i8;
+    if read(1) != 0 { read(8); } // compr
+    if acmod == 0 {
+        read(5); // dialnorm2
+        if read(1) != 0 { read(8); } // compr2
+    }
+    if strmtyp == 1 && read(1) != 0 {
+        read(16); // chanmap
+    }
+
+    // mixing metadata
+    if read(1) != 0 {
+        if acmod > 2 {
+            read(2); // dmixmod
+            if acmod & 1 != 0 { read(6); } // Lt/Rt and Lo/Ro center mix levels
+            if acmod & 4 != 0 { read(6); } // Lt/Rt and Lo/Ro surround mix levels
+        }
+        if lfeon && read(1) != 0 { read(5); } // lfemixlevcod
+        if strmtyp == 0 {
+            for _ in 0..programs {
+                if read(1) != 0 { read(6); } // pgmscl
+            }
+            if read(1) != 0 { read(6); } // extpgmscl
+            match read(2) {
+                1 => { read(5); }
+                2 => { read(12); }
+                3 => {
+                    for _ in 0..read(5) + 2 { read(8); } // mixdata bytes
+                }
+                _ => {}
+            }
+            if acmod < 2 {
+                for _ in 0..programs {
+                    if read(1) != 0 { read(8 + 6); } // panmean, paninfo
+                }
+            }
+            if read(1) != 0 {
+                for _ in 0..numblks {
+                    if numblks == 1 || read(1) != 0 { read(5); } // blkmixcfginfo
+                }
+            }
+        }
+    }
+
+    // informational metadata
+    let mut bsmod = 0;
+    if read(1) != 0 {
+        bsmod = read(3) as u8;
+        read(2); // copyrightb, origbs
+        if acmod == 2 { read(4); } // dsurmod, dheadphonmod
+        if acmod >= 6 { read(2); } // dsurexmod
+        for _ in 0..programs {
+            if read(1) != 0 { read(8); } // mixlevel, roomtyp, adconvtyp
+        }
+        if fscod < 3 { read(1); } // sourcefscod
+    }
+    if strmtyp == 0 && numblks != 6 {
+        read(1); // convsync
+    }
+    if strmtyp == 2 && (numblks == 6 || read(1) != 0) {
+        read(6); // frmsizecod
+    }
+
+    // additional bsi: the first byte ends with flag_ec3_extension_type_a
+    let atmos = read(1) != 0 && {
+        read(6); // addbsil
+        read(8) & 0x01 != 0
+    };
+    if pos > frame.len() * 8 {
+        return None; // frame truncated within the bsi
+    }
+
+    let channels = match acmod {
+        0x01 => 1,
+        0x00 | 0x02 => 2,
+        0x03 | 0x04 => 3,
+        0x05 | 0x06 => 4,
+        _ => 5,
+    };
+    Some(AudioInfo {
+        codec: "E-AC-3".to_string(),
+        profile: None,
+        sample_rate: Some(sample_rate),
+        channels: Some(channels + lfeon as u8),
+        dialnorm: Some(if dialnorm == 0 { -31 } else { -dialnorm }),
+        service_type: Some(ac3_service_type(bsmod, acmod).to_string()),
+        declared_bitrate_kbps: None,
+        atmos,
+    })
+}
+
+/// What an AC-3/enhanced_AC-3 descriptor declares, named as [`parse_ac3`] would name it
+/// from the bitstream. component_type (EN 300 468 Table D.1) carries the service type in
+/// bsmod terms and a channel layout; multichannel layouts give no exact count.
+pub fn ac3_descriptor_info(descriptor: &Ac3Descriptor) -> AudioInfo {
+    let component_type = descriptor.component_type;
+    // bsid 11-16 is the E-AC-3 syntax, as in parse_ac3
+    let enhanced = descriptor.enhanced
+        || component_type.is_some_and(|c| c & 0x80 != 0)
+        || descriptor.bsid.is_some_and(|bsid| bsid > 10);
+    let layout = component_type.map(|c| c & 0x07);
+    AudioInfo {
+        codec: if enhanced { "E-AC-3" } else { "AC-3" }.to_string(),
+        channels: layout.and_then(|l| match l {
+            0 => Some(1),    // mono
+            1..=3 => Some(2), // 1+1, stereo, Dolby Surround encoded stereo
+            _ => None,
+        }),
+        // Mono is the 1/0 acmod, which makes service type 7 "Voice Over"
+        service_type: component_type
+            .map(|c| ac3_service_type((c >> 3) & 0x07, if layout == Some(0) { 0x01 } else { 0x02 }).to_string()),
+        ..AudioInfo::default()
+    }
+}
+
+/// AC-3 bitstream mode name (A/52 Table 5.7)
+fn ac3_service_type(bsmod: u8, acmod: u8) -> &'static str {
+    match bsmod {
+        0 => "Complete Main",
+        1 => "Music and Effects",
+        2 => "Visually Impaired",
+        3 => "Hearing Impaired",
+        4 => "Dialogue",
+        5 => "Commentary",
+        6 => "Emergency",
+        _ if acmod == 0x01 => "Voice Over",
+        _ => "Karaoke",
+    }
+}
+
 /// Parse AAC LATM (Low-overhead MPEG-4 Audio Transport Multiplex) header
 /// Used in stream_type 0x11 (LATM AAC)
 pub fn parse_aac_latm(data: &[u8]) -> Option<AudioInfo> {
@@ -148,7 +341,7 @@ pub fn parse_aac_latm(data: &[u8]) -> Option<AudioInfo> {
         // Check for LATM sync word: 0x2B7 (11 bits)
         if ((data[i] as u16) << 3) | ((data[i + 1] as u16) >> 5) == 0x2B7 {
             // Found LATM sync, now parse the AudioMuxElement
-            let mut bit_offset = 11; // Skip sync word
+            let mut bit_offset = 11 + 13; // Skip sync word and audioMuxLengthBytes
             let byte_offset = i;
 
             // Parse useSameStreamMux flag
@@ -163,6 +356,7 @@ pub fn parse_aac_latm(data: &[u8]) -> Option<AudioInfo> {
                         profile: Some(profile),
                         sample_rate: Some(sample_rate),
                         channels: Some(channels),
+                        ..Default::default()
                     });
                 }
             } else {
@@ -172,6 +366,7 @@ pub fn parse_aac_latm(data: &[u8]) -> Option<AudioInfo> {
                     profile: Some("LC".to_string()),
                     sample_rate: None, // Would need to store previous config
                     channels: None,
+                    ..Default::default()
                 });
             }
         }
@@ -288,6 +483,140 @@ fn parse_audio_specific_config_latm(data: &[u8], byte_offset: usize, bit_offset:
     }
 }
 
+/// Header bytes [`audio_frame_length`] needs to size any supported frame
+pub const AUDIO_FRAME_HEADER_LEN: usize = 6;
+
+/// Length in bytes (header included) of the audio frame whose sync header starts
+/// `h`, for MPEG audio (0x03/0x04), ADTS (0x0F), LATM (0x11) and AC-3 (0x81).
+/// None if `h` is not a valid frame header of that stream type.
+pub fn audio_frame_length(stream_type: u8, h: &[u8]) -> Option<usize> {
+    if h.len() < AUDIO_FRAME_HEADER_LEN {
+        return None;
+    }
+    match stream_type {
+        0x03 | 0x04 => mpeg_audio_frame_length(h),
+        0x0F if h[0] == 0xFF && h[1] & 0xF6 == 0xF0 => {
+            // aac_frame_length(13)
+            Some((((h[3] & 0x03) as usize) << 11) | ((h[4] as usize) << 3) | (h[5] as usize >> 5))
+        }
+        0x11 if h[0] == 0x56 && h[1] & 0xE0 == 0xE0 => {
+            // AudioSyncStream: syncword(11) audioMuxLengthBytes(13)
+            Some(3 + ((((h[1] & 0x1F) as usize) << 8) | h[2] as usize))
+        }
+        0x81 | 0x87 if h[0] == 0x0B && h[1] == 0x77 && h[5] >> 3 > 10 => {
+            // E-AC-3 frmsiz(11): frame size in 16-bit words minus one
+            Some(((((h[2] & 0x07) as usize) << 8) | h[3] as usize) * 2 + 2)
+        }
+        0x81 | 0x87 if h[0] == 0x0B && h[1] == 0x77 => {
+            let (fscod, frmsizecod) = (h[4] >> 6, (h[4] & 0x3F) as usize);
+            let kbps = *AC3_BITRATES.get(frmsizecod / 2)?;
+            // frame size in 16-bit words: 1536 samples at the coded bitrate
+            let words = match fscod {
+                0 => kbps * 2,
+                1 => kbps * 320 / 147 + (frmsizecod & 1),
+                2 => kbps * 3,
+                _ => return None,
+            };
+            Some(words * 2)
+        }
+        _ => None,
+    }
+}
+
+/// Nominal duration in 90 kHz ticks (samples per frame / sample rate) of the audio
+/// frame whose sync header starts `h`, for MPEG audio, ADTS (one raw_data_block per
+/// frame assumed), AC-3 and E-AC-3. None for LATM, whose header carries no sample rate.
+pub fn audio_frame_ticks(stream_type: u8, h: &[u8]) -> Option<f64> {
+    if h.len() < AUDIO_FRAME_HEADER_LEN {
+        return None;
+    }
+    let (samples, sample_rate) = match stream_type {
+        0x03 | 0x04 if h[0] == 0xFF && h[1] & 0xE0 == 0xE0 => {
+            let version = (h[1] >> 3) & 0x03;
+            let base_rate = [44100, 48000, 32000].get(((h[2] >> 2) & 0x03) as usize)?;
+            let sample_rate = match version {
+                3 => *base_rate,
+                2 => base_rate / 2,
+                0 => base_rate / 4,
+                _ => return None,
+            };
+            let samples = match (h[1] >> 1) & 0x03 {
+                3 => 384,                   // Layer I
+                2 => 1152,                  // Layer II
+                1 if version == 3 => 1152,  // Layer III, MPEG-1
+                1 => 576,                   // Layer III, MPEG-2/2.5
+                _ => return None,
+            };
+            (samples, sample_rate)
+        }
+        0x0F if h[0] == 0xFF && h[1] & 0xF6 == 0xF0 => {
+            (1024, *AAC_SAMPLE_RATES.get(((h[2] >> 2) & 0x0F) as usize)?)
+        }
+        0x81 | 0x87 if h[0] == 0x0B && h[1] == 0x77 && h[5] >> 3 > 10 => {
+            // E-AC-3: fscod(2) numblkscod(2), or fscod2 in their place at half rate
+            let (fscod, numblkscod) = (h[4] >> 6, (h[4] >> 4) & 0x03);
+            if fscod == 3 {
+                (1536, *[24000, 22050, 16000].get(numblkscod as usize)?)
+            } else {
+                ([1, 2, 3, 6][numblkscod as usize] * 256, [48000, 44100, 32000][fscod as usize])
+            }
+        }
+        0x81 | 0x87 if h[0] == 0x0B && h[1] == 0x77 => {
+            (1536, *[48000, 44100, 32000].get((h[4] >> 6) as usize)?)
+        }
+        _ => return None,
+    };
+    Some(samples as f64 * 90_000.0 / sample_rate as f64)
+}
+
+/// ADTS sampling_frequency_index → Hz
+const AAC_SAMPLE_RATES: [u32; 12] = [96000, 88200, 64000, 48000, 44100, 32000, 24000, 22050, 16000, 12000, 11025, 8000];
+
+const AC3_BITRATES: [usize; 19] = [32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384, 448, 512, 576, 640];
+
+/// ISO/IEC 11172-3 / 13818-3 frame length (Layers I-III, MPEG-1/2/2.5)
+fn mpeg_audio_frame_length(h: &[u8]) -> Option<usize> {
+    if h[0] != 0xFF || h[1] & 0xE0 != 0xE0 {
+        return None;
+    }
+    let version = (h[1] >> 3) & 0x03; // 3 = MPEG-1, 2 = MPEG-2, 0 = MPEG-2.5
+    let layer = (h[1] >> 1) & 0x03;   // 3 = Layer I, 2 = II, 1 = III
+    let bitrate_index = h[2] >> 4;
+    let padding = ((h[2] >> 1) & 0x01) as usize;
+
+    let base_rate = [44100, 48000, 32000].get(((h[2] >> 2) & 0x03) as usize)?;
+    let sample_rate = match version {
+        3 => *base_rate,
+        2 => base_rate / 2,
+        0 => base_rate / 4,
+        _ => return None,
+    };
+    // free format or invalid bitrate: no computable length
+    let bitrate = mpeg_audio_bitrate_kbps(version, layer, bitrate_index)? as usize * 1000;
+    Some(match layer {
+        3 => (12 * bitrate / sample_rate + padding) * 4,
+        1 if version != 3 => 72 * bitrate / sample_rate + padding,
+        _ => 144 * bitrate / sample_rate + padding,
+    })
+}
+
+/// Header bitrate in kbps from bitrate_index (ISO/IEC 11172-3 / 13818-3 tables);
+/// None for free format (index 0), the reserved index 15 and reserved versions/layers
+fn mpeg_audio_bitrate_kbps(version: u8, layer: u8, bitrate_index: u8) -> Option<u32> {
+    if version == 1 {
+        return None; // reserved
+    }
+    let kbps: &[u32; 16] = match (version == 3, layer) {
+        (true, 3) => &[0, 32, 64, 96, 128, 160, 192, 224, 256, 288, 320, 352, 384, 416, 448, 0],
+        (true, 2) => &[0, 32, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 384, 0],
+        (true, 1) => &[0, 32, 40, 48, 56, 64, 80, 96, 112, 128, 160, 192, 224, 256, 320, 0],
+        (false, 3) => &[0, 32, 48, 56, 64, 80, 96, 112, 128, 144, 160, 176, 192, 224, 256, 0],
+        (false, 1 | 2) => &[0, 8, 16, 24, 32, 40, 48, 56, 64, 80, 96, 112, 128, 144, 160, 0],
+        _ => return None,
+    };
+    Some(kbps[bitrate_index as usize & 0x0F]).filter(|&k| k > 0)
+}
+
 /// Extract a single bit from data at given byte and bit offset
 fn get_bit(data: &[u8], byte_offset: usize, bit_offset: usize) -> bool {
     let byte_idx = byte_offset + (bit_offset / 8);
@@ -303,16 +632,60 @@ fn get_bit(data: &[u8], byte_offset: usize, bit_offset: usize) -> bool {
 /// Extract multiple bits from data at given byte and bit offset
 fn get_bits(data: &[u8], byte_offset: usize, bit_offset: usize, num_bits: usize) -> u32 {
     let mut result = 0u32;
-    let mut current_bit = bit_offset;
 
-    for _ in 0..num_bits {
+    for current_bit in bit_offset..bit_offset + num_bits {
         if get_bit(data, byte_offset, current_bit) {
             result = (result << 1) | 1;
         } else {
             result <<= 1;
         }
-        current_bit += 1;
     }
 
     result
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn descriptor(enhanced: bool, component_type: Option<u8>, bsid: Option<u8>) -> Ac3Descriptor {
+        Ac3Descriptor { enhanced, component_type, bsid, mainid: None, asvc: None }
+    }
+
+    #[test]
+    fn descriptor_with_only_bsid() {
+        let info = ac3_descriptor_info(&descriptor(false, None, Some(8)));
+        assert_eq!((info.codec.as_str(), info.channels, info.service_type), ("AC-3", None, None));
+        // bsid 16 is E-AC-3 even under tag 0x6A
+        assert_eq!(ac3_descriptor_info(&descriptor(false, None, Some(16))).codec, "E-AC-3");
+    }
+
+    #[test]
+    fn enhanced_descriptor_with_component_type() {
+        let info = ac3_descriptor_info(&descriptor(true, Some(0xC2), None));
+        assert_eq!(info.codec, "E-AC-3");
+        assert_eq!(info.channels, Some(2));
+        assert_eq!(info.service_type.as_deref(), Some("Complete Main"));
+        // Multichannel layouts give no exact count
+        assert_eq!(ac3_descriptor_info(&descriptor(true, Some(0xC4), None)).channels, None);
+    }
+
+    #[test]
+    fn empty_descriptor_declares_only_the_codec() {
+        let info = ac3_descriptor_info(&descriptor(false, None, None));
+        assert_eq!((info.codec.as_str(), info.channels, info.service_type), ("AC-3", None, None));
+        assert_eq!(ac3_descriptor_info(&descriptor(true, None, None)).codec, "E-AC-3");
+    }
+
+    #[test]
+    fn descriptor_agrees_with_the_bitstream() {
+        // 48 kHz, bsid 8, bsmod 0 (complete main), acmod 2/0, no LFE, dialnorm 27
+        let mut frame = vec![0x0B, 0x77, 0x00, 0x00, 0x00, 0x40, 0x43, 0x60];
+        frame.resize(32, 0);
+        let stream = parse_ac3(&frame).expect("AC-3 frame");
+        let declared = ac3_descriptor_info(&descriptor(false, Some(0x42), Some(8)));
+        assert_eq!(declared.codec, stream.codec);
+        assert_eq!(declared.channels, stream.channels);
+        assert_eq!(declared.service_type, stream.service_type);
+        assert_eq!(stream.dialnorm, Some(-27));
+    }
+}
@@ -6,28 +6,42 @@
 mod video;
 mod audio;
 mod utils;
+mod pes;
+mod packet;
 
-pub use video::{parse_mpeg2_seq_hdr, parse_h26x_sps};
-pub use audio::{parse_aac_adts, parse_aac_latm, parse_mp2, parse_ac3};
+pub use pes::{parse_pes_header, PesStreamKind};
+pub use packet::{inspect_packet, PacketInfo};
+pub use video::{parse_mpeg2_seq_hdr, parse_h26x_sps, parse_h266_sps, parse_av1, exceeds_level_limits};
+pub use audio::{parse_aac_adts, parse_aac_latm, parse_mp2, parse_ac3, ac3_descriptor_info, audio_frame_length, audio_frame_ticks, AUDIO_FRAME_HEADER_LEN};
 
 use crate::types::{VideoInfo, AudioInfo};
+use crate::psi::descriptor::FORMAT_ID_AV1;
 
-/// Parse any video codec from elementary stream data
-pub fn parse_video_codec(stream_type: u8, data: &[u8]) -> Option<VideoInfo> {
+/// Parse any video codec from elementary stream data.
+/// `registration` is the PMT registration descriptor format_identifier, used to
+/// identify codecs carried as private data (stream_type 0x06).
+pub fn parse_video_codec(stream_type: u8, registration: Option<[u8; 4]>, data: &[u8]) -> Option<VideoInfo> {
     match stream_type {
         0x02 => parse_mpeg2_seq_hdr(data),
         0x1B | 0x24 => parse_h26x_sps(data),
+        0x33 => parse_h266_sps(data),
+        0x06 if registration == Some(FORMAT_ID_AV1) => parse_av1(data),
         _ => None,
     }
 }
 
+/// Whether [`parse_video_codec`] handles this stream
+pub fn is_video_codec(stream_type: u8, registration: Option<[u8; 4]>) -> bool {
+    matches!(stream_type, 0x02 | 0x1B | 0x24 | 0x33) || (stream_type == 0x06 && registration == Some(FORMAT_ID_AV1))
+}
+
 /// Parse any audio codec from elementary stream data
 pub fn parse_audio_codec(stream_type: u8, data: &[u8]) -> Option<AudioInfo> {
     match stream_type {
         0x03 | 0x04 => parse_mp2(data),
         0x0F => parse_aac_adts(data),
         0x11 => parse_aac_latm(data),    // AAC LATM
-        0x81 => parse_ac3(data),
+        0x81 | 0x87 => parse_ac3(data), // AC-3 or E-AC-3, told apart by bsid
         _ => None,
     }
 }
\ No newline at end of file
@@ -0,0 +1,139 @@
+//! TS packet header parsing (ISO/IEC 13818-1 §2.4.3.2-2.4.3.5)
+
+use serde::Serialize;
+use crate::constants::{TS_PACKET_SIZE, TS_SYNC_BYTE};
+
+/// Header and adaptation field fields of one TS packet, decoded without any state
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct PacketInfo {
+    pub pid: u16,
+    pub transport_error_indicator: bool,
+    pub payload_unit_start: bool,
+    pub transport_priority: bool,
+    pub scrambling_control: u8,       // 0 = clear, 2/3 = even/odd key
+    pub adaptation_field_control: u8, // 1 = payload only, 2 = adaptation only, 3 = both
+    pub continuity_counter: u8,
+    pub adaptation_field_length: Option<u8>,
+    pub discontinuity_indicator: bool,
+    pub random_access_indicator: bool,
+    pub pcr: Option<(u64, u16)>,      // (base 90 kHz, extension 27 MHz)
+    pub payload_offset: Option<usize>, // Start of the payload in the packet, None if there is none
+}
+
+impl PacketInfo {
+    /// PCR in 27 MHz ticks
+    pub fn pcr_27mhz(&self) -> Option<u64> {
+        self.pcr.map(|(base, ext)| base * 300 + ext as u64)
+    }
+}
+
+/// Decode the 188-byte TS packet at the start of `chunk`.
+/// None if `chunk` is shorter than a packet or doesn't start with the sync byte.
+pub fn inspect_packet(chunk: &[u8]) -> Option<PacketInfo> {
+    if chunk.len() < TS_PACKET_SIZE || chunk[0] != TS_SYNC_BYTE {
+        return None;
+    }
+
+    let adaptation_field_control = (chunk[3] & 0x30) >> 4;
+    let mut info = PacketInfo {
+        pid: (((chunk[1] & 0x1F) as u16) << 8) | chunk[2] as u16,
+        transport_error_indicator: chunk[1] & 0x80 != 0,
+        payload_unit_start: chunk[1] & 0x40 != 0,
+        transport_priority: chunk[1] & 0x20 != 0,
+        scrambling_control: chunk[3] >> 6,
+        adaptation_field_control,
+        continuity_counter: chunk[3] & 0x0F,
+        adaptation_field_length: None,
+        discontinuity_indicator: false,
+        random_access_indicator: false,
+        pcr: None,
+        payload_offset: (adaptation_field_control == 1).then_some(4),
+    };
+
+    if adaptation_field_control & 0x02 != 0 {
+        let ad_len = chunk[4];
+        info.adaptation_field_length = Some(ad_len);
+        if ad_len >= 1 {
+            let flags = chunk[5];
+            info.discontinuity_indicator = flags & 0x80 != 0;
+            info.random_access_indicator = flags & 0x40 != 0;
+            if ad_len >= 7 && flags & 0x10 != 0 {
+                let p = &chunk[6..12];
+                let base = ((p[0] as u64) << 25)
+                        | ((p[1] as u64) << 17)
+                        | ((p[2] as u64) << 9)
+                        | ((p[3] as u64) << 1)
+                        | ((p[4] as u64) >> 7);
+                let ext = (((p[4] & 0x01) as u16) << 8) | (p[5] as u16);
+                info.pcr = Some((base, ext));
+            }
+        }
+        if adaptation_field_control == 3 {
+            let offset = 5 + ad_len as usize;
+            info.payload_offset = (offset < TS_PACKET_SIZE).then_some(offset);
+        }
+    }
+
+    Some(info)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn packet(header: [u8; 4], rest: &[u8]) -> Vec<u8> {
+        let mut pkt = header.to_vec();
+        pkt.extend_from_slice(rest);
+        pkt.resize(TS_PACKET_SIZE, 0xFF);
+        pkt
+    }
+
+    #[test]
+    fn pcr_base_and_extension() {
+        // base 0x1_2345_6789 (33 bits), extension 0x155 (9 bits), discontinuity flagged
+        let base: u64 = 0x1_2345_6789;
+        let ext: u16 = 0x155;
+        let pcr = [
+            (base >> 25) as u8,
+            (base >> 17) as u8,
+            (base >> 9) as u8,
+            (base >> 1) as u8,
+            ((base as u8 & 0x01) << 7) | 0x7E | (ext >> 8) as u8,
+            ext as u8,
+        ];
+        let pkt = packet([TS_SYNC_BYTE, 0x41, 0x00, 0x35], &[&[7, 0x90][..], &pcr].concat());
+        let info = inspect_packet(&pkt).expect("packet");
+        assert_eq!((info.pid, info.payload_unit_start, info.continuity_counter), (0x100, true, 5));
+        assert_eq!(info.pcr, Some((base, ext)));
+        assert_eq!(info.pcr_27mhz(), Some(base * 300 + ext as u64));
+        assert!(info.discontinuity_indicator);
+        assert_eq!(info.adaptation_field_length, Some(7));
+        assert_eq!(info.payload_offset, Some(12));
+    }
+
+    #[test]
+    fn payload_offset_follows_the_adaptation_field_control() {
+        let payload_only = packet([TS_SYNC_BYTE, 0x01, 0x00, 0x10], &[]);
+        assert_eq!(inspect_packet(&payload_only).unwrap().payload_offset, Some(4));
+
+        let adaptation_only = packet([TS_SYNC_BYTE, 0x01, 0x00, 0x20], &[183, 0x00]);
+        assert_eq!(inspect_packet(&adaptation_only).unwrap().payload_offset, None);
+
+        // An adaptation field filling the packet leaves no room for the payload
+        for ad_len in [183, 184, 255] {
+            let both = packet([TS_SYNC_BYTE, 0x01, 0x00, 0x30], &[ad_len, 0x00]);
+            assert_eq!(inspect_packet(&both).unwrap().payload_offset, None, "{ad_len}");
+        }
+        let both = packet([TS_SYNC_BYTE, 0x01, 0x00, 0x30], &[182, 0x00]);
+        assert_eq!(inspect_packet(&both).unwrap().payload_offset, Some(187));
+    }
+
+    #[test]
+    fn bad_sync_byte_or_short_chunk_is_not_a_packet() {
+        let mut pkt = packet([TS_SYNC_BYTE, 0x01, 0x00, 0x10], &[]);
+        assert!(inspect_packet(&pkt[..TS_PACKET_SIZE - 1]).is_none());
+        assert!(inspect_packet(&[]).is_none());
+        pkt[0] = 0x48;
+        assert!(inspect_packet(&pkt).is_none());
+    }
+}
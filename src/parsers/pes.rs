@@ -0,0 +1,135 @@
+//! PES packet header parsing (ISO/IEC 13818-1 §2.4.3.6)
+
+use crate::constants::PES_START_CODE;
+
+/// What a PES stream_id carries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PesStreamKind {
+    Video,          // 0xE0-0xEF
+    Audio,          // 0xC0-0xDF
+    PrivateStream1, // 0xBD (AC-3, DVB subtitles, teletext, ...)
+    Extended,       // 0xFD extended_stream_id
+    Padding,        // 0xBE
+    Other,          // PSM, PSD, private_stream_2, ECM/EMM, DSM-CC, ...
+}
+
+impl PesStreamKind {
+    pub fn from_stream_id(stream_id: u8) -> Self {
+        match stream_id {
+            0xE0..=0xEF => Self::Video,
+            0xC0..=0xDF => Self::Audio,
+            0xBD => Self::PrivateStream1,
+            0xFD => Self::Extended,
+            0xBE => Self::Padding,
+            _ => Self::Other,
+        }
+    }
+
+    /// Elementary stream data, expected to carry the optional header with PTS/DTS
+    pub fn is_elementary(self) -> bool {
+        matches!(self, Self::Video | Self::Audio | Self::PrivateStream1 | Self::Extended)
+    }
+}
+
+/// Stream kind, length and timestamps of a PES header
+#[derive(Debug, Clone, Copy)]
+pub struct PesHeader {
+    pub kind: PesStreamKind,
+    pub packet_length: u16, // PES_packet_length; 0 = unbounded (video only)
+    pub pts: Option<u64>,   // 90 kHz
+    pub dts: Option<u64>,   // 90 kHz
+    pub es_offset: usize,   // first elementary stream byte, may be past the end of a short payload
+}
+
+/// Parse the PES header at the start of a PUSI payload. Only elementary stream
+/// ids carry the optional header (flags, PTS/DTS); for the others the data
+/// follows PES_packet_length directly. None if there is no start code or the
+/// fixed part of the header is truncated.
+pub fn parse_pes_header(payload: &[u8]) -> Option<PesHeader> {
+    if payload.len() < 6 || !payload.starts_with(&PES_START_CODE) {
+        return None;
+    }
+    let kind = PesStreamKind::from_stream_id(payload[3]);
+    let packet_length = u16::from_be_bytes([payload[4], payload[5]]);
+
+    if !kind.is_elementary() {
+        return Some(PesHeader { kind, packet_length, pts: None, dts: None, es_offset: 6 });
+    }
+    if payload.len() < 9 {
+        return None;
+    }
+
+    // Timestamps only count when PES_header_data_length covers them; otherwise the
+    // bytes after the header are elementary stream data
+    let header_data_length = payload[8] as usize;
+    let pts_dts_flags = (payload[7] & 0xC0) >> 6;
+    let pts = if pts_dts_flags & 0b10 != 0 && header_data_length >= 5 && payload.len() >= 14 {
+        Some(read_timestamp(&payload[9..14]))
+    } else {
+        None
+    };
+    let dts = if pts_dts_flags == 0b11 && header_data_length >= 10 && payload.len() >= 19 {
+        Some(read_timestamp(&payload[14..19]))
+    } else {
+        None
+    };
+
+    Some(PesHeader { kind, packet_length, pts, dts, es_offset: 9 + header_data_length })
+}
+
+/// Decode a 33-bit PTS/DTS from its 5-byte PES header encoding
+fn read_timestamp(p: &[u8]) -> u64 {
+    ((p[0] as u64 & 0x0E) << 29)
+        | ((p[1] as u64) << 22)
+        | (((p[2] as u64 & 0xFE) >> 1) << 15)
+        | ((p[3] as u64) << 7)
+        | ((p[4] as u64) >> 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Video PES header with a PTS of 90000 and PES_header_data_length `header_data_length`
+    /// (the bytes after the PTS are stuffing), cut to `len` bytes
+    fn pes_header(header_data_length: u8, len: usize) -> Vec<u8> {
+        let mut pes = vec![0x00, 0x00, 0x01, 0xE0, 0x00, 0x00, 0x80, 0x80, header_data_length, 0x21, 0x00, 0x05, 0xBF, 0x21];
+        pes.resize(len, 0xFF);
+        pes
+    }
+
+    #[test]
+    fn minimal_header_without_timestamps() {
+        let pes = parse_pes_header(&[0x00, 0x00, 0x01, 0xE0, 0x00, 0x00, 0x80, 0x00, 0x00]).unwrap();
+        assert_eq!(pes.kind, PesStreamKind::Video);
+        assert_eq!((pes.pts, pes.dts), (None, None));
+        assert_eq!(pes.es_offset, 9);
+        // One byte short of the fixed header
+        assert!(parse_pes_header(&[0x00, 0x00, 0x01, 0xE0, 0x00, 0x00, 0x80, 0x00]).is_none());
+    }
+
+    #[test]
+    fn header_exactly_filling_the_payload() {
+        let pes = parse_pes_header(&pes_header(175, 184)).unwrap();
+        assert_eq!(pes.pts, Some(90_000));
+        assert_eq!(pes.es_offset, 184);
+    }
+
+    #[test]
+    fn header_data_length_past_the_payload() {
+        let pes = parse_pes_header(&pes_header(215, 184)).unwrap();
+        assert_eq!(pes.pts, Some(90_000));
+        assert_eq!(pes.es_offset, 224);
+        // The PTS itself cut off
+        let pes = parse_pes_header(&pes_header(5, 12)).unwrap();
+        assert_eq!(pes.pts, None);
+    }
+
+    #[test]
+    fn pts_flag_without_room_in_the_header_is_ignored() {
+        // PTS_DTS_flags say PTS, but PES_header_data_length 0 leaves no room for it
+        let pes = parse_pes_header(&pes_header(0, 20)).unwrap();
+        assert_eq!(pes.pts, None);
+        assert_eq!(pes.es_offset, 9);
+    }
+}
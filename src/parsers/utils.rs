@@ -18,7 +18,7 @@ pub fn ue<R: std::io::Read>(br: &mut BitReader<R, BigEndian>) -> Option<u32> {
 /// Signed Exp-Golomb decoder
 pub fn se<R: std::io::Read>(br: &mut BitReader<R, BigEndian>) -> Option<i32> {
     let k = ue(br)? as i32;
-    Some(if k & 1 == 0 { -(k as i32 + 1) / 2 } else { (k + 1) / 2 })
+    Some(if k & 1 == 0 { -(k + 1) / 2 } else { (k + 1) / 2 })
 }
 
 /// Remove emulation prevention bytes (0x000003)
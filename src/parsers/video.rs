@@ -17,24 +17,37 @@ pub fn parse_mpeg2_seq_hdr(data: &[u8]) -> Option<VideoInfo> {
                 let aspect_ratio_info = (seq_hdr[3] >> 4) & 0x0F;
                 let frame_rate_code = seq_hdr[3] & 0x0F;
 
-                let fps = match frame_rate_code {
-                    1 => 23.976,
+                let mut fps = match frame_rate_code {
+                    1 => 24000.0 / 1001.0,
                     2 => 24.0,
                     3 => 25.0,
-                    4 => 29.97,
+                    4 => 30000.0 / 1001.0,
                     5 => 30.0,
                     6 => 50.0,
-                    7 => 59.94,
+                    7 => 60000.0 / 1001.0,
                     8 => 60.0,
                     _ => 0.0,
                 };
 
-                let _aspect_ratio = match aspect_ratio_info {
-                    1 => "1:1",     // Square pixels
-                    2 => "4:3",     // 4:3 display
-                    3 => "16:9",    // 16:9 display
-                    4 => "2.21:1",  // 2.21:1 display
-                    _ => "?",
+                // Sequence extension (MPEG-2 only): frame_rate = base * (n + 1) / (d + 1)
+                if let Some((n, d)) = mpeg2_frame_rate_extension(seq_hdr) {
+                    fps = fps * (n + 1) as f64 / (d + 1) as f64;
+                }
+
+                // aspect_ratio_information: 1 = square samples, 2-4 = display aspect ratio
+                let dar = match aspect_ratio_info {
+                    1 => Some((horizontal_size as u32, vertical_size as u32)),
+                    2 => Some((4, 3)),
+                    3 => Some((16, 9)),
+                    4 => Some((221, 100)),
+                    _ => None,
+                };
+                let sar = dar.and_then(|(dw, dh)| {
+                    reduce_ratio(dw * vertical_size as u32, dh * horizontal_size as u32)
+                });
+                let aspect_ratio = match aspect_ratio_info {
+                    4 => Some("2.21:1".to_string()),
+                    _ => dar.and_then(|(dw, dh)| reduce_ratio(dw, dh)).map(|(w, h)| format!("{w}:{h}")),
                 };
 
                 return Some(VideoInfo {
@@ -44,6 +57,11 @@ pub fn parse_mpeg2_seq_hdr(data: &[u8]) -> Option<VideoInfo> {
                     fps: fps as f32,
                     chroma: "4:2:0".to_string(), // MPEG-2 is typically 4:2:0
                     interlaced: false, // MPEG-2 sequence header doesn't provide interlaced info reliably
+                    profile: None,
+                    sar,
+                    aspect_ratio,
+                    bit_depth: Some(8), // MPEG-2 video is always 8-bit
+                    ..Default::default()
                 });
             }
         }
@@ -51,6 +69,15 @@ pub fn parse_mpeg2_seq_hdr(data: &[u8]) -> Option<VideoInfo> {
     None
 }
 
+/// frame_rate_extension_n/d from the sequence_extension (0x000001B5, id 1) following a sequence header
+fn mpeg2_frame_rate_extension(data: &[u8]) -> Option<(u8, u8)> {
+    let pos = data
+        .windows(5)
+        .position(|w| w[..4] == [0x00, 0x00, 0x01, 0xB5] && w[4] >> 4 == 0x1)?;
+    let ext = data.get(pos + 4..pos + 10)?;
+    Some(((ext[5] >> 5) & 0x03, ext[5] & 0x1F))
+}
+
 /// Tries to find the first SPS in a H.264 or HEVC ES payload and returns parsed info
 pub fn parse_h26x_sps(data: &[u8]) -> Option<VideoInfo> {
     // Find NAL start 0x000001 / 0x00000001 and check nal_unit_type
@@ -59,13 +86,13 @@ pub fn parse_h26x_sps(data: &[u8]) -> Option<VideoInfo> {
         if data[i] == 0x00 && data[i + 1] == 0x00 && data[i + 2] == 0x01 {
             let nal_start = i + 3;
             let nal_type = data[nal_start] & 0x1F; // H264
-            if nal_type == 7 {
-                return parse_avc_sps(&data[nal_start + 1..]);
+            if nal_type == 7 && let Some(info) = parse_avc_sps(&data[nal_start + 1..]) {
+                return Some(info);
             }
             // HEVC (0x000001 0x40..0x4F types 33 = SPS)
             let nal_type265 = (data[nal_start] >> 1) & 0x3F;
-            if nal_type265 == 33 {
-                return parse_hevc_sps(&data[nal_start + 2..]);
+            if nal_type265 == 33 && let Some(info) = parse_hevc_sps(&data[nal_start + 2..]) {
+                return Some(info);
             }
         }
         i += 1;
@@ -73,17 +100,186 @@ pub fn parse_h26x_sps(data: &[u8]) -> Option<VideoInfo> {
     None
 }
 
+/// Find and parse a VVC/H.266 SPS (NAL unit type 15, two-byte NAL header)
+pub fn parse_h266_sps(data: &[u8]) -> Option<VideoInfo> {
+    let mut i = 0;
+    while i + 5 < data.len() {
+        if data[i] == 0x00 && data[i + 1] == 0x00 && data[i + 2] == 0x01 {
+            let nal_start = i + 3;
+            let nal_type = (data[nal_start + 1] >> 3) & 0x1F;
+            if nal_type == 15 {
+                return parse_vvc_sps(&data[nal_start + 2..]);
+            }
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Parse the AV1 sequence header OBU (AV1 in MPEG-TS: stream_type 0x06 + 'AV01' registration).
+/// TS carriage uses start codes with emulation prevention in front of each temporal unit;
+/// if no start code is present the payload is treated as a plain sequence of sized OBUs.
+pub fn parse_av1(data: &[u8]) -> Option<VideoInfo> {
+    let mut i = 0;
+    while i + 3 < data.len() {
+        if data[i] == 0x00 && data[i + 1] == 0x00 && data[i + 2] == 0x01
+            && let Some(info) = find_av1_seq_hdr(&remove_emulation_prevention(&data[i + 3..]))
+        {
+            return Some(info);
+        }
+        i += 1;
+    }
+    find_av1_seq_hdr(data)
+}
+
+/// Walk size-delimited OBUs looking for OBU_SEQUENCE_HEADER (type 1)
+fn find_av1_seq_hdr(obus: &[u8]) -> Option<VideoInfo> {
+    let mut pos = 0;
+    while pos < obus.len() {
+        let header = obus[pos];
+        if header & 0x80 != 0 {
+            return None; // forbidden bit set → not an OBU
+        }
+        let obu_type = (header >> 3) & 0x0F;
+        let has_extension = header & 0x04 != 0;
+        let has_size = header & 0x02 != 0;
+        pos += 1 + has_extension as usize;
+
+        let size = if has_size {
+            let (size, len) = leb128(obus.get(pos..)?)?;
+            pos += len;
+            size
+        } else {
+            obus.len().saturating_sub(pos)
+        };
+        let end = pos.checked_add(size)?.min(obus.len());
+
+        if obu_type == 1 {
+            return parse_av1_seq_hdr(obus.get(pos..end)?);
+        }
+        pos = end;
+    }
+    None
+}
+
+fn parse_av1_seq_hdr(raw: &[u8]) -> Option<VideoInfo> {
+    let mut br = BitReader::endian(raw, BigEndian);
+
+    let seq_profile = br.read::<3, u8>().ok()?;
+    br.skip(1).ok()?; // still_picture
+    let reduced_still_picture_header = br.read::<1, u8>().ok()? != 0;
+
+    let mut fps = 0.0_f32;
+    if reduced_still_picture_header {
+        br.skip(5).ok()?; // seq_level_idx[0]
+    } else {
+        let mut buffer_delay_length = 0;
+        let mut decoder_model_info_present = false;
+        if br.read::<1, u8>().ok()? != 0 {
+            // timing_info_present_flag
+            let num_units_in_display_tick = br.read::<32, u32>().ok()?;
+            let time_scale = br.read::<32, u32>().ok()?;
+            let mut ticks_per_picture = 1u32;
+            if br.read::<1, u8>().ok()? != 0 {
+                // equal_picture_interval
+                ticks_per_picture = uvlc(&mut br)?.saturating_add(1);
+            }
+            if num_units_in_display_tick > 0 {
+                fps = time_scale as f32 / (num_units_in_display_tick as f32 * ticks_per_picture as f32);
+                if !(1.0..=120.0).contains(&fps) {
+                    fps = 0.0; // Invalid, will be calculated from PTS
+                }
+            }
+
+            decoder_model_info_present = br.read::<1, u8>().ok()? != 0;
+            if decoder_model_info_present {
+                buffer_delay_length = br.read::<5, u32>().ok()? + 1;
+                br.skip(32).ok()?; // num_units_in_decoding_tick
+                br.skip(10).ok()?; // buffer_removal_time_length_minus_1, frame_presentation_time_length_minus_1
+            }
+        }
+        let initial_display_delay_present = br.read::<1, u8>().ok()? != 0;
+        let operating_points = br.read::<5, u8>().ok()? + 1;
+        for _ in 0..operating_points {
+            br.skip(12).ok()?; // operating_point_idc
+            let seq_level_idx = br.read::<5, u8>().ok()?;
+            if seq_level_idx > 7 {
+                br.skip(1).ok()?; // seq_tier
+            }
+            if decoder_model_info_present && br.read::<1, u8>().ok()? != 0 {
+                // operating_parameters_info
+                br.skip(2 * buffer_delay_length + 1).ok()?;
+            }
+            if initial_display_delay_present && br.read::<1, u8>().ok()? != 0 {
+                br.skip(4).ok()?; // initial_display_delay_minus_1
+            }
+        }
+    }
+
+    let frame_width_bits = br.read::<4, u32>().ok()? + 1;
+    let frame_height_bits = br.read::<4, u32>().ok()? + 1;
+    let max_frame_width_minus_1 = br.read_var::<u32>(frame_width_bits).ok()?;
+    let max_frame_height_minus_1 = br.read_var::<u32>(frame_height_bits).ok()?;
+
+    let (profile, chroma) = match seq_profile {
+        0 => ("Main", "4:2:0"),
+        1 => ("High", "4:4:4"),
+        2 => ("Professional", ""), // 4:2:2 or 4:4:4 12-bit, needs color_config
+        _ => ("?", ""),
+    };
+
+    Some(VideoInfo {
+        codec: "AV1".to_string(),
+        width: (max_frame_width_minus_1 + 1) as u16,
+        height: (max_frame_height_minus_1 + 1) as u16,
+        fps,
+        chroma: chroma.to_string(),
+        interlaced: false, // AV1 has no interlaced coding tools
+        profile: Some(profile.to_string()),
+        sar: None,
+        aspect_ratio: None,
+        ..Default::default()
+    })
+}
+
+/// AV1 leb128() → (value, bytes consumed)
+fn leb128(data: &[u8]) -> Option<(usize, usize)> {
+    let mut value = 0usize;
+    for (i, &byte) in data.iter().take(8).enumerate() {
+        value |= ((byte & 0x7F) as usize) << (i * 7);
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// AV1 uvlc() variable length code
+fn uvlc<R: std::io::Read>(br: &mut BitReader<R, BigEndian>) -> Option<u32> {
+    let mut leading_zeros = 0u32;
+    while br.read::<1, u8>().ok()? == 0 {
+        leading_zeros += 1;
+        if leading_zeros >= 32 {
+            return Some(u32::MAX);
+        }
+    }
+    let value = br.read_var::<u32>(leading_zeros).ok()?;
+    Some(value + ((1u32 << leading_zeros) - 1))
+}
+
 fn parse_avc_sps(raw: &[u8]) -> Option<VideoInfo> {
     let rbsp = remove_ep(raw);
     let mut br = BitReader::endian(&rbsp[..], BigEndian);
 
     // Header
     let profile_idc = br.read::<8, u8>().ok()?;
-    br.skip(16).ok()?;                          // constraint flags + level_idc
+    let constraint_flags = br.read::<8, u8>().ok()?;
+    let level_idc = br.read::<8, u8>().ok()?;
     ue(&mut br)?;                                   // seq_parameter_set_id
 
     // High profiles
     let mut chroma_format_idc = 1;
+    let mut bit_depth_luma = 8;
     if matches!(
         profile_idc,
         100 | 110 | 122 | 244 | 44 | 83 | 86 | 118 | 128 | 138 | 144
@@ -92,7 +288,7 @@ fn parse_avc_sps(raw: &[u8]) -> Option<VideoInfo> {
         if chroma_format_idc == 3 {
             br.skip(1).ok()?; // separate_colour_plane_flag
         }
-        ue(&mut br)?; // bit_depth_luma_minus8
+        bit_depth_luma = ue(&mut br)? + 8;
         ue(&mut br)?; // bit_depth_chroma_minus8
         br.skip(1).ok()?; // qpprime_y_zero_transform_bypass_flag
 
@@ -130,8 +326,8 @@ fn parse_avc_sps(raw: &[u8]) -> Option<VideoInfo> {
     br.skip(1).ok()?; // gaps_in_frame_num_value_allowed_flag
 
     // Size
-    let pic_width_in_mbs_minus1 = ue(&mut br)? as u32;
-    let pic_height_in_map_units_minus1 = ue(&mut br)? as u32;
+    let pic_width_in_mbs_minus1 = ue(&mut br)?;
+    let pic_height_in_map_units_minus1 = ue(&mut br)?;
     let frame_mbs_only_flag = br.read::<1, u8>().ok()? != 0;
     if !frame_mbs_only_flag {
         br.skip(1).ok()?; // mb_adaptive_frame_field_flag
@@ -151,16 +347,23 @@ fn parse_avc_sps(raw: &[u8]) -> Option<VideoInfo> {
         (0, 0, 0, 0)
     };
 
-    // VUI → fps
+    // VUI → SAR, colour description, fps
     let mut fps = 0.0_f32;
+    let mut sar = None;
+    let mut colour = VideoColour::default();
     if br.read::<1, u8>().ok()? != 0 {
         // vui_parameters_present_flag
         if br.read::<1, u8>().ok()? != 0 {
             // aspect_ratio_info_present_flag
             let idc = br.read::<8, u8>().ok()?;
-            if idc == 255 {
-                br.skip(16).ok()?; // sar_width/height
-            }
+            sar = if idc == 255 {
+                // Extended_SAR
+                let sar_width = br.read::<16, u16>().ok()?;
+                let sar_height = br.read::<16, u16>().ok()?;
+                Some((sar_width, sar_height))
+            } else {
+                avc_sar(idc)
+            };
         }
         if br.read::<1, u8>().ok()? != 0 {
             // overscan_info_present_flag
@@ -168,9 +371,13 @@ fn parse_avc_sps(raw: &[u8]) -> Option<VideoInfo> {
         }
         if br.read::<1, u8>().ok()? != 0 {
             // video_signal_type_present_flag
-            br.skip(3).ok()?;
+            br.skip(3).ok()?; // video_format
+            colour.full_range = Some(br.read::<1, u8>().ok()? != 0);
             if br.read::<1, u8>().ok()? != 0 {
-                br.skip(24).ok()?;
+                // colour_description_present_flag
+                colour.primaries = colour_primaries_name(br.read::<8, u8>().ok()?);
+                colour.transfer = transfer_name(br.read::<8, u8>().ok()?);
+                colour.matrix = matrix_name(br.read::<8, u8>().ok()?);
             }
         }
         if br.read::<1, u8>().ok()? != 0 {
@@ -231,21 +438,614 @@ fn parse_avc_sps(raw: &[u8]) -> Option<VideoInfo> {
         }
         .to_string(),
         interlaced: !frame_mbs_only_flag,
+        profile: None,
+        // Level 1b: level_idc 9, or 11 with constraint_set3_flag in Baseline/Main/Extended
+        level: Some(if level_idc == 9 || (level_idc == 11 && constraint_flags & 0x10 != 0 && matches!(profile_idc, 66 | 77 | 88)) {
+            1.0
+        } else {
+            level_idc as f32 / 10.0
+        }),
+        sar: sar.filter(|&(w, h)| w > 0 && h > 0),
+        aspect_ratio: sar.and_then(|(sw, sh)| {
+            reduce_ratio(width * sw as u32, height * sh as u32).map(|(w, h)| format!("{w}:{h}"))
+        }),
+        bit_depth: Some(bit_depth_luma as u8),
+        full_range: colour.full_range,
+        colour_primaries: colour.primaries,
+        transfer: colour.transfer,
+        matrix: colour.matrix,
+        ..Default::default()
+    })
+}
+
+/// VUI video_signal_type of an SPS
+#[derive(Default)]
+struct VideoColour {
+    full_range: Option<bool>,
+    primaries: Option<String>,
+    transfer: Option<String>,
+    matrix: Option<String>,
+}
+
+/// ITU-T H.273 colour_primaries; None for unspecified (2)
+fn colour_primaries_name(code: u8) -> Option<String> {
+    Some(match code {
+        1 => "BT.709",
+        2 => return None,
+        4 => "BT.470M",
+        5 => "BT.470BG",
+        6 => "SMPTE 170M",
+        7 => "SMPTE 240M",
+        8 => "Film",
+        9 => "BT.2020",
+        10 => "SMPTE ST 428",
+        11 => "DCI-P3",
+        12 => "Display P3",
+        22 => "EBU 3213",
+        _ => return Some(format!("reserved ({code})")),
+    }.to_string())
+}
+
+/// ITU-T H.273 transfer_characteristics; None for unspecified (2)
+fn transfer_name(code: u8) -> Option<String> {
+    Some(match code {
+        1 => "BT.709",
+        2 => return None,
+        4 => "BT.470M",
+        5 => "BT.470BG",
+        6 => "SMPTE 170M",
+        7 => "SMPTE 240M",
+        8 => "Linear",
+        9 => "Log 100:1",
+        10 => "Log 316:1",
+        11 => "IEC 61966-2-4",
+        12 => "BT.1361",
+        13 => "sRGB",
+        14 => "BT.2020 10-bit",
+        15 => "BT.2020 12-bit",
+        16 => "PQ",
+        17 => "SMPTE ST 428",
+        18 => "HLG",
+        _ => return Some(format!("reserved ({code})")),
+    }.to_string())
+}
+
+/// ITU-T H.273 matrix_coefficients; None for unspecified (2)
+fn matrix_name(code: u8) -> Option<String> {
+    Some(match code {
+        0 => "Identity",
+        1 => "BT.709",
+        2 => return None,
+        4 => "FCC",
+        5 => "BT.470BG",
+        6 => "SMPTE 170M",
+        7 => "SMPTE 240M",
+        8 => "YCgCo",
+        9 => "BT.2020 NCL",
+        10 => "BT.2020 CL",
+        11 => "SMPTE ST 2085",
+        12 => "Chroma NCL",
+        13 => "Chroma CL",
+        14 => "ICtCp",
+        _ => return Some(format!("reserved ({code})")),
+    }.to_string())
+}
+
+/// Whether the resolution, or resolution × fps when the fps is known, is beyond the
+/// limits of the signaled H.264 (Table A-1) or HEVC (Tables A.8/A.9) level
+pub fn exceeds_level_limits(v: &VideoInfo) -> bool {
+    let Some(level) = v.level else { return false };
+    // The limits count frames. An interlaced stream's fps may be its field rate (VUI
+    // timing without fixed_frame_rate_flag, or field-coded PTS steps); no interlaced
+    // format runs above 30 frames/s, so a higher rate is halved.
+    let fps = if v.interlaced && v.fps > 30.0 { v.fps as f64 / 2.0 } else { v.fps as f64 };
+    let (width, height) = (v.width as f64, v.height as f64);
+    match v.codec.as_str() {
+        "H.264" => {
+            // (max macroblocks per second, max frame size in macroblocks)
+            let (max_mbps, max_fs) = match (level * 10.0).round() as u32 {
+                10 => (1485.0, 99.0),
+                11 => (3000.0, 396.0),
+                12 => (6000.0, 396.0),
+                13 | 20 => (11880.0, 396.0),
+                21 => (19800.0, 792.0),
+                22 => (20250.0, 1620.0),
+                30 => (40500.0, 1620.0),
+                31 => (108000.0, 3600.0),
+                32 => (216000.0, 5120.0),
+                40 | 41 => (245760.0, 8192.0),
+                42 => (522240.0, 8704.0),
+                50 => (589824.0, 22080.0),
+                51 => (983040.0, 36864.0),
+                52 => (2073600.0, 36864.0),
+                60 => (4177920.0, 139264.0),
+                61 => (8355840.0, 139264.0),
+                62 => (16711680.0, 139264.0),
+                _ => return false,
+            };
+            let (w_mbs, h_mbs) = ((width / 16.0).ceil(), (height / 16.0).ceil());
+            let max_side = (max_fs * 8.0_f64).sqrt();
+            w_mbs * h_mbs > max_fs
+                || w_mbs > max_side
+                || h_mbs > max_side
+                || (fps > 0.0 && w_mbs * h_mbs * fps > max_mbps)
+        }
+        "HEVC" => {
+            // (MaxLumaPs, MaxLumaSr)
+            let (max_ps, max_sr) = match (level * 30.0).round() as u32 {
+                30 => (36864.0, 552960.0),
+                60 => (122880.0, 3686400.0),
+                63 => (245760.0, 7372800.0),
+                90 => (552960.0, 16588800.0),
+                93 => (983040.0, 33177600.0),
+                120 => (2228224.0, 66846720.0),
+                123 => (2228224.0, 133693440.0),
+                150 => (8912896.0, 267386880.0),
+                153 => (8912896.0, 534773760.0),
+                156 => (8912896.0, 1069547520.0),
+                180 => (35651584.0, 1069547520.0),
+                183 => (35651584.0, 2139095040.0),
+                186 => (35651584.0, 4278190080.0),
+                _ => return false,
+            };
+            let max_side = (max_ps * 8.0_f64).sqrt();
+            width * height > max_ps
+                || width > max_side
+                || height > max_side
+                || (fps > 0.0 && width * height * fps > max_sr)
+        }
+        _ => false,
+    }
+}
+
+/// H.264 Table E-1 aspect_ratio_idc → sample aspect ratio
+fn avc_sar(idc: u8) -> Option<(u16, u16)> {
+    Some(match idc {
+        1 => (1, 1),
+        2 => (12, 11),
+        3 => (10, 11),
+        4 => (16, 11),
+        5 => (40, 33),
+        6 => (24, 11),
+        7 => (20, 11),
+        8 => (32, 11),
+        9 => (80, 33),
+        10 => (18, 11),
+        11 => (15, 11),
+        12 => (64, 33),
+        13 => (160, 99),
+        14 => (4, 3),
+        15 => (3, 2),
+        16 => (2, 1),
+        _ => return None,
+    })
+}
+
+/// Reduce a ratio to lowest terms (None if either side is zero or it doesn't fit u16)
+fn reduce_ratio(w: u32, h: u32) -> Option<(u16, u16)> {
+    if w == 0 || h == 0 {
+        return None;
+    }
+    let (mut a, mut b) = (w, h);
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    Some((u16::try_from(w / a).ok()?, u16::try_from(h / a).ok()?))
+}
+
+fn parse_vvc_sps(raw: &[u8]) -> Option<VideoInfo> {
+    let rbsp = remove_emulation_prevention(raw);
+    let mut br = BitReader::endian(&rbsp[..], BigEndian);
+
+    br.skip(8).ok()?; // sps_seq_parameter_set_id, sps_video_parameter_set_id
+    let max_sublayers_minus1 = br.read::<3, u32>().ok()?;
+    let chroma_format_idc = br.read::<2, u8>().ok()?;
+    br.skip(2).ok()?; // sps_log2_ctu_size_minus5
+    let ptl_present = br.read::<1, u8>().ok()? != 0;
+
+    let mut profile = None;
+    if ptl_present {
+        // profile_tier_level(1, sps_max_sublayers_minus1)
+        let general_profile_idc = br.read::<7, u8>().ok()?;
+        br.skip(1 + 8 + 2).ok()?; // tier, level_idc, frame_only, multilayer_enabled
+
+        // general_constraints_info()
+        if br.read::<1, u8>().ok()? != 0 {
+            br.skip(71).ok()?;
+            let additional_bits = br.read::<8, u32>().ok()?;
+            br.skip(additional_bits).ok()?;
+        }
+        br.byte_align();
+
+        let mut sublayer_level_present = 0;
+        for _ in 0..max_sublayers_minus1 {
+            sublayer_level_present += br.read::<1, u32>().ok()?;
+        }
+        br.byte_align();
+        br.skip(8 * sublayer_level_present).ok()?; // sublayer_level_idc
+        let num_sub_profiles = br.read::<8, u32>().ok()?;
+        br.skip(32 * num_sub_profiles).ok()?;
+
+        profile = Some(match general_profile_idc {
+            1 => "Main 10",
+            17 => "Multilayer Main 10",
+            33 => "Main 10 4:4:4",
+            49 => "Multilayer Main 10 4:4:4",
+            65 => "Main 10 Still Picture",
+            97 => "Main 10 4:4:4 Still Picture",
+            _ => "?",
+        }.to_string());
+    }
+
+    br.skip(1).ok()?; // sps_gdr_enabled_flag
+    if br.read::<1, u8>().ok()? != 0 {
+        br.skip(1).ok()?; // sps_res_change_in_clvs_allowed_flag
+    }
+    let width = ue(&mut br)?;
+    let height = ue(&mut br)?;
+
+    Some(VideoInfo {
+        codec: "VVC".to_string(),
+        width: width as u16,
+        height: height as u16,
+        fps: 0.0,
+        chroma: match chroma_format_idc {
+            0 => "4:0:0",
+            1 => "4:2:0",
+            2 => "4:2:2",
+            _ => "4:4:4",
+        }.to_string(),
+        interlaced: false, // VVC has no interlaced coding tools
+        profile,
+        sar: None,
+        aspect_ratio: None,
+        ..Default::default()
     })
 }
 
 fn parse_hevc_sps(raw: &[u8]) -> Option<VideoInfo> {
     let rbsp = remove_emulation_prevention(raw);
-    let mut rdr = BitReader::endian(&rbsp[..], bitstream_io::BigEndian);
-    rdr.skip(4 * 8).ok()?; // skip sps_video_parameter_set_id .. etc
-    let width = ue(&mut rdr)? as u16; // misleading – real parsing needs more, simplified
-    let height = ue(&mut rdr)? as u16;
+    let mut br = BitReader::endian(&rbsp[..], BigEndian);
+
+    br.skip(4).ok()?; // sps_video_parameter_set_id
+    let max_sub_layers_minus1 = br.read::<3, u32>().ok()?;
+    br.skip(1).ok()?; // sps_temporal_id_nesting_flag
+    let ptl = parse_hevc_profile_tier_level(&mut br, max_sub_layers_minus1)?;
+    ue(&mut br)?; // sps_seq_parameter_set_id
+
+    let chroma_format_idc = ue(&mut br)?;
+    if chroma_format_idc == 3 {
+        br.skip(1).ok()?; // separate_colour_plane_flag
+    }
+    let pic_width = ue(&mut br)?;
+    let pic_height = ue(&mut br)?;
+
+    let (mut width, mut height) = (pic_width, pic_height);
+    if br.read::<1, u8>().ok()? != 0 {
+        // conformance_window_flag, offsets in chroma sample units
+        let sub_width = if matches!(chroma_format_idc, 1 | 2) { 2 } else { 1 };
+        let sub_height = if chroma_format_idc == 1 { 2 } else { 1 };
+        let (left, right) = (ue(&mut br)?, ue(&mut br)?);
+        let (top, bottom) = (ue(&mut br)?, ue(&mut br)?);
+        width = width.saturating_sub((left + right) * sub_width);
+        height = height.saturating_sub((top + bottom) * sub_height);
+    }
+    let bit_depth_luma = ue(&mut br)? + 8;
+    // The VUI is optional: a stream whose SPS is cut short still reports what came before
+    let colour = hevc_vui_colour(&mut br, max_sub_layers_minus1).unwrap_or_default();
+
     Some(VideoInfo {
         codec: "HEVC".to_string(),
-        width,
-        height,
+        width: width as u16,
+        height: height as u16,
         fps: 0.0,
-        chroma: String::new(),
-        interlaced: false, // Simplified HEVC parser doesn't detect interlaced
+        chroma: match chroma_format_idc {
+            0 => "4:0:0",
+            1 => "4:2:0",
+            2 => "4:2:2",
+            3 => "4:4:4",
+            _ => "?",
+        }
+        .to_string(),
+        interlaced: false, // field_seq_flag lives in the VUI, not parsed
+        profile: Some(hevc_profile_name(ptl.profile_idc, chroma_format_idc, bit_depth_luma)),
+        level: Some(ptl.level_idc as f32 / 30.0),
+        tier: Some(if ptl.tier_flag { "High" } else { "Main" }.to_string()),
+        sar: None,
+        aspect_ratio: None,
+        bit_depth: Some(bit_depth_luma as u8),
+        full_range: colour.full_range,
+        colour_primaries: colour.primaries,
+        transfer: colour.transfer,
+        matrix: colour.matrix,
+        ..Default::default()
     })
-}
\ No newline at end of file
+}
+
+/// Walk the rest of an HEVC SPS from bit_depth_chroma_minus8 to the VUI and read its
+/// video_signal_type (H.265 §7.3.2.2, E.2.1); default when there is no VUI
+fn hevc_vui_colour<R: std::io::Read>(br: &mut BitReader<R, BigEndian>, max_sub_layers_minus1: u32) -> Option<VideoColour> {
+    ue(br)?; // bit_depth_chroma_minus8
+    let log2_max_poc_lsb = ue(br)?.checked_add(4).filter(|&n| n <= 16)?;
+    let ordering_info_all_layers = br.read::<1, u8>().ok()? != 0;
+    let layers = if ordering_info_all_layers { max_sub_layers_minus1 + 1 } else { 1 };
+    for _ in 0..layers * 3 {
+        ue(br)?; // max_dec_pic_buffering_minus1, max_num_reorder_pics, max_latency_increase_plus1
+    }
+    for _ in 0..6 {
+        ue(br)?; // coding/transform block sizes, max_transform_hierarchy_depth_inter/intra
+    }
+    if br.read::<1, u8>().ok()? != 0 && br.read::<1, u8>().ok()? != 0 {
+        // scaling_list_enabled_flag, sps_scaling_list_data_present_flag
+        for size_id in 0..4 {
+            let step = if size_id == 3 { 3 } else { 1 };
+            for _ in (0..6).step_by(step) {
+                if br.read::<1, u8>().ok()? == 0 {
+                    ue(br)?; // scaling_list_pred_matrix_id_delta
+                } else {
+                    if size_id > 1 {
+                        se(br)?; // scaling_list_dc_coef_minus8
+                    }
+                    for _ in 0..(1 << (4 + (size_id << 1))).min(64) {
+                        se(br)?; // scaling_list_delta_coef
+                    }
+                }
+            }
+        }
+    }
+    br.skip(2).ok()?; // amp_enabled_flag, sample_adaptive_offset_enabled_flag
+    if br.read::<1, u8>().ok()? != 0 {
+        // pcm_enabled_flag
+        br.skip(8).ok()?; // pcm_sample_bit_depth_luma/chroma_minus1
+        ue(br)?;
+        ue(br)?;
+        br.skip(1).ok()?; // pcm_loop_filter_disabled_flag
+    }
+    let num_short_term_ref_pic_sets = ue(br)?;
+    let mut num_delta_pocs: Vec<u32> = Vec::new();
+    for idx in 0..num_short_term_ref_pic_sets {
+        // st_ref_pic_set(idx); in the SPS a predicted set refers to the one before it
+        let predicted = idx != 0 && br.read::<1, u8>().ok()? != 0;
+        let count = if predicted {
+            br.skip(1).ok()?; // delta_rps_sign
+            ue(br)?; // abs_delta_rps_minus1
+            let mut count = 0;
+            for _ in 0..=num_delta_pocs[idx as usize - 1] {
+                let used_by_curr_pic = br.read::<1, u8>().ok()? != 0;
+                if used_by_curr_pic || br.read::<1, u8>().ok()? != 0 {
+                    count += 1; // used_by_curr_pic_flag or use_delta_flag
+                }
+            }
+            count
+        } else {
+            let count = ue(br)?.checked_add(ue(br)?)?; // num_negative_pics, num_positive_pics
+            for _ in 0..count {
+                ue(br)?; // delta_poc_s0/s1_minus1
+                br.skip(1).ok()?; // used_by_curr_pic_s0/s1_flag
+            }
+            count
+        };
+        num_delta_pocs.push(count);
+    }
+    if br.read::<1, u8>().ok()? != 0 {
+        // long_term_ref_pics_present_flag
+        for _ in 0..ue(br)? {
+            br.skip(log2_max_poc_lsb + 1).ok()?; // lt_ref_pic_poc_lsb_sps, used_by_curr_pic_lt_sps_flag
+        }
+    }
+    br.skip(2).ok()?; // sps_temporal_mvp_enabled_flag, strong_intra_smoothing_enabled_flag
+
+    let mut colour = VideoColour::default();
+    if br.read::<1, u8>().ok()? == 0 {
+        return Some(colour); // vui_parameters_present_flag
+    }
+    if br.read::<1, u8>().ok()? != 0 && br.read::<8, u8>().ok()? == 255 {
+        // aspect_ratio_info_present_flag, Extended_SAR
+        br.skip(32).ok()?;
+    }
+    if br.read::<1, u8>().ok()? != 0 {
+        // overscan_info_present_flag
+        br.skip(1).ok()?;
+    }
+    if br.read::<1, u8>().ok()? != 0 {
+        // video_signal_type_present_flag
+        br.skip(3).ok()?; // video_format
+        colour.full_range = Some(br.read::<1, u8>().ok()? != 0);
+        if br.read::<1, u8>().ok()? != 0 {
+            // colour_description_present_flag
+            colour.primaries = colour_primaries_name(br.read::<8, u8>().ok()?);
+            colour.transfer = transfer_name(br.read::<8, u8>().ok()?);
+            colour.matrix = matrix_name(br.read::<8, u8>().ok()?);
+        }
+    }
+    Some(colour)
+}
+
+/// General profile/tier/level from an HEVC profile_tier_level()
+struct HevcPtl {
+    profile_idc: u8,
+    tier_flag: bool,
+    level_idc: u8,
+}
+
+/// Parse profile_tier_level(1, sps_max_sub_layers_minus1), skipping the sub-layer part
+fn parse_hevc_profile_tier_level<R: std::io::Read>(
+    br: &mut BitReader<R, BigEndian>,
+    max_sub_layers_minus1: u32,
+) -> Option<HevcPtl> {
+    br.skip(2).ok()?; // general_profile_space
+    let tier_flag = br.read::<1, u8>().ok()? != 0;
+    let mut profile_idc = br.read::<5, u8>().ok()?;
+    let compatibility_flags = br.read::<32, u32>().ok()?;
+    br.skip(4 + 43 + 1).ok()?; // source/constraint flags, general_inbld_flag
+    let level_idc = br.read::<8, u8>().ok()?;
+
+    if profile_idc == 0 {
+        // Some encoders only signal the profile through the compatibility flags
+        profile_idc = (1..32u8).find(|&j| compatibility_flags & (1 << (31 - j)) != 0).unwrap_or(0);
+    }
+
+    let mut sub_layer_bits = 0;
+    for _ in 0..max_sub_layers_minus1 {
+        let profile_present = br.read::<1, u8>().ok()? != 0;
+        let level_present = br.read::<1, u8>().ok()? != 0;
+        sub_layer_bits += if profile_present { 88 } else { 0 } + if level_present { 8 } else { 0 };
+    }
+    if max_sub_layers_minus1 > 0 {
+        br.skip(2 * (8 - max_sub_layers_minus1)).ok()?; // reserved_zero_2bits
+    }
+    br.skip(sub_layer_bits).ok()?;
+    Some(HevcPtl { profile_idc, tier_flag, level_idc })
+}
+
+/// HEVC profile name; range extension profiles are told apart by chroma format and bit depth
+fn hevc_profile_name(profile_idc: u8, chroma_format_idc: u32, bit_depth: u32) -> String {
+    match profile_idc {
+        1 => "Main".to_string(),
+        2 => "Main 10".to_string(),
+        3 => "Main Still Picture".to_string(),
+        4 => match (chroma_format_idc, bit_depth) {
+            (0, _) => "Monochrome".to_string(),
+            (1, 12) => "Main 12".to_string(),
+            (2, ..=10) => "Main 4:2:2 10".to_string(),
+            (2, _) => "Main 4:2:2 12".to_string(),
+            (3, 8) => "Main 4:4:4".to_string(),
+            (3, ..=10) => "Main 4:4:4 10".to_string(),
+            (3, _) => "Main 4:4:4 12".to_string(),
+            _ => "Format Range Extensions".to_string(),
+        },
+        5 => "High Throughput".to_string(),
+        9 => "Screen Content Coding".to_string(),
+        n => format!("Profile {n}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 720x576 sequence header with `frame_rate_code`, optionally followed by a sequence
+    /// extension carrying frame_rate_extension_n/d
+    fn mpeg2_header(frame_rate_code: u8, extension: Option<(u8, u8)>) -> Vec<u8> {
+        let mut data = vec![0x00, 0x00, 0x01, 0xB3, 0x2D, 0x02, 0x40, 0x30 | frame_rate_code, 0xFF, 0xFF, 0xE0, 0xA0];
+        if let Some((n, d)) = extension {
+            data.extend_from_slice(&[0x00, 0x00, 0x01, 0xB5, 0x14, 0x8A, 0x00, 0x01, 0x00, (n << 5) | d]);
+        }
+        data
+    }
+
+    #[test]
+    fn mpeg2_ntsc_frame_rate() {
+        let info = parse_mpeg2_seq_hdr(&mpeg2_header(4, None)).unwrap();
+        assert_eq!((info.width, info.height), (720, 576));
+        assert!((info.fps - 30000.0 / 1001.0).abs() < 0.001, "{}", info.fps);
+    }
+
+    #[test]
+    fn mpeg2_frame_rate_extension_scales_the_base_rate() {
+        // 50 * (0 + 1) / (1 + 1)
+        let info = parse_mpeg2_seq_hdr(&mpeg2_header(6, Some((0, 1)))).unwrap();
+        assert_eq!(info.fps, 25.0);
+        // 24000/1001 * (1 + 1) / (0 + 1)
+        let info = parse_mpeg2_seq_hdr(&mpeg2_header(1, Some((1, 0)))).unwrap();
+        assert!((info.fps - 48000.0 / 1001.0).abs() < 0.001, "{}", info.fps);
+    }
+
+    /// HEVC SPS RBSP (after the NAL header) for 1920x1080 Main 4:2:0 with two short-term
+    /// RPS, the second predicted from the first, and a VUI signaling `full_range`
+    /// BT.709 when `vui` is set
+    fn hevc_sps(vui: Option<bool>) -> Vec<u8> {
+        use bitstream_io::{BitWrite, BitWriter};
+        let mut w = BitWriter::endian(Vec::new(), BigEndian);
+        let ue = |w: &mut BitWriter<Vec<u8>, BigEndian>, v: u32| {
+            let bits = 32 - (v + 1).leading_zeros();
+            w.write_var(bits - 1, 0u32).unwrap();
+            w.write_var(bits, v + 1).unwrap();
+        };
+        w.write::<8, u8>(0x01).unwrap(); // vps_id 0, max_sub_layers_minus1 0, temporal_id_nesting
+        w.write::<8, u8>(0x01).unwrap(); // Main tier, Main profile
+        w.write::<32, u32>(0x6000_0000).unwrap();
+        w.write::<16, u16>(0x9000).unwrap(); // progressive_source, frame_only_constraint
+        w.write::<32, u32>(0).unwrap();
+        w.write::<8, u8>(120).unwrap(); // level 4.0
+        for v in [0, 1, 1920, 1080] {
+            ue(&mut w, v); // sps_id, chroma_format_idc, width, height
+        }
+        w.write_bit(false).unwrap(); // conformance_window_flag
+        for v in [0, 0, 4] {
+            ue(&mut w, v); // bit depths, log2_max_pic_order_cnt_lsb_minus4
+        }
+        w.write_bit(true).unwrap(); // sub_layer_ordering_info_present_flag
+        for v in [4, 2, 0, 0, 3, 0, 3, 1, 1] {
+            ue(&mut w, v);
+        }
+        w.write::<4, u8>(0b0110).unwrap(); // no scaling list, amp, sao, no pcm
+        ue(&mut w, 2); // num_short_term_ref_pic_sets
+        ue(&mut w, 1); // set 0: one negative picture
+        ue(&mut w, 0);
+        ue(&mut w, 0);
+        w.write_bit(true).unwrap();
+        w.write_bit(true).unwrap(); // set 1: predicted from set 0
+        w.write_bit(false).unwrap();
+        ue(&mut w, 0);
+        w.write::<3, u8>(0b101).unwrap(); // used, not used but use_delta
+        w.write::<3, u8>(0b011).unwrap(); // no long-term refs, temporal MVP, strong intra smoothing
+        w.write_bit(vui.is_some()).unwrap();
+        if let Some(full_range) = vui {
+            w.write::<9, u16>(0b1_00000001).unwrap(); // SAR 1:1
+            w.write::<2, u8>(0b01).unwrap(); // no overscan info, video_signal_type
+            w.write::<3, u8>(5).unwrap(); // unspecified video_format
+            w.write_bit(full_range).unwrap();
+            w.write_bit(true).unwrap();
+            w.write::<24, u32>(0x01_01_01).unwrap(); // BT.709
+        }
+        w.write::<8, u8>(0x80).unwrap(); // rest of the VUI, not read
+        w.byte_align().unwrap();
+        w.into_writer()
+    }
+
+    #[test]
+    fn hevc_vui_video_signal_type() {
+        let info = parse_hevc_sps(&hevc_sps(Some(true))).expect("HEVC SPS");
+        assert_eq!((info.width, info.height, info.level), (1920, 1080, Some(4.0)));
+        assert_eq!(info.full_range, Some(true));
+        assert_eq!(info.colour_primaries.as_deref(), Some("BT.709"));
+        assert_eq!(info.matrix.as_deref(), Some("BT.709"));
+
+        assert_eq!(parse_hevc_sps(&hevc_sps(Some(false))).unwrap().full_range, Some(false));
+        let no_vui = parse_hevc_sps(&hevc_sps(None)).expect("HEVC SPS");
+        assert_eq!((no_vui.width, no_vui.full_range), (1920, None));
+    }
+
+    fn h264(width: u16, height: u16, fps: f32, interlaced: bool, level: f32) -> VideoInfo {
+        VideoInfo { codec: "H.264".into(), width, height, fps, interlaced, level: Some(level), ..VideoInfo::default() }
+    }
+
+    #[test]
+    fn level_limits_count_frames() {
+        assert!(!exceeds_level_limits(&h264(1920, 1080, 25.0, false, 4.0)));
+        assert!(exceeds_level_limits(&h264(3840, 2160, 25.0, false, 3.0)));
+        // 1080i25 at level 4.0, with the frame rate or the field rate as fps
+        assert!(!exceeds_level_limits(&h264(1920, 1080, 25.0, true, 4.0)));
+        assert!(!exceeds_level_limits(&h264(1920, 1080, 50.0, true, 4.0)));
+        // 1080p50 needs level 4.2
+        assert!(exceeds_level_limits(&h264(1920, 1080, 50.0, false, 4.0)));
+    }
+
+    #[test]
+    fn vvc_sps_on_stream_type_0x33() {
+        let sps = [
+            0x00, 0x00, 0x01, 0x00, 0x79, // start code, NAL header: SPS_NUT (15), TemporalId 0
+            // ids 0, 1 sublayer, 4:2:0, CTU 128, profile_tier_level: Main 10, level 5.1, no GCI
+            0x00, 0x0D, 0x02, 0x53, 0x80,
+            // no sub-profiles, no GDR/RPR, 1920x1080
+            0x00, 0x00, 0x0F, 0x02, 0x00, 0x43, 0x98,
+        ];
+        let info = crate::parsers::parse_video_codec(0x33, None, &sps).unwrap();
+        assert_eq!(info.codec, "VVC");
+        assert_eq!((info.width, info.height), (1920, 1080));
+        assert_eq!(info.chroma, "4:2:0");
+        assert_eq!(info.profile.as_deref(), Some("Main 10"));
+        assert_eq!(crate::types::stream_type_name(0x33), "H.266/VVC");
+    }
+}
@@ -1,11 +1,18 @@
 //! Main packet processing logic
 
 use std::collections::HashMap;
-use crate::types::{CodecInfo, SubtitleInfo, AnalysisMode, SiTableContext, PacketContext, CrcValidation};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use crate::types::{CodecHint, CodecInfo, EpgCoverage, ErrorPacket, ManualProgram, PidCollision, PidCollisionKind, StreamClass, StreamCountMismatch, StreamTemplate, SubtitleInfo, AnalysisMode, FpsSource, ProgramListener, SiTable, SiTableListener, PcrIntervalInfo, LastPcrInfo, TransportStats, ScramblingState, SiTableContext, PacketContext, CrcValidation};
 use crate::constants::*;
 use crate::stats::StatsManager;
-use crate::parsers::{parse_video_codec, parse_audio_codec};
-use crate::psi::{parse_pat, parse_pmt, parse_cat, parse_nit, parse_sdt, parse_eit_pf, parse_tdt_tot, PatSection, PmtSection};
+use crate::parsers::{ac3_descriptor_info, exceeds_level_limits, inspect_packet, is_video_codec, parse_video_codec, parse_audio_codec, parse_pes_header, audio_frame_length, audio_frame_ticks, PesStreamKind, AUDIO_FRAME_HEADER_LEN};
+use crate::psi::{parse_pat, parse_pmt, parse_cat, parse_nit, parse_sdt, parse_eit_pf, parse_eit_schedule, parse_tdt_tot, PatSection, PmtSection};
+use crate::psi::eit::EitPfSection;
+use crate::psi::pat::PatEntry;
+use crate::psi::tdt::TdtTot;
+use crate::psi::descriptor::{ac3_descriptor, component_tag, subtitle_pages, Component, Descriptor, FORMAT_ID_AV1};
+use crate::psi::section::{peek_section, SectionAccumulator, SectionAssembler};
 use crate::si_cache::SiCache;
 use crate::tr101::Tr101Metrics;
 
@@ -13,12 +20,44 @@ pub struct PacketProcessor {
     pub pat_map: HashMap<u16, PatSection>,
     pub pmt_map: HashMap<u16, PmtSection>,
     pub pcr_pid_map: HashMap<u16, u16>, // program_number -> pcr_pid
-    pub pat_versions: HashMap<u16, u8>, // program_number -> version
-    pub pmt_versions: HashMap<u16, u8>, // pmt_pid -> version
     pub stats_manager: StatsManager,
     pub si_cache: SiCache,
     pub tr101: Option<Tr101Metrics>,
     pub total_bytes_processed: u64, // Total bytes processed for PCR accuracy calculation
+    pub socket_drops: Option<u64>,  // Kernel UDP drops, refreshed by the socket loop before each report
+    pub dropped_datagrams: Option<u64>, // Messages a lagging broadcast receiver skipped (broadcast input only)
+    pub misaligned_datagrams: Option<u64>, // Datagrams not a whole number of packets (datagram input only)
+    pub transport: Option<TransportStats>, // Datagram arrival stats of the last completed window (socket input only)
+    pub program_filter: Option<Vec<u16>>, // Only analyse these programs (PSI is always parsed)
+    pub pid_filter: Option<Vec<u16>>,     // Only analyse these elementary PIDs
+    pub include_null_in_mux: bool,        // Null packets count towards the mux bitrate
+    pub program_listener: Option<Arc<dyn ProgramListener>>, // Told when the PAT adds/removes programs
+    pub si_table_listener: Option<Arc<dyn SiTableListener>>, // Told of every CRC-validated SI table
+    pub fps_source: FpsSource,            // Signaled vs PTS-derived fps precedence
+    pub codec_overrides: HashMap<u8, CodecHint>, // PMT stream_type → forced codec parser
+    pub expected_streams: HashMap<u16, StreamTemplate>, // program_number → expected stream lineup
+    declared_pids: HashMap<u16, Instant>, // elementary PID → first seen in a PMT
+    last_pcr: HashMap<u16, u64>,          // PCR PID → latest PCR base (90 kHz)
+    last_payloads: HashMap<u16, (u8, u64)>, // PID → CC and payload hash of the last payload packet
+    pcr_timing: HashMap<u16, PcrTiming>,  // PCR PID → PCR spacing statistics
+    pid_role_conflicts: std::collections::BTreeSet<u16>, // PSI PIDs also declared as elementary PIDs
+    eit_sections: SectionAssembler,       // PID 0x0012 sections spanning packets
+    epg: HashMap<u16, HashMap<u16, (i64, u32)>>, // service_id → event_id → (start, duration secs)
+    epg_sections: HashMap<u16, ScheduleSections>, // service_id → EIT-actual schedule sections received
+    stream_utc: Option<i64>,              // Latest TDT/TOT time; events ended before it leave the EPG
+    event_components: HashMap<u16, Vec<Component>>, // service_id → components of the present EIT event
+    pub error_packets: std::collections::VecDeque<ErrorPacket>, // Packets that raised Priority 1 errors
+    pub error_capture_limit: usize,       // Capacity of error_packets; 0 captures nothing
+    psi_changed: bool,                    // PAT/PMT (re)acquired or new version since take_event
+    p1_errors_seen: u16,                  // Bit per priority_1_counters entry that has left zero
+    error_onset: bool,                    // A priority_1_counters entry left zero since take_event
+    network_pid: u16,                     // NIT PID from the PAT's program 0 entry
+    pat_sections: SectionAccumulator<PatSection>,
+    pmt_sections: SectionAccumulator<PmtSection>,
+    leftover: Vec<u8>,  // Partial trailing packet carried into the next process_buffer call
+    misaligned_run: u64, // Consecutive misaligned datagrams, for the encapsulation warning
+    arrivals: Option<ArrivalWindow>, // Datagram arrivals of the current transport window
+    packet_size: usize, // 188, or 204 for streams carrying Reed-Solomon parity
 }
 
 impl PacketProcessor {
@@ -27,52 +66,246 @@ impl PacketProcessor {
             pat_map: HashMap::new(),
             pmt_map: HashMap::new(),
             pcr_pid_map: HashMap::new(),
-            pat_versions: HashMap::new(),
-            pmt_versions: HashMap::new(),
             stats_manager: StatsManager::new(),
             si_cache: SiCache::default(),
             tr101: if enable_tr101 { Some(Tr101Metrics::new()) } else { None },
             total_bytes_processed: 0,
+            socket_drops: None,
+            dropped_datagrams: None,
+            misaligned_datagrams: None,
+            transport: None,
+            program_filter: None,
+            pid_filter: None,
+            include_null_in_mux: false,
+            program_listener: None,
+            si_table_listener: None,
+            fps_source: FpsSource::default(),
+            codec_overrides: HashMap::new(),
+            expected_streams: HashMap::new(),
+            declared_pids: HashMap::new(),
+            last_pcr: HashMap::new(),
+            last_payloads: HashMap::new(),
+            pcr_timing: HashMap::new(),
+            pid_role_conflicts: Default::default(),
+            eit_sections: SectionAssembler::default(),
+            epg: HashMap::new(),
+            epg_sections: HashMap::new(),
+            stream_utc: None,
+            event_components: HashMap::new(),
+            error_packets: Default::default(),
+            error_capture_limit: 0,
+            psi_changed: false,
+            p1_errors_seen: 0,
+            error_onset: false,
+            network_pid: DEFAULT_NIT_PID,
+            pat_sections: SectionAccumulator::new(),
+            pmt_sections: SectionAccumulator::new(),
+            leftover: Vec::new(),
+            misaligned_run: 0,
+            arrivals: None,
+            packet_size: TS_PACKET_SIZE,
         }
     }
 
     pub fn set_analysis_mode(&mut self, mode: Option<AnalysisMode>) {
-        match mode {
-            Some(AnalysisMode::Tr101) | Some(AnalysisMode::Tr101Priority1) | Some(AnalysisMode::Tr101Priority12) => {
-                if self.tr101.is_none() {
-                    self.tr101 = Some(Tr101Metrics::new());
+        // Mux/None keep an existing tr101 instance (and all other state) but stop updating it
+        if mode.and_then(AnalysisMode::tr101_priority).is_some() && self.tr101.is_none() {
+            self.tr101 = Some(Tr101Metrics::new());
+        }
+    }
+
+    /// Process an arbitrary byte buffer: locks onto the sync byte, resyncs after
+    /// corruption, detects 188/204-byte packets and carries a partial trailing
+    /// packet over to the next call
+    pub fn process_buffer(&mut self, buf: &[u8], analysis_mode: Option<AnalysisMode>) {
+        let mut data = std::mem::take(&mut self.leftover);
+        let data: &[u8] = if data.is_empty() {
+            buf
+        } else {
+            data.extend_from_slice(buf);
+            &data
+        };
+
+        let mut pos = 0;
+        while pos + TS_PACKET_SIZE <= data.len() {
+            if data[pos] != TS_SYNC_BYTE {
+                // Not a packet: account the sync loss without parsing the misaligned bytes,
+                // then hunt for the next packet start
+                match find_sync(&data[pos + 1..]) {
+                    Some(offset) => {
+                        self.note_skipped_bytes(1 + offset, analysis_mode);
+                        pos += 1 + offset;
+                        self.packet_size = detect_packet_size(&data[pos..]).unwrap_or(self.packet_size);
+                        continue;
+                    }
+                    None => {
+                        self.note_skipped_bytes(data.len() - pos, analysis_mode);
+                        pos = data.len();
+                        break;
+                    }
                 }
             }
-            Some(AnalysisMode::Mux) => {
-                // Keep existing tr101 instance but don't use it actively
+            if data.get(pos + self.packet_size).is_some_and(|&b| b != TS_SYNC_BYTE) {
+                // Next sync isn't where the current stride expects it; try the other size
+                self.packet_size = detect_packet_size(&data[pos..]).unwrap_or(self.packet_size);
+            }
+            if pos + self.packet_size > data.len() && self.packet_size != TS_PACKET_SIZE {
+                break; // parity bytes of a 204-byte packet still to come
             }
-            Some(AnalysisMode::None) | None => {
-                // Keep all data structures but minimal processing
+            self.process_packet(&data[pos..pos + TS_PACKET_SIZE], analysis_mode);
+            pos += self.packet_size;
+        }
+        self.leftover = data[pos..].to_vec();
+        self.note_error_onsets();
+    }
+
+    /// Whether a payload packet repeats the previous one on its PID: the same continuity
+    /// counter and byte-identical payload (ISO/IEC 13818-1 §2.4.3.3). A duplicate may carry
+    /// a different PCR, so the adaptation field is not compared.
+    fn is_repeated_payload(&mut self, pid: u16, cc: u8, payload: &[u8]) -> bool {
+        use std::hash::{DefaultHasher, Hash, Hasher};
+
+        let mut hasher = DefaultHasher::new();
+        payload.hash(&mut hasher);
+        let packet = (cc, hasher.finish());
+        self.last_payloads.insert(pid, packet) == Some(packet)
+    }
+
+    /// Bytes skipped while hunting for sync stand for one missed sync byte per packet they
+    /// span (at least one), so a long run of garbage reads as a sync loss
+    fn note_skipped_bytes(&mut self, skipped: usize, analysis_mode: Option<AnalysisMode>) {
+        self.total_bytes_processed += skipped as u64;
+        let Some(tr101) = &mut self.tr101 else { return };
+        for _ in 0..(skipped / self.packet_size).max(1) {
+            tr101.check_ts_sync_loss(false, analysis_mode.unwrap_or(AnalysisMode::None));
+        }
+    }
+
+    /// Drop the partial packet carried over from the last buffer, for input that
+    /// resumes after a gap (e.g. analysis stopped and restarted)
+    pub fn discard_partial_packet(&mut self) {
+        self.leftover.clear();
+    }
+
+    /// Log each Priority 1 counter the first time it leaves zero, and flag the onset for
+    /// [`Self::take_event`]
+    fn note_error_onsets(&mut self) {
+        let Some(tr101) = &self.tr101 else { return };
+        for (i, (counter, count)) in tr101.priority_1_counters().into_iter().enumerate() {
+            if count > 0 && self.p1_errors_seen & (1 << i) == 0 {
+                tracing::warn!(counter, count, "first TR 101 290 Priority 1 error");
+                self.p1_errors_seen |= 1 << i;
+                self.error_onset = true;
+            }
+        }
+    }
+
+    /// Process one UDP datagram (or broadcast message) via [`Self::process_buffer`].
+    /// Sizes that aren't a whole number of packets are counted in `misaligned_datagrams`
+    /// (the partial packet is still reassembled with the next datagram); when that
+    /// persists the encapsulation (e.g. RTP) is probably not being stripped, so warn once.
+    pub fn process_datagram(&mut self, buf: &[u8], analysis_mode: Option<AnalysisMode>) {
+        let misaligned = self.misaligned_datagrams.get_or_insert(0);
+        if !buf.len().is_multiple_of(self.packet_size) {
+            *misaligned += 1;
+            self.misaligned_run += 1;
+            if self.misaligned_run == MISALIGNED_DATAGRAM_WARN_COUNT {
+                tracing::warn!(
+                    consecutive = MISALIGNED_DATAGRAM_WARN_COUNT,
+                    packet_size = self.packet_size,
+                    datagram_bytes = buf.len(),
+                    "datagrams are not a whole number of packets; is the encapsulation being stripped?"
+                );
             }
+        } else {
+            self.misaligned_run = 0;
         }
+        self.process_buffer(buf, analysis_mode);
     }
 
-    /// Process a single TS packet
+    /// Record that a datagram of `bytes` bytes was just received from the network, for the
+    /// `transport` arrival rate and jitter; a new window is published every TRANSPORT_WINDOW_SECONDS
+    pub fn record_arrival(&mut self, bytes: usize) {
+        let now = Instant::now();
+        let Some(window) = &mut self.arrivals else {
+            self.arrivals = Some(ArrivalWindow::starting_at(now));
+            return;
+        };
+        let delta_ms = now.duration_since(window.last).as_secs_f64() * 1000.0;
+        window.last = now;
+        window.datagrams += 1;
+        window.packets += (bytes / self.packet_size) as u64;
+        window.sum_ms += delta_ms;
+        window.sum_sq_ms += delta_ms * delta_ms;
+
+        let secs = now.duration_since(window.start).as_secs_f64();
+        if secs >= TRANSPORT_WINDOW_SECONDS {
+            let n = window.datagrams as f64;
+            let mean_ms = window.sum_ms / n;
+            self.transport = Some(TransportStats {
+                window_secs: secs,
+                datagrams_per_sec: n / secs,
+                packets_per_sec: window.packets as f64 / secs,
+                mean_interarrival_ms: mean_ms,
+                packet_jitter_ms: (window.sum_sq_ms / n - mean_ms * mean_ms).max(0.0).sqrt(),
+            });
+            *window = ArrivalWindow::starting_at(now);
+        }
+    }
+
+    /// Process a single TS packet; with `error_capture_limit` set, keep it if it raised
+    /// a Priority 1 error
     pub fn process_packet(&mut self, chunk: &[u8], analysis_mode: Option<AnalysisMode>) {
+        let Some(before) = self.tr101.as_ref().filter(|_| self.error_capture_limit > 0).map(|t| t.priority_1_counters()) else {
+            return self.analyse_packet(chunk, analysis_mode);
+        };
+        self.analyse_packet(chunk, analysis_mode);
+        let Some(tr101) = &self.tr101 else { return };
+        let raised = tr101.priority_1_counters().into_iter().zip(before).find(|((_, now), (_, was))| now > was);
+        if let Some(((error, _), _)) = raised && chunk.len() >= TS_PACKET_SIZE {
+            while self.error_packets.len() >= self.error_capture_limit {
+                self.error_packets.pop_front();
+            }
+            self.error_packets.push_back(ErrorPacket {
+                pid: (((chunk[1] & 0x1F) as u16) << 8) | chunk[2] as u16,
+                error,
+                captured_at: chrono::Utc::now().to_rfc3339(),
+                packet: chunk[..TS_PACKET_SIZE].to_vec(),
+            });
+        }
+    }
+
+    fn analyse_packet(&mut self, chunk: &[u8], analysis_mode: Option<AnalysisMode>) {
         // Check packet length
         if chunk.len() < TS_PACKET_SIZE {
             return; // Invalid packet
         }
+        self.total_bytes_processed += TS_PACKET_SIZE as u64;
 
-        // Check sync byte and detect sync loss
-        let sync_byte_valid = chunk[0] == TS_SYNC_BYTE;
+        // Decode the header; None means a bad sync byte
+        let header = inspect_packet(chunk);
         if let Some(ref mut tr101) = self.tr101 {
-            tr101.check_ts_sync_loss(sync_byte_valid, analysis_mode.unwrap_or(AnalysisMode::None));
+            tr101.check_ts_sync_loss(header.is_some(), analysis_mode.unwrap_or(AnalysisMode::None));
         }
-
-        if !sync_byte_valid {
+        let Some(header) = header else {
             return; // Invalid sync byte
-        }
+        };
+
+        let pid = header.pid;
+        let payload_unit_start = header.payload_unit_start;
+        let adaption_field_ctrl = header.adaptation_field_control;
 
-        let pid = (((chunk[1] & 0x1F) as u16) << 8) | (chunk[2] as u16);
-        let payload_unit_start = chunk[1] & 0x40 != 0;
-        let adaption_field_ctrl = (chunk[3] & 0x30) >> 4;
-        let mut payload_offset = 4usize;
+        self.stats_manager.record_packet(pid, adaption_field_ctrl, header.transport_priority, header.scrambling_control);
+
+        // adaptation_field_length must be 183 without payload and at most 182 with one
+        let adaptation_len_valid = match adaption_field_ctrl {
+            2 => chunk[4] == 183,
+            3 => chunk[4] <= 182,
+            _ => true,
+        };
+        if !adaptation_len_valid {
+            self.stats_manager.record_adaptation_field_error(pid);
+        }
 
         // Check for PID errors (unexpected/undeclared PIDs)
         if let Some(ref mut tr101) = self.tr101 {
@@ -85,105 +318,108 @@ impl PacketProcessor {
             ..Default::default()
         };
 
-        // Skip packets with no payload or adaptation field only
-        if adaption_field_ctrl == 2 || adaption_field_ctrl == 0 {
-            return;
-        }
+        let is_pcr_pid = self.pcr_pid_map.values().any(|&pcr_pid| pcr_pid == pid);
 
-        // Handle adaptation field
-        if adaption_field_ctrl == 3 {
-            let adap_len = chunk[4] as usize;
-            payload_offset += 1 + adap_len;
-            if payload_offset >= 188 {
-                return;
-            }
+        // Keep the latest PCR for PTS/PCR delay, including adaptation-only packets
+        if is_pcr_pid && adaption_field_ctrl & 0x02 != 0 && let Some((base, ext)) = header.pcr {
+            self.last_pcr.insert(pid, base);
+            let discontinuity = header.discontinuity_indicator;
+            self.record_pcr_interval(pid, base * 300 + ext as u64, discontinuity);
+            self.stats_manager.record_pcr(pid, base * 300 + ext as u64, discontinuity, self.total_bytes_processed);
         }
 
-        // Extract PCR if present and this PID is a designated PCR PID
-        let mut pcr_found: Option<(u64, u16)> = None;
-        let is_pcr_pid = self.pcr_pid_map.values().any(|&pcr_pid| pcr_pid == pid);
+        // The PCR is only passed on for a designated PCR PID, with or without a payload;
+        // the discontinuity_indicator also covers the continuity counter of any PID
+        let pcr_found = if adaption_field_ctrl & 0x02 != 0 && is_pcr_pid { header.pcr } else { None };
+        let discontinuity_indicator = header.discontinuity_indicator;
 
-        if adaption_field_ctrl & 0x02 != 0 && payload_offset > 4 && is_pcr_pid {
-            let ad_len = chunk[4] as usize;
-            if ad_len >= 7 && chunk[5] & 0x10 != 0 { // PCR_flag
-                let p = &chunk[6..12];
-                let base = ((p[0] as u64) << 25)
-                        | ((p[1] as u64) << 17)
-                        | ((p[2] as u64) << 9)
-                        | ((p[3] as u64) << 1)
-                        | ((p[4] as u64) >> 7);
-                let ext = (((p[4] & 0x01) as u16) << 8) | (p[5] as u16);
-                pcr_found = Some((base, ext));
-            }
-        }
+        // Packets with no payload (adaptation field only, or one filling the packet)
+        // skip the SI and elementary stream parsing but still go through TR-101
+        let payload = header.payload_offset.map(|offset| &chunk[offset..]);
 
-        let payload = &chunk[payload_offset..];
+        // A duplicate packet carries no new data, so it skips the elementary stream
+        // tracking; TR-101 still sees it to tell a legal duplicate from a CC error
+        let repeated_payload = pid != NULL_PID
+            && payload.is_some_and(|payload| self.is_repeated_payload(pid, chunk[3] & 0x0F, payload));
 
-        // Only process SI tables if in analysis mode (any TR-101 level or Mux)
-        if matches!(analysis_mode, Some(AnalysisMode::Mux) | Some(AnalysisMode::Tr101) | Some(AnalysisMode::Tr101Priority1) | Some(AnalysisMode::Tr101Priority12)) {
+        // SI tables and codec detection run in every mode but None, TR-101 ones included
+        if analysis_mode.is_some_and(AnalysisMode::detects_codecs) && let Some(payload) = payload {
             self.process_si_tables(pid, payload_unit_start, payload, &mut si_context, analysis_mode);
-            self.process_elementary_streams(pid, payload_unit_start, payload, analysis_mode);
+            // A PSI PID never doubles as an elementary PID, even if a PMT says so
+            if self.is_psi_pid(pid) {
+                if self.pmt_stream(pid).is_some() && self.pid_role_conflicts.insert(pid) {
+                    tracing::warn!(pid, "PID is declared as an elementary stream but carries PSI; treating it as PSI");
+                }
+            } else if self.pid_selected(pid) && !repeated_payload {
+                self.process_elementary_streams(pid, payload_unit_start, payload, analysis_mode);
+            }
+        }
+
+        // Filtered-out PIDs skip the per-packet TR-101 checks as well
+        if !self.pid_selected(pid) {
+            return;
         }
 
         // TR-101 analysis if enabled
-        if matches!(analysis_mode, Some(AnalysisMode::Tr101) | Some(AnalysisMode::Tr101Priority1) | Some(AnalysisMode::Tr101Priority12)) {
-            if let Some(ref mut tr101) = self.tr101 {
-                // Check for service ID mismatch - Priority 3
-                if matches!(analysis_mode, Some(AnalysisMode::Tr101)) && self.si_cache.check_service_id_mismatch() {
-                    tr101.service_id_mismatch += 1;
-                }
+        if analysis_mode.and_then(AnalysisMode::tr101_priority).is_some()
+            && let Some(ref mut tr101) = self.tr101
+        {
+            // Check for service ID mismatch - Priority 3
+            if analysis_mode.is_some_and(|m| m.checks_priority(3)) && self.si_cache.check_service_id_mismatch() {
+                tr101.service_id_mismatch += 1;
+            }
 
-                // Handle splice_countdown in adaptation field - Priority 3
-                if matches!(analysis_mode, Some(AnalysisMode::Tr101)) && adaption_field_ctrl & 0x02 != 0 && payload_offset > 4 {
-                    let ad_len = chunk[4] as usize;
-                    if ad_len >= 1 {
-                        let flags = chunk[5];
-                        if flags & 0x04 != 0 {
-                            // splice_countdown present
-                            let sc_pos = 6 + ad_len - 1;
-                            if sc_pos < chunk.len() {
-                                let val = chunk[sc_pos] as i8;
-                                match tr101.last_splice_value {
-                                    None => tr101.last_splice_value = Some(val),
-                                    Some(prev) => {
-                                        // Legal: same value, decrement by 1, or wrap -1→0
-                                        if !(val == prev || val == prev - 1 || (prev == -1 && val == 0)) {
-                                            tr101.splice_count_errors += 1;
-                                        }
-                                        tr101.last_splice_value = Some(val);
+            // Handle splice_countdown in adaptation field - Priority 3
+            if analysis_mode.is_some_and(|m| m.checks_priority(3)) && adaption_field_ctrl & 0x02 != 0 {
+                let ad_len = chunk[4] as usize;
+                if ad_len >= 1 {
+                    let flags = chunk[5];
+                    if flags & 0x04 != 0 {
+                        // splice_countdown present
+                        let sc_pos = 6 + ad_len - 1;
+                        if sc_pos < chunk.len() {
+                            let val = chunk[sc_pos] as i8;
+                            match tr101.last_splice_value {
+                                None => tr101.last_splice_value = Some(val),
+                                Some(prev) => {
+                                    // Legal: same value, decrement by 1, or wrap -1→0
+                                    if !(val == prev || val == prev.wrapping_sub(1) || (prev == -1 && val == 0)) {
+                                        tr101.splice_count_errors += 1;
                                     }
+                                    tr101.last_splice_value = Some(val);
                                 }
                             }
                         }
                     }
                 }
-
-                // Update total bytes processed
-                self.total_bytes_processed += chunk.len() as u64;
-
-                // Call optimized TR-101 packet handler
-                let packet_ctx = PacketContext {
-                    chunk,
-                    pid,
-                    payload_unit_start,
-                    pat_pid: 0x0000,
-                    pcr_opt: pcr_found,
-                    table_id: si_context.table_id,
-                    priority_level: analysis_mode.unwrap_or(AnalysisMode::None),
-                    total_bytes_processed: self.total_bytes_processed,
-                };
-
-                let crc_validation = CrcValidation {
-                    pat_crc_ok: si_context.pat_crc_ok,
-                    pmt_crc_ok: si_context.pmt_crc_ok,
-                    cat_crc_ok: si_context.cat_crc_ok,
-                    nit_crc_ok: si_context.nit_crc_ok,
-                    sdt_crc_ok: si_context.sdt_crc_ok,
-                    eit_crc_ok: si_context.eit_crc_ok,
-                };
-
-                tr101.on_packet_with_context(packet_ctx, crc_validation);
             }
+
+            // Call optimized TR-101 packet handler
+            let packet_ctx = PacketContext {
+                chunk,
+                pid,
+                payload_unit_start,
+                pat_pid: 0x0000,
+                nit_pid: self.network_pid,
+                pcr_opt: pcr_found,
+                discontinuity_indicator,
+                repeated_payload,
+                table_id: si_context.table_id,
+                priority_level: analysis_mode.unwrap_or(AnalysisMode::None),
+                total_bytes_processed: self.total_bytes_processed,
+            };
+
+            let crc_validation = CrcValidation {
+                pat_crc_ok: si_context.pat_crc_ok,
+                pmt_crc_ok: si_context.pmt_crc_ok,
+                cat_crc_ok: si_context.cat_crc_ok,
+                nit_crc_ok: si_context.nit_crc_ok,
+                sdt_crc_ok: si_context.sdt_crc_ok,
+                eit_crc_ok: si_context.eit_crc_ok,
+                tdt_crc_ok: si_context.tdt_crc_ok,
+            };
+
+            tr101.on_packet_with_context(packet_ctx, crc_validation);
         }
     }
 
@@ -195,23 +431,72 @@ impl PacketProcessor {
         context: &mut SiTableContext,
         analysis_mode: Option<AnalysisMode>,
     ) {
+        // A PUSI whose pointer_field lands on stuffing (or past the payload) starts no
+        // section; that is not a CRC error of the single-packet tables below
+        let section_start = payload_unit_start && peek_section(payload).is_some();
+
         // PAT (PID 0x0000)
-        if pid == 0x0000 && payload_unit_start {
+        if pid == 0x0000 && section_start {
             match parse_pat(payload) {
-                Ok(pat) => {
+                Ok(section) => {
                     context.pat_crc_ok = Some(true);
 
-                    // Check for PAT version changes (Priority 2)
-                    if let Some(ref mut tr101) = self.tr101 {
+                    let key = (pid, 0x00, section.transport_stream_id);
+                    let (version, number, last) = (section.version, section.section_number, section.last_section);
+                    if let Some(sections) = self.pat_sections.push(key, version, number, last, section) {
+                        let pat = PatSection::merge(sections);
+                        self.notify_si_table(pid, 0x00, Some(pat.version), SiTable::Pat(&pat));
+
+                        // Check for PAT version changes (Priority 2)
+                        if let Some(ref mut tr101) = self.tr101 {
+                            for entry in &pat.programs {
+                                tr101.check_pat_version_change(entry.program_number, pat.version, analysis_mode.unwrap_or(AnalysisMode::None));
+                            }
+                        }
+
+                        self.network_pid = pat.network_pid.unwrap_or(DEFAULT_NIT_PID);
+
+                        // Program membership changes: drop programs the PAT no longer lists
+                        // (manually declared programs are kept as configured)
+                        let mut removed: Vec<u16> = self.pat_map.keys()
+                            .copied()
+                            .filter(|pn| !pat.programs.iter().any(|e| e.program_number == *pn))
+                            .filter(|pn| !self.is_manual_program(*pn))
+                            .collect();
+                        let mut added: Vec<u16> = pat.programs.iter()
+                            .map(|e| e.program_number)
+                            .filter(|pn| !self.pat_map.contains_key(pn))
+                            .collect();
+                        removed.sort_unstable();
+                        added.sort_unstable();
+                        for pn in &removed {
+                            self.remove_program(*pn, &pat);
+                        }
+
+                        // Store PAT efficiently - avoid multiple clones
+                        match self.si_cache.pat.as_ref().map(|old| old.version) {
+                            None => tracing::info!(version = pat.version, programs = pat.programs.len(), "PAT acquired"),
+                            Some(old) if old != pat.version => {
+                                tracing::info!(old_version = old, new_version = pat.version, "PAT version changed")
+                            }
+                            Some(_) => {}
+                        }
+                        self.psi_changed |= self.si_cache.pat.as_ref().is_none_or(|old| old.version != pat.version);
+                        self.si_cache.update_pat(pat.clone());
                         for entry in &pat.programs {
-                            tr101.check_pat_version_change(entry.program_number, pat.version, analysis_mode.unwrap_or(AnalysisMode::None));
+                            if !self.is_manual_program(entry.program_number) {
+                                self.pat_map.insert(entry.program_number, pat.clone());
+                            }
                         }
-                    }
 
-                    // Store PAT efficiently - avoid multiple clones
-                    self.si_cache.update_pat(pat.clone());
-                    for entry in &pat.programs {
-                        self.pat_map.insert(entry.program_number, pat.clone());
+                        if let Some(listener) = &self.program_listener {
+                            for pn in removed {
+                                listener.on_program_removed(pn);
+                            }
+                            for pn in added {
+                                listener.on_program_added(pn);
+                            }
+                        }
                     }
                 }
                 Err(_) => { context.pat_crc_ok = Some(false); }
@@ -219,23 +504,28 @@ impl PacketProcessor {
         }
 
         // CAT (PID 0x0001)
-        if pid == 0x0001 && payload_unit_start {
+        if pid == 0x0001 && section_start {
             match parse_cat(payload) {
-                Ok((_table_id, _cat)) => {
+                Ok((table_id, cat)) => {
                     context.cat_crc_ok = Some(true);
-                    context.table_id = _table_id;
+                    context.table_id = table_id;
+                    self.notify_si_table(pid, table_id, Some(cat.version), SiTable::Cat(&cat));
                 }
                 Err(_) => { context.cat_crc_ok = Some(false); }
             }
         }
 
-        // NIT (PID 0x0010)
-        if pid == 0x0010 && payload_unit_start {
+        // NIT (PID 0x0010 unless the PAT declares another network_PID)
+        if pid == self.network_pid && section_start {
             match parse_nit(payload) {
                 Ok((tid, nit)) => {
                     context.nit_crc_ok = Some(true);
                     context.table_id = tid;
-                    self.si_cache.update_nit(nit);
+                    self.notify_si_table(pid, tid, Some(nit.version), SiTable::Nit(&nit));
+                    if tid == 0x40 {
+                        // Only NIT-actual describes the network this TS belongs to
+                        self.si_cache.update_nit(nit);
+                    }
                 }
                 Err(_) => {
                     context.nit_crc_ok = Some(false);
@@ -244,13 +534,18 @@ impl PacketProcessor {
         }
 
         // PMT
-        if let Some((_prog_num, _pat)) =
-            self.pat_map.iter().find(|(_, p)| p.programs.iter().any(|e| e.pmt_pid == pid))
-        {
-            if payload_unit_start {
-                match parse_pmt(payload) {
-                    Ok(pmt) => {
-                        context.pmt_crc_ok = Some(true);
+        if section_start && self.pat_map.values().any(|p| p.programs.iter().any(|e| e.pmt_pid == pid)) {
+            match parse_pmt(payload) {
+                Ok(section) => {
+                    context.pmt_crc_ok = Some(true);
+
+                    // A program declared in Options::manual_programs keeps that declaration
+                    let manual = self.is_manual_program(section.program_number);
+                    let key = (pid, 0x02, section.program_number);
+                    let (version, number, last) = (section.version, section.section_number, section.last_section);
+                    if !manual && let Some(sections) = self.pmt_sections.push(key, version, number, last, section) {
+                        let pmt = PmtSection::merge(sections);
+                        self.notify_si_table(pid, 0x02, Some(pmt.version), SiTable::Pmt(&pmt));
 
                         // Check for PMT version changes (Priority 2)
                         if let Some(ref mut tr101) = self.tr101 {
@@ -264,103 +559,412 @@ impl PacketProcessor {
                         }
 
                         // Extract and store PCR PID for this program
-                        if let Some((_prog_num, _pat)) = self.pat_map.iter().find(|(_, p)| p.programs.iter().any(|e| e.pmt_pid == pid)) {
-                            if let Some(pat_entry) = _pat.programs.iter().find(|e| e.pmt_pid == pid) {
-                                self.pcr_pid_map.insert(pat_entry.program_number, pmt.pcr_pid);
-                            }
+                        if let Some(pat_entry) = self.pat_map.values().flat_map(|p| &p.programs).find(|e| e.pmt_pid == pid) {
+                            self.pcr_pid_map.insert(pat_entry.program_number, pmt.pcr_pid);
                         }
 
+                        let now = Instant::now();
+                        for stream in &pmt.streams {
+                            self.declared_pids.entry(stream.elementary_pid).or_insert(now);
+                        }
+
+                        match self.pmt_map.get(&pid).map(|old| old.version) {
+                            None => tracing::info!(
+                                pmt_pid = pid, program_number = pmt.program_number, version = pmt.version,
+                                streams = pmt.streams.len(), "PMT acquired"
+                            ),
+                            Some(old) if old != pmt.version => tracing::info!(
+                                pmt_pid = pid, program_number = pmt.program_number,
+                                old_version = old, new_version = pmt.version, "PMT version changed"
+                            ),
+                            Some(_) => {}
+                        }
+                        self.psi_changed |= self.pmt_map.get(&pid).is_none_or(|old| old.version != pmt.version);
                         self.si_cache.update_pmt(pid, pmt.clone());
                         self.pmt_map.insert(pid, pmt.clone());
                     }
-                    Err(_) => { context.pmt_crc_ok = Some(false); }
                 }
+                Err(_) => { context.pmt_crc_ok = Some(false); }
             }
         }
 
-        // SDT/EIT (PID 0x0011)
-        if pid == 0x0011 && payload_unit_start {
-            let mut handled = false;
-            if context.sdt_crc_ok.is_none() {
-                if let Ok((tid, sdt)) = parse_sdt(payload) {
-                    context.sdt_crc_ok = Some(true);
-                    context.table_id = tid;
-                    self.si_cache.update_sdt(sdt);
-                    handled = true;
-                }
+        // SDT/EIT (PID 0x0011): dispatch on table_id so a CRC failure is charged to the right table
+        if pid == 0x0011 && payload_unit_start && let Some((table_id, complete)) = peek_section(payload) {
+            context.table_id = table_id; // also on failure, so TR-101 charges the right counter
+            match table_id {
+                0x42 | 0x46 => match parse_sdt(payload) {
+                    Ok((tid, sdt)) => {
+                        context.sdt_crc_ok = Some(true);
+                        self.notify_si_table(pid, tid, Some(sdt.version), SiTable::Sdt(&sdt));
+                        if tid == 0x42 {
+                            // Only SDT-actual describes the services in this TS
+                            let left: Vec<u16> = self.si_cache.sdt.iter()
+                                .flat_map(|old| &old.services)
+                                .map(|s| s.service_id)
+                                .filter(|id| !sdt.services.iter().any(|s| s.service_id == *id))
+                                .filter(|id| !self.pat_map.contains_key(id))
+                                .collect();
+                            for service_id in left {
+                                self.forget_epg(service_id);
+                            }
+                            self.si_cache.update_sdt(sdt);
+                        }
+                    }
+                    Err(_) if complete => { context.sdt_crc_ok = Some(false); }
+                    Err(_) => { /* section continues in following packets */ }
+                },
+                0x4E | 0x4F => match parse_eit_pf(payload) {
+                    Ok((tid, pf)) => {
+                        context.eit_crc_ok = Some(true);
+                        self.notify_si_table(pid, tid, Some(pf.version), SiTable::EitPf(&pf));
+                        if tid == 0x4E {
+                            self.update_present_event(pf);
+                        }
+                    }
+                    Err(_) if complete => { context.eit_crc_ok = Some(false); }
+                    Err(_) => { /* section continues in following packets */ }
+                },
+                _ => { /* BAT, EIT schedule: not parsed */ }
             }
+        }
 
-            if !handled {
-                match parse_eit_pf(payload) {
-                    Ok((tid, _eit)) => {
-                        context.eit_crc_ok = Some(true);
-                        context.table_id = tid;
+        // EIT (PID 0x0012): sections span many packets, so reassemble first.
+        // Only EIT-actual (p/f 0x4E, schedule 0x50-0x5F) describes services of this TS.
+        // p/f sections report to TR-101 like those on 0x0011; a bad one in the packet wins.
+        if pid == 0x0012 {
+            for section in self.eit_sections.push(payload_unit_start, payload) {
+                let Some((table_id, _)) = peek_section(&section) else { continue };
+                match table_id {
+                    0x4E | 0x4F => {
+                        context.table_id = table_id;
+                        let crc_ok = match parse_eit_pf(&section) {
+                            Ok((tid, pf)) => {
+                                self.notify_si_table(pid, tid, Some(pf.version), SiTable::EitPf(&pf));
+                                if tid == 0x4E {
+                                    self.update_present_event(pf);
+                                }
+                                true
+                            }
+                            Err(_) => false,
+                        };
+                        context.eit_crc_ok = Some(crc_ok && context.eit_crc_ok != Some(false));
+                    }
+                    _ => {
+                        let Ok(eit) = parse_eit_schedule(&section) else { continue };
+                        self.notify_si_table(pid, eit.table_id, Some(eit.version), SiTable::EitSchedule(&eit));
+                        if eit.table_id <= 0x5F {
+                            self.epg_sections.entry(eit.service_id).or_default().record(&eit);
+                            let events = self.epg.entry(eit.service_id).or_default();
+                            for event in eit.events {
+                                if let Some(start) = event.start
+                                    && self.stream_utc.is_none_or(|now| start + event.duration_secs as i64 > now)
+                                {
+                                    events.insert(event.event_id, (start, event.duration_secs));
+                                }
+                            }
+                        }
                     }
-                    Err(_) => { /* may be TOT/TDT or CRC error → ignore */ }
                 }
             }
         }
 
-        // TDT/TOT (PID 0x0014)
-        if pid == 0x0014 && payload_unit_start {
+        // TDT/TOT (PID 0x0014): TDT (0x70) has no CRC, so only TOT (0x73) reports tdt_crc_ok
+        if pid == 0x0014 && payload_unit_start && let Some((table_id, complete)) = peek_section(payload) {
+            context.table_id = table_id;
             match parse_tdt_tot(payload) {
-                Ok((tid, _tdt_tot)) => {
-                    context.table_id = tid;
-                    // TDT (0x70) has no CRC, TOT (0x73) has CRC
+                Ok((tid, time)) => {
                     if tid == 0x73 {
-                        context.tdt_crc_ok = Some(true);  // TOT CRC was validated successfully
+                        context.tdt_crc_ok = Some(true);
+                    }
+                    if let Some(now) = time.utc() {
+                        self.stream_utc = Some(now);
+                        self.prune_ended_events(now);
+                    }
+                    if let Some(listener) = &self.si_table_listener {
+                        let table = match time {
+                            TdtTot::Tdt(_) => SiTable::Tdt { utc: time.utc() },
+                            TdtTot::Tot(_) => SiTable::Tot { utc: time.utc(), descriptors: &time.descriptors() },
+                        };
+                        listener.on_table(pid, tid, None, &table);
                     }
-                    // For TDT, we don't set tdt_crc_ok since it has no CRC
-                }
-                Err(_) => {
-                    // If it's a TOT (should have CRC), mark as CRC error
-                    // We can't easily determine if it was supposed to be TOT vs TDT here,
-                    // so we conservatively assume CRC error only if parse failed
-                    context.tdt_crc_ok = Some(false);
                 }
+                Err(_) if complete && table_id == 0x73 => { context.tdt_crc_ok = Some(false); }
+                Err(_) => { /* not TDT/TOT, or section continues in following packets */ }
             }
         }
     }
 
+    /// Hand a validated table to the SI table listener, if one is set
+    fn notify_si_table(&self, pid: u16, table_id: u8, version: Option<u8>, table: SiTable<'_>) {
+        if let Some(listener) = &self.si_table_listener {
+            listener.on_table(pid, table_id, version, &table);
+        }
+    }
+
     fn process_elementary_streams(&mut self, pid: u16, payload_unit_start: bool, payload: &[u8], analysis_mode: Option<AnalysisMode>) {
         // Update byte counts for existing streams
         if self.stats_manager.contains_pid(pid) {
             self.stats_manager.update_bytes(pid, TS_PACKET_SIZE);
-            self.parse_codec_info(pid, payload_unit_start, payload, analysis_mode);
+            self.track_pes_timestamps(pid, payload_unit_start, payload);
+            self.track_pes_length(pid, payload_unit_start, payload);
+            self.track_gop(pid, payload_unit_start, payload);
+            self.track_frames(pid, payload_unit_start, payload);
+            self.parse_codec_info(pid, payload_unit_start, payload);
+            // PTS samples (fps estimate, PTS errors) are taken on every PES, not only
+            // until the codec is known
+            self.calculate_fps_from_pts(pid, payload_unit_start, payload, analysis_mode);
+            if let Some(CodecInfo::Video(v)) = self.stats_manager.get_mut(pid).and_then(|s| s.codec.as_mut()) {
+                v.level_resolution_mismatch = exceeds_level_limits(v);
+            }
         } else if payload_unit_start {
             // Check if this PID is an elementary stream from any PMT
-            if let Some((_, pmt)) = self.pmt_map
-                .iter()
-                .find(|(_, p)| p.streams.iter().any(|s| s.elementary_pid == pid))
+            if let Some(stream) = self.pmt_map
+                .values()
+                .flat_map(|p| &p.streams)
+                .find(|s| s.elementary_pid == pid)
             {
-                if let Some(stream) = pmt
-                    .streams
-                    .iter()
-                    .find(|s| s.elementary_pid == pid)
-                {
+                self.stats_manager.add_stream(pid, stream.stream_type);
+                self.stats_manager.update_bytes(pid, TS_PACKET_SIZE);
+            }
+        }
+    }
 
-                    self.stats_manager.add_stream(pid, stream.stream_type);
-                    self.stats_manager.update_bytes(pid, TS_PACKET_SIZE);
+    /// Record PTS/DTS presence for audio (0xC0-0xDF), video (0xE0-0xEF),
+    /// private_stream_1 (0xBD) and extended (0xFD) PES headers, which are all
+    /// expected to be timestamped
+    fn track_pes_timestamps(&mut self, pid: u16, payload_unit_start: bool, payload: &[u8]) {
+        if !payload_unit_start {
+            return;
+        }
+        let Some(pes) = parse_pes_header(payload) else { return };
+        if !pes.kind.is_elementary() {
+            return;
+        }
+        let (pts, dts) = (pes.pts, pes.dts);
+
+        // PTS relative to the latest PCR of the program carrying this PID
+        let pts_pcr_delay = pts.and_then(|pts| {
+            let pcr_pid = self
+                .pmt_map
+                .values()
+                .find(|pmt| pmt.streams.iter().any(|s| s.elementary_pid == pid))?
+                .pcr_pid;
+            let pcr = *self.last_pcr.get(&pcr_pid)?;
+            // Signed distance on the 33-bit clock
+            let delta = pts.wrapping_sub(pcr) & (PTS_WRAP_THRESHOLD - 1);
+            Some(if delta >= PTS_WRAP_THRESHOLD / 2 { delta as i64 - PTS_WRAP_THRESHOLD as i64 } else { delta as i64 })
+        });
+
+        if let Some(stats) = self.stats_manager.get_mut(pid) {
+            stats.pes_timed += 1;
+            if let Some(delay) = pts_pcr_delay {
+                stats.last_pts_pcr_delay = Some(delay);
+                if !(0..=MAX_PTS_PCR_DELAY_TICKS).contains(&delay) {
+                    stats.pts_pcr_delay_errors += 1;
                 }
             }
+            if pts.is_some() {
+                stats.pes_with_pts += 1;
+            }
+            if let (Some(pts), Some(dts)) = (pts, dts) {
+                stats.pes_with_dts += 1;
+                // PTS >= DTS always; mask handles the 33-bit wrap
+                stats.last_pts_dts_delta = Some(pts.wrapping_sub(dts) & (PTS_WRAP_THRESHOLD - 1));
+            }
+        }
+    }
+
+    /// Compare each bounded PES's declared PES_packet_length with the payload bytes
+    /// that arrived before the next PUSI. Lost packets show up here as well.
+    fn track_pes_length(&mut self, pid: u16, payload_unit_start: bool, payload: &[u8]) {
+        let Some(stats) = self.stats_manager.get_mut(pid) else { return };
+        if !payload_unit_start {
+            stats.pes_bytes += payload.len();
+            return;
+        }
+        if stats.pes_expected_bytes.is_some_and(|expected| expected != stats.pes_bytes) {
+            stats.pes_length_errors += 1;
         }
+        stats.pes_expected_bytes = parse_pes_header(payload)
+            .filter(|pes| pes.packet_length != 0)
+            .map(|pes| 6 + pes.packet_length as usize);
+        stats.pes_bytes = payload.len();
     }
 
-    fn parse_codec_info(&mut self, pid: u16, payload_unit_start: bool, payload: &[u8], analysis_mode: Option<AnalysisMode>) {
+    /// Count access units between IDR frames for H.264/HEVC. Each PES header starts
+    /// a new access unit; IDR slices are found by scanning NAL start codes in the payload.
+    fn track_gop(&mut self, pid: u16, payload_unit_start: bool, payload: &[u8]) {
+        let Some(stats) = self.stats_manager.get_mut(pid) else { return };
+        let is_idr: fn(&[u8]) -> bool = match parser_stream_type(&self.codec_overrides, stats.stream_type) {
+            0x1B => |nal| nal[0] & 0x1F == 5,
+            0x24 => |nal| matches!((nal[0] >> 1) & 0x3F, 19 | 20),
+            _ => return,
+        };
+
+        let mut es = payload;
+        if payload_unit_start
+            && let Some(pes) = parse_pes_header(payload)
+            && pes.kind.is_elementary()
+        {
+            stats.au_pts = pes.pts;
+            stats.au_has_idr = false;
+            if let Some(count) = stats.aus_since_idr.as_mut() {
+                *count += 1;
+            }
+            es = payload.get(pes.es_offset..).unwrap_or(&[]);
+        }
+
+        if !stats.au_has_idr && es.windows(4).any(|w| w[..3] == PES_START_CODE && is_idr(&w[3..])) {
+            stats.au_has_idr = true;
+            let gop_length = stats.aus_since_idr.replace(0);
+            let interval = match (stats.last_idr_pts, stats.au_pts) {
+                (Some(prev), Some(cur)) => {
+                    let ticks = cur.wrapping_sub(prev) & (PTS_WRAP_THRESHOLD - 1);
+                    Some(ticks as f64 / PTS_CLOCK_HZ as f64)
+                }
+                _ => None,
+            };
+            stats.last_idr_pts = stats.au_pts;
+            stats.last_idr_seen = Instant::now();
+            if let Some(CodecInfo::Video(v)) = stats.codec.as_mut() {
+                v.gop_length = gop_length.or(v.gop_length);
+                v.keyframe_interval_secs = interval.or(v.keyframe_interval_secs);
+                v.keyframe_overdue = false;
+            }
+        } else if let Some(CodecInfo::Video(v)) = stats.codec.as_mut() {
+            v.keyframe_overdue =
+                stats.last_idr_seen.elapsed() > Duration::from_secs(KEYFRAME_TIMEOUT_SECONDS);
+        }
+    }
+
+    /// Count delivered frames: one access unit per video PES, and audio sync frames
+    /// found by walking frame lengths from the start of each PES. measured_fps is the
+    /// frame count over FRAME_RATE_WINDOW_TICKS of DTS (or PTS) time.
+    /// For audio, the PTS advance across whole PES is also compared with the nominal
+    /// duration of the frames walked in between, giving audio_drift_ppm.
+    fn track_frames(&mut self, pid: u16, payload_unit_start: bool, payload: &[u8]) {
+        let Some(stats) = self.stats_manager.get_mut(pid) else { return };
+        let stream_type = parser_stream_type(&self.codec_overrides, stats.stream_type);
+        let mut es = payload;
+
+        if payload_unit_start
+            && let Some(pes) = parse_pes_header(payload)
+            && pes.kind.is_elementary()
+        {
+            // The previous PES ends here
+            if let Some((_, frames)) = stats.frame_window.as_mut() {
+                *frames += stats.pes_frames;
+            }
+            if let Some(ts) = pes.dts.or(pes.pts) {
+                match stats.frame_window {
+                    Some((start, frames)) => {
+                        let span = ts.wrapping_sub(start) & (PTS_WRAP_THRESHOLD - 1);
+                        if span == 0 || span > MAX_PTS_JUMP {
+                            stats.frame_window = Some((ts, 0)); // discontinuity: start over
+                        } else if span >= FRAME_RATE_WINDOW_TICKS {
+                            stats.measured_fps = Some(frames as f64 * PTS_CLOCK_HZ as f64 / span as f64);
+                            stats.frame_window = Some((ts, 0));
+                        }
+                    }
+                    None => stats.frame_window = Some((ts, 0)),
+                }
+            }
+
+            // Frames before this PES only count towards drift if they were walked to its end.
+            // A PES without PTS keeps its frames for the next PTS to time.
+            let frames_whole = stats.audio_synced && stats.audio_frame_skip == 0 && stats.audio_header.is_empty();
+            if !frames_whole || pes.pts.is_some() {
+                stats.audio_timing = match (pes.pts, stats.audio_timing) {
+                    (Some(pts), Some((last, elapsed, nominal))) if frames_whole && stats.pes_frame_ticks > 0.0 => {
+                        let delta = pts.wrapping_sub(last) & (PTS_WRAP_THRESHOLD - 1);
+                        if delta == 0 || delta > MAX_PTS_JUMP {
+                            Some((pts, 0, 0.0)) // discontinuity: start over
+                        } else {
+                            let (elapsed, nominal) = (elapsed + delta, nominal + stats.pes_frame_ticks);
+                            if elapsed >= FRAME_RATE_WINDOW_TICKS {
+                                stats.audio_drift_ppm = Some((elapsed as f64 / nominal - 1.0) * 1e6);
+                            }
+                            Some((pts, elapsed, nominal))
+                        }
+                    }
+                    (pts, _) => pts.map(|pts| (pts, 0, 0.0)),
+                };
+                stats.pes_frame_ticks = 0.0;
+            }
+
+            stats.pes_frames = 0;
+            if pes.kind == PesStreamKind::Video {
+                stats.pes_frames = 1;
+                stats.frames_delivered += 1;
+            }
+            stats.audio_synced = matches!(stream_type, 0x03 | 0x04 | 0x0F | 0x11 | 0x81 | 0x87);
+            // A PES header running past this packet is skipped like frame data
+            stats.audio_frame_skip = pes.es_offset.saturating_sub(payload.len());
+            stats.audio_header.clear();
+            es = payload.get(pes.es_offset..).unwrap_or(&[]);
+        }
+
+        // Audio: hop from frame header to frame header; a bad header stops counting until the next PES
+        while stats.audio_synced {
+            let skip = stats.audio_frame_skip.min(es.len());
+            stats.audio_frame_skip -= skip;
+            es = &es[skip..];
+            let take = (AUDIO_FRAME_HEADER_LEN - stats.audio_header.len()).min(es.len());
+            stats.audio_header.extend_from_slice(&es[..take]);
+            es = &es[take..];
+            if stats.audio_header.len() < AUDIO_FRAME_HEADER_LEN {
+                break; // header continues in the next packet
+            }
+            match audio_frame_length(stream_type, &stats.audio_header) {
+                Some(len) if len >= AUDIO_FRAME_HEADER_LEN => {
+                    stats.frames_delivered += 1;
+                    stats.pes_frames += 1;
+                    stats.pes_frame_ticks += audio_frame_ticks(stream_type, &stats.audio_header).unwrap_or(0.0);
+                    stats.audio_frame_skip = len - AUDIO_FRAME_HEADER_LEN;
+                }
+                _ => stats.audio_synced = false,
+            }
+            stats.audio_header.clear();
+        }
+    }
+
+    fn parse_codec_info(&mut self, pid: u16, payload_unit_start: bool, payload: &[u8]) {
         let Some(stats) = self.stats_manager.get(pid) else { return };
 
-        if stats.codec.is_some() {
+        // An override picks the parser outright; otherwise registration and descriptors
+        // refine the PMT stream_type
+        let override_hint = self.codec_overrides.get(&stats.stream_type).copied();
+        let stream_type = override_hint.map_or(stats.stream_type, CodecHint::stream_type);
+
+        // LATM keeps being parsed until a StreamMuxConfig has been seen
+        let latm_pending = stream_type == 0x11 && stats.latm_config.is_none();
+        if stats.codec.is_some() && !latm_pending {
             return; // Already parsed
         }
 
-        let stream_type = stats.stream_type;
+        let registration = match override_hint {
+            Some(hint) => (hint == CodecHint::Av1).then_some(FORMAT_ID_AV1),
+            None => self.pmt_stream(pid).and_then(|(pmt, s)| pmt.stream_registration(s)),
+        };
+
+        // DVB carries (E-)AC-3 as private data, marked by an AC-3/enhanced_AC-3 descriptor
+        let dvb_ac3 = override_hint.is_none()
+            && stream_type == 0x06
+            && self.pmt_stream(pid).is_some_and(|(_, s)| ac3_descriptor(&s.descriptors).is_some());
 
         // Handle stream types that don't require PES header parsing
         match stream_type {
-            0x06 => {
-                // DVB Subtitle - no ES parsing needed
+            0x06 if dvb_ac3 => {
+                if let Some(ac3) = parse_audio_codec(0x81, payload) {
+                    self.stats_manager.set_codec(pid, CodecInfo::Audio(ac3));
+                }
+            }
+            0x06 if registration != Some(FORMAT_ID_AV1) => {
+                // DVB Subtitle - no ES parsing needed; language/page info comes from the PMT
+                let pages = self.pmt_stream(pid).map(|(_, s)| subtitle_pages(&s.descriptors)).unwrap_or_default();
                 let codec = CodecInfo::Subtitle(SubtitleInfo {
                     codec: "DVB Subtitle".to_string(),
+                    pages,
                 });
                 self.stats_manager.set_codec(pid, codec);
             }
@@ -372,14 +976,22 @@ impl PacketProcessor {
                 }
             }
             0x11 => {
-                // AAC LATM - can be found directly in payload
+                // AAC LATM - can be found directly in payload. Frames with
+                // useSameStreamMux carry no config, so reuse the last one decoded.
                 if let Some(latm) = parse_audio_codec(stream_type, payload) {
-                    let codec = CodecInfo::Audio(latm);
-                    self.stats_manager.set_codec(pid, codec);
+                    let Some(stats) = self.stats_manager.get_mut(pid) else { return };
+                    let latm = if latm.sample_rate.is_some() {
+                        stats.latm_config = Some(latm.clone());
+                        latm
+                    } else {
+                        stats.latm_config.clone().unwrap_or(latm)
+                    };
+                    self.stats_manager.set_codec(pid, CodecInfo::Audio(latm));
                 }
+                return; // The PES path below would re-parse the same frames without the cache
             }
-            0x81 => {
-                // AC-3 - can be found directly in payload
+            0x81 | 0x87 => {
+                // AC-3 / E-AC-3 - can be found directly in payload
                 if let Some(ac3) = parse_audio_codec(stream_type, payload) {
                     let codec = CodecInfo::Audio(ac3);
                     self.stats_manager.set_codec(pid, codec);
@@ -388,50 +1000,62 @@ impl PacketProcessor {
             _ => {}
         }
 
-        // Handle PES-based parsing for video and AAC
-        if payload_unit_start && payload.len() >= 6 && payload.starts_with(&[0x00, 0x00, 0x01]) {
-            let pes_hdr_len = 9 + payload[8] as usize;
-            if pes_hdr_len < payload.len() {
-                let es_payload = &payload[pes_hdr_len..];
-
-                // Try video parsing
-                if let Some(video_info) = parse_video_codec(stream_type, es_payload) {
-                    let codec = CodecInfo::Video(video_info);
-                    self.stats_manager.set_codec(pid, codec);
+        // Video headers (SPS/VPS/PPS, sequence header) may sit behind an AUD and SEI or
+        // in a later packet of the PES, so the start of every PES is collected and
+        // re-parsed until the codec is known. The PES header itself may end exactly at,
+        // or run past, the end of the first packet; its remainder is skipped in the next.
+        if is_video_codec(stream_type, registration) {
+            if let Some(stats) = self.stats_manager.get_mut(pid) {
+                if payload_unit_start {
+                    stats.video_header.clear();
+                    stats.video_header_skip = parse_pes_header(payload)
+                        .filter(|pes| pes.kind.is_elementary())
+                        .map(|pes| pes.es_offset);
                 }
-                // Try audio parsing
-                else if let Some(audio_info) = parse_audio_codec(stream_type, es_payload) {
-                    let codec = CodecInfo::Audio(audio_info);
-                    self.stats_manager.set_codec(pid, codec);
+                match stats.video_header_skip {
+                    Some(skip) if stats.video_header.len() < VIDEO_HEADER_SEARCH_BYTES => {
+                        stats.video_header.extend_from_slice(payload.get(skip..).unwrap_or_default());
+                        stats.video_header_skip = Some(skip.saturating_sub(payload.len()));
+                    }
+                    _ => {
+                        // Nothing new to parse
+                        stats.video_header.clear();
+                        stats.video_header_skip = None;
+                    }
+                }
+                if !stats.video_header.is_empty()
+                    && let Some(video_info) = parse_video_codec(stream_type, registration, &stats.video_header)
+                {
+                    stats.video_header = Vec::new();
+                    stats.video_header_skip = None;
+                    self.stats_manager.set_codec(pid, CodecInfo::Video(video_info));
                 }
             }
         }
+        // Handle PES-based parsing for AAC; a PES whose header fills the first packet
+        // is retried on the next PES
+        else if payload_unit_start
+            && let Some(pes) = parse_pes_header(payload)
+            && pes.kind.is_elementary()
+            && let Some(es_payload) = payload.get(pes.es_offset..).filter(|es| !es.is_empty())
+            && let Some(audio_info) = parse_audio_codec(stream_type, es_payload)
+        {
+            let codec = CodecInfo::Audio(audio_info);
+            self.stats_manager.set_codec(pid, codec);
+        }
 
-        // FPS calculation by PTS for video streams
-        self.calculate_fps_from_pts(pid, payload_unit_start, payload, analysis_mode);
     }
 
     fn calculate_fps_from_pts(&mut self, pid: u16, payload_unit_start: bool, payload: &[u8], analysis_mode: Option<AnalysisMode>) {
-        if !payload_unit_start || payload.len() <= 14 || !payload.starts_with(&[0x00, 0x00, 0x01]) {
+        if !payload_unit_start {
             return;
         }
-
-        let stream_id = payload[3];
-        if stream_id & 0xF0 != 0xE0 { // Not video stream
+        let Some(pes) = parse_pes_header(payload) else { return };
+        if pes.kind != PesStreamKind::Video {
             return;
         }
-
-        let pts_dts_flags = (payload[7] & 0xC0) >> 6;
-        if pts_dts_flags & 0b10 == 0 { // No PTS
-            return;
-        }
-
-        let p = &payload[9..14];
-        let pts: u64 = ((p[0] as u64 & 0x0E) << 29)
-            | ((p[1] as u64) << 22)
-            | (((p[2] as u64 & 0xFE) >> 1) << 15)
-            | ((p[3] as u64) << 7)
-            | ((p[4] as u64) >> 1);
+        let Some(pts) = pes.pts else { return };
+        let fps_source = self.fps_source;
 
         if let Some(stats) = self.stats_manager.get_mut(pid) {
             // Store PTS sample for FPS calculation
@@ -472,7 +1096,12 @@ impl PacketProcessor {
                         // Only update FPS if:
                         // 1. We don't have FPS from SPS (fps == 0.0), OR
                         // 2. The FPS from SPS seems wrong (too different from PTS calculation)
-                        if vinfo.fps == 0.0 || (vinfo.fps - fps_est).abs() > 2.0 {
+                        //    and fps_source lets the PTS estimate override it
+                        let override_signaled = match fps_source {
+                            FpsSource::PtsOverride(tolerance) => (vinfo.fps - fps_est).abs() > tolerance,
+                            FpsSource::Signaled => false,
+                        };
+                        if vinfo.fps == 0.0 || override_signaled {
                             vinfo.fps = round_to_common_fps(fps_est);
                         }
                     }
@@ -487,9 +1116,384 @@ impl PacketProcessor {
         }
     }
 
+    /// Codec of an elementary stream: parsed from the bitstream, or until then (and for
+    /// scrambled audio) what its AC-3/enhanced_AC-3 descriptor declares
+    pub fn stream_codec(&self, stream: &crate::psi::pmt::StreamInfo) -> Option<CodecInfo> {
+        let parsed = self.stats_manager.get(stream.elementary_pid).and_then(|s| s.codec.clone());
+        parsed.or_else(|| {
+            let descriptor = ac3_descriptor(&stream.descriptors).filter(|_| !self.codec_overrides.contains_key(&stream.stream_type))?;
+            Some(CodecInfo::Audio(ac3_descriptor_info(&descriptor)))
+        })
+    }
+
+    /// PMT and entry declaring this elementary PID
+    fn pmt_stream(&self, pid: u16) -> Option<(&PmtSection, &crate::psi::pmt::StreamInfo)> {
+        self.pmt_map
+            .values()
+            .find_map(|pmt| Some((pmt, pmt.streams.iter().find(|s| s.elementary_pid == pid)?)))
+    }
+
+    /// Whether a PAT/PMT was acquired or changed version, or a Priority 1 counter left
+    /// zero, since the last call; clears the pending event
+    pub fn take_event(&mut self) -> bool {
+        std::mem::take(&mut self.psi_changed) | std::mem::take(&mut self.error_onset)
+    }
+
+    /// Refresh the TR-101 per-second error rates; call once per report
+    pub fn update_tr101_rates(&mut self) {
+        if let Some(ref mut tr101) = self.tr101 {
+            tr101.update_rates();
+        }
+    }
+
+    /// Drop a program that `pat` no longer lists: its PMT (unless another program of
+    /// `pat` still uses that PMT PID), and the elementary and PCR PID state only it declared
+    fn remove_program(&mut self, program_number: u16, pat: &PatSection) {
+        let pmt_pid = self
+            .pat_map
+            .remove(&program_number)
+            .and_then(|old| old.programs.iter().find(|e| e.program_number == program_number).map(|e| e.pmt_pid))
+            .filter(|&pid| !pat.programs.iter().any(|e| e.pmt_pid == pid));
+        self.pcr_pid_map.remove(&program_number);
+        if let Some(pmt_pid) = pmt_pid {
+            self.pmt_map.remove(&pmt_pid);
+            self.si_cache.pmts.remove(&pmt_pid);
+            self.last_payloads.remove(&pmt_pid);
+        }
+        if let Some(tr101) = &mut self.tr101 {
+            tr101.forget_program(program_number, pmt_pid);
+        }
+        self.prune_undeclared_pids();
+        if !self.si_cache.sdt.as_ref().is_some_and(|sdt| sdt.services.iter().any(|s| s.service_id == program_number)) {
+            self.forget_epg(program_number);
+        }
+    }
+
+    /// Drop the EPG of a service that neither the PAT nor SDT-actual lists any more
+    fn forget_epg(&mut self, service_id: u16) {
+        self.epg.remove(&service_id);
+        self.epg_sections.remove(&service_id);
+    }
+
+    /// Drop schedule events that ended before `now` (TDT/TOT time), and services left
+    /// without any
+    fn prune_ended_events(&mut self, now: i64) {
+        for events in self.epg.values_mut() {
+            events.retain(|_, &mut (start, duration)| start + duration as i64 > now);
+        }
+        self.epg.retain(|_, events| !events.is_empty());
+    }
+
     /// Clean up old/inactive streams
     pub fn cleanup_old_streams(&mut self, timeout_secs: u64) {
         self.stats_manager.cleanup_old_streams(std::time::Duration::from_secs(timeout_secs));
+        self.prune_undeclared_pids();
+    }
+
+    /// Forget elementary and PCR PIDs that no current PMT declares
+    fn prune_undeclared_pids(&mut self) {
+        let pmt_map = &self.pmt_map;
+        self.declared_pids.retain(|pid, _| {
+            pmt_map.values().any(|pmt| pmt.streams.iter().any(|s| s.elementary_pid == *pid))
+        });
+        self.last_pcr.retain(|pid, _| pmt_map.values().any(|pmt| pmt.pcr_pid == *pid));
+        self.pcr_timing.retain(|pid, _| pmt_map.values().any(|pmt| pmt.pcr_pid == *pid));
+        // Let the stream clock follow another PCR PID once its reference is gone
+        if let Some(clock) = self.stats_manager.clock.as_mut()
+            && clock.pid.is_some_and(|pid| !pmt_map.values().any(|pmt| pmt.pcr_pid == pid))
+        {
+            clock.pid = None;
+        }
+    }
+
+    /// Spacing between successive PCRs on `pid`, measured from the PCR values. Across a
+    /// discontinuity, or a step no sane PCR spacing explains, delivery time is used instead.
+    fn record_pcr_interval(&mut self, pid: u16, pcr_ticks: u64, discontinuity: bool) {
+        let now = Instant::now();
+        let Some(timing) = self.pcr_timing.get_mut(&pid) else {
+            self.pcr_timing.insert(pid, PcrTiming {
+                last_ticks: pcr_ticks,
+                last_seen: now,
+                max_ms: 0.0,
+                sum_ms: 0.0,
+                intervals: 0,
+                over_limit: 0,
+            });
+            return;
+        };
+        let ticks = if pcr_ticks >= timing.last_ticks {
+            pcr_ticks - timing.last_ticks
+        } else {
+            PCR_WRAP_THRESHOLD - timing.last_ticks + pcr_ticks
+        };
+        let interval_ms = if !discontinuity && ticks > 0 && ticks < PCR_CLOCK_HZ as u64 {
+            ticks as f64 / PCR_CLOCK_HZ * 1000.0
+        } else {
+            now.duration_since(timing.last_seen).as_secs_f64() * 1000.0
+        };
+        timing.max_ms = timing.max_ms.max(interval_ms);
+        timing.sum_ms += interval_ms;
+        timing.intervals += 1;
+        if interval_ms > PCR_REPETITION_MS as f64 {
+            timing.over_limit += 1;
+        }
+        timing.last_ticks = pcr_ticks;
+        timing.last_seen = now;
+    }
+
+    /// PCR repetition statistics for `pcr_pid`, once two PCRs have been seen
+    pub fn pcr_interval(&self, pcr_pid: u16) -> Option<PcrIntervalInfo> {
+        let timing = self.pcr_timing.get(&pcr_pid).filter(|t| t.intervals > 0)?;
+        Some(PcrIntervalInfo {
+            max_ms: timing.max_ms,
+            mean_ms: timing.sum_ms / timing.intervals as f64,
+            intervals: timing.intervals,
+            over_limit: timing.over_limit,
+        })
+    }
+
+    /// Latest PCR on `pcr_pid`; the arrival wallclock is derived from its receive instant
+    pub fn last_pcr(&self, pcr_pid: u16) -> Option<LastPcrInfo> {
+        let timing = self.pcr_timing.get(&pcr_pid)?;
+        let age = chrono::Duration::from_std(timing.last_seen.elapsed()).ok()?;
+        Some(LastPcrInfo {
+            ticks: timing.last_ticks,
+            secs: timing.last_ticks as f64 / PCR_CLOCK_HZ,
+            arrival: (chrono::Utc::now() - age).to_rfc3339(),
+        })
+    }
+
+    /// Elementary PIDs declared in a current PMT for longer than the grace period
+    /// that have carried no packets (within the stream inactivity timeout)
+    pub fn dangling_pids(&self) -> Vec<u16> {
+        let grace = Duration::from_secs(DANGLING_PID_GRACE_SECONDS);
+        let mut pids: Vec<u16> = self
+            .pmt_map
+            .values()
+            .flat_map(|pmt| pmt.streams.iter().map(|s| s.elementary_pid))
+            .filter(|pid| self.declared_pids.get(pid).is_some_and(|t| t.elapsed() >= grace))
+            .filter(|pid| !self.stats_manager.pid_stats.contains_key(pid))
+            .collect();
+        pids.sort_unstable();
+        pids.dedup();
+        pids
+    }
+
+    /// PIDs reserved for PSI/SI (0x0000-0x001F), the NIT PID and every PMT PID in the PAT
+    fn is_psi_pid(&self, pid: u16) -> bool {
+        pid < 0x0020
+            || pid == self.network_pid
+            || self.pat_map.values().any(|p| p.programs.iter().any(|e| e.pmt_pid == pid))
+    }
+
+    /// PSI PIDs that a PMT also declares as elementary PIDs, sorted
+    pub fn pid_role_conflicts(&self) -> Vec<u16> {
+        self.pid_role_conflicts.iter().copied().collect()
+    }
+
+    /// PMT PIDs the PAT lists for more than one program, elementary PIDs the PMTs of several
+    /// programs declare with different stream_types, and PCR PIDs that are not one of the program's own elementary
+    /// streams but another program's, or a PMT PID. A dedicated PCR PID shared by several
+    /// programs (one common clock) is allowed.
+    pub fn program_pid_collisions(&self) -> Vec<PidCollision> {
+        use std::collections::{BTreeMap, BTreeSet};
+
+        let mut pmt_pids: BTreeMap<u16, BTreeSet<u16>> = BTreeMap::new();
+        for (program_number, pat) in &self.pat_map {
+            if let Some(entry) = pat.programs.iter().find(|e| e.program_number == *program_number) {
+                pmt_pids.entry(entry.pmt_pid).or_default().insert(*program_number);
+            }
+        }
+        let mut collisions: Vec<PidCollision> = pmt_pids
+            .iter()
+            .filter(|(_, programs)| programs.len() > 1)
+            .map(|(&pid, programs)| PidCollision { pid, kind: PidCollisionKind::PmtPid, programs: programs.iter().copied().collect() })
+            .collect();
+
+        // A component shared by several programs is legal; only disagreement on its
+        // stream_type is a fault
+        let mut es_pids: BTreeMap<u16, (BTreeSet<u16>, BTreeSet<u8>)> = BTreeMap::new();
+        for pmt in self.pmt_map.values() {
+            for stream in &pmt.streams {
+                let (programs, stream_types) = es_pids.entry(stream.elementary_pid).or_default();
+                programs.insert(pmt.program_number);
+                stream_types.insert(stream.stream_type);
+            }
+        }
+        collisions.extend(
+            es_pids
+                .into_iter()
+                .filter(|(_, (programs, stream_types))| programs.len() > 1 && stream_types.len() > 1)
+                .map(|(pid, (programs, _))| PidCollision { pid, kind: PidCollisionKind::ElementaryPid, programs: programs.into_iter().collect() }),
+        );
+
+        let mut pcr_pids: BTreeMap<u16, BTreeSet<u16>> = BTreeMap::new();
+        for pmt in self.pmt_map.values().filter(|pmt| pmt.pcr_pid != NULL_PID) {
+            if pmt.streams.iter().any(|s| s.elementary_pid == pmt.pcr_pid) {
+                continue;
+            }
+            let owners: Vec<u16> = self
+                .pmt_map
+                .values()
+                .filter(|other| other.streams.iter().any(|s| s.elementary_pid == pmt.pcr_pid))
+                .map(|other| other.program_number)
+                .chain(pmt_pids.get(&pmt.pcr_pid).into_iter().flatten().copied())
+                .collect();
+            if !owners.is_empty() {
+                let programs = pcr_pids.entry(pmt.pcr_pid).or_default();
+                programs.insert(pmt.program_number);
+                programs.extend(owners);
+            }
+        }
+        collisions.extend(pcr_pids.into_iter().map(|(pid, programs)| PidCollision {
+            pid,
+            kind: PidCollisionKind::PcrPid,
+            programs: programs.into_iter().collect(),
+        }));
+        collisions
+    }
+
+    /// EPG coverage of a service from the EIT-actual schedule events seen so far
+    pub fn epg_coverage(&self, service_id: u16) -> Option<EpgCoverage> {
+        let events = self.epg.get(&service_id).filter(|e| !e.is_empty())?;
+        let secs: u64 = events.values().map(|&(_, duration)| duration as u64).sum();
+        let until = events.values().map(|&(start, duration)| start + duration as i64).max()?;
+        let (sections_received, sections_expected) =
+            self.epg_sections.get(&service_id).map_or((0, 0), ScheduleSections::counts);
+        Some(EpgCoverage {
+            events: events.len() as u32,
+            hours: secs as f64 / 3600.0,
+            until: chrono::DateTime::from_timestamp(until, 0)?.to_rfc3339(),
+            sections_received,
+            sections_expected,
+            complete_percent: if sections_expected > 0 {
+                sections_received as f64 * 100.0 / sections_expected as f64
+            } else {
+                0.0
+            },
+        })
+    }
+
+    /// Keep the component_descriptors of the present event (EIT-actual p/f section 0)
+    fn update_present_event(&mut self, pf: EitPfSection) {
+        if pf.section_number != 0 {
+            return;
+        }
+        match pf.events.into_iter().next().map(|e| e.components).filter(|c| !c.is_empty()) {
+            Some(components) => { self.event_components.insert(pf.service_id, components); }
+            None => { self.event_components.remove(&pf.service_id); }
+        }
+    }
+
+    /// Description of an elementary stream from the component_descriptor whose
+    /// component_tag matches the stream's stream_identifier_descriptor; the present
+    /// EIT event is preferred over the SDT
+    pub fn stream_component(&self, program_number: u16, es_descriptors: &[Descriptor]) -> Option<String> {
+        let tag = component_tag(es_descriptors)?;
+        let matching = |components: &[Component]| components.iter().find(|c| c.component_tag == tag).and_then(|c| c.description());
+        self.event_components
+            .get(&program_number)
+            .and_then(|c| matching(c))
+            .or_else(|| matching(&self.sdt_service(program_number)?.components))
+    }
+
+    /// SDT service entry for a program (service_id == program_number)
+    pub fn sdt_service(&self, program_number: u16) -> Option<&crate::psi::sdt::Service> {
+        self.si_cache
+            .sdt
+            .as_ref()?
+            .services
+            .iter()
+            .find(|s| s.service_id == program_number)
+    }
+
+    /// Whether `pid` passes the program/PID filters. PSI PIDs always do, so the
+    /// PID→program mapping stays current.
+    pub fn pid_selected(&self, pid: u16) -> bool {
+        if self.program_filter.is_none() && self.pid_filter.is_none() {
+            return true;
+        }
+        if pid < 0x0020 || pid == self.network_pid || self.pat_map.values().any(|pat| pat.programs.iter().any(|p| p.pmt_pid == pid)) {
+            return true;
+        }
+        if self.pid_filter.as_ref().is_some_and(|f| f.contains(&pid)) {
+            return true;
+        }
+        self.program_filter.as_ref().is_some_and(|f| {
+            self.pmt_map.values().any(|pmt| {
+                f.contains(&pmt.program_number)
+                    && (pmt.pcr_pid == pid || pmt.streams.iter().any(|s| s.elementary_pid == pid))
+            })
+        })
+    }
+
+    /// Whether a program should appear in reports under the current filters
+    pub fn program_selected(&self, program_number: u16, pmt: &PmtSection) -> bool {
+        if self.program_filter.is_none() && self.pid_filter.is_none() {
+            return true;
+        }
+        self.program_filter.as_ref().is_some_and(|f| f.contains(&program_number))
+            || self.pid_filter.as_ref().is_some_and(|f| pmt.streams.iter().any(|s| f.contains(&s.elementary_pid)))
+    }
+
+    /// Stream classes whose count in `pmt` differs from the program's `expected_streams`
+    /// template; empty without a template
+    pub fn stream_mismatches(&self, pmt: &PmtSection) -> Vec<StreamCountMismatch> {
+        let Some(template) = self.expected_streams.get(&pmt.program_number) else { return Vec::new() };
+        let mut counts: HashMap<StreamClass, u32> = HashMap::new();
+        for stream in &pmt.streams {
+            *counts.entry(self.stream_class(pmt, stream)).or_default() += 1;
+        }
+        [StreamClass::Video, StreamClass::Audio, StreamClass::Subtitle, StreamClass::Data]
+            .into_iter()
+            .filter_map(|class| {
+                let expected = template.expected(class)?;
+                let found = counts.get(&class).copied().unwrap_or(0);
+                (found != expected).then_some(StreamCountMismatch { class, expected, found })
+            })
+            .collect()
+    }
+
+    /// Class of a PMT stream from its stream_type (after `codec_overrides`), registration
+    /// and, for DVB private data, its descriptors
+    fn stream_class(&self, pmt: &PmtSection, stream: &crate::psi::pmt::StreamInfo) -> StreamClass {
+        if let Some(hint) = self.codec_overrides.get(&stream.stream_type) {
+            return match hint {
+                CodecHint::Mpeg2Video | CodecHint::H264 | CodecHint::Hevc | CodecHint::Vvc | CodecHint::Av1 => StreamClass::Video,
+                CodecHint::Mpeg1Audio | CodecHint::AacAdts | CodecHint::AacLatm | CodecHint::Ac3 | CodecHint::Eac3 => StreamClass::Audio,
+                CodecHint::DvbSubtitle => StreamClass::Subtitle,
+                CodecHint::Data => StreamClass::Data,
+            };
+        }
+        let has = |tags: &[u8]| stream.descriptors.iter().any(|d| tags.contains(&d.tag));
+        match stream.stream_type {
+            0x01 | 0x02 | 0x10 | 0x1B | 0x1F | 0x20 | 0x21 | 0x24 | 0x33 => StreamClass::Video,
+            0x03 | 0x04 | 0x0F | 0x11 | 0x1C | 0x2D | 0x80..=0x85 | 0x87 => StreamClass::Audio,
+            0x06 if pmt.stream_registration(stream) == Some(FORMAT_ID_AV1) => StreamClass::Video,
+            // AC-3, enhanced_AC-3, DTS and AAC descriptors (EN 300 468 Annex D/G/H)
+            0x06 if has(&[0x6A, 0x7A, 0x7B, 0x7C]) => StreamClass::Audio,
+            0x06 if has(&[0x59]) => StreamClass::Subtitle,
+            _ => StreamClass::Data,
+        }
+    }
+
+    /// Aggregate the scrambling state of a program's elementary streams; None until
+    /// any of them has carried a payload
+    pub fn program_scrambling(&self, pmt: &PmtSection) -> Option<ScramblingState> {
+        let observed: Vec<bool> = pmt
+            .streams
+            .iter()
+            .filter_map(|s| self.stats_manager.pid_scrambled(s.elementary_pid))
+            .collect();
+        if observed.is_empty() {
+            None
+        } else if observed.iter().all(|&s| s) {
+            Some(ScramblingState::Scrambled)
+        } else if observed.iter().any(|&s| s) {
+            Some(ScramblingState::Partial)
+        } else {
+            Some(ScramblingState::Clear)
+        }
     }
 
     /// Get PCR PID for a specific program number
@@ -497,9 +1501,60 @@ impl PacketProcessor {
         self.pcr_pid_map.get(&program_number).copied()
     }
 
-    /// Get PMT version for a specific PMT PID
+    /// Get PMT version for a specific PMT PID; None for a manually declared program
     pub fn get_pmt_version(&self, pmt_pid: u16) -> Option<u8> {
-        self.pmt_map.get(&pmt_pid).map(|pmt| pmt.version)
+        self.pmt_map.get(&pmt_pid).filter(|_| pmt_pid < MANUAL_PMT_KEY_BASE).map(|pmt| pmt.version)
+    }
+
+    /// Seed `pat_map`/`pmt_map` with the programs of `Options::manual_programs`, as if a
+    /// PAT and PMT had declared them; each gets a pmt_map key from MANUAL_PMT_KEY_BASE up
+    pub fn add_manual_programs(&mut self, programs: &HashMap<u16, ManualProgram>) {
+        let mut numbers: Vec<u16> = programs.keys().copied().collect();
+        numbers.sort_unstable();
+        let now = Instant::now();
+        for (key, program_number) in (MANUAL_PMT_KEY_BASE..).zip(numbers) {
+            let program = &programs[&program_number];
+            let pmt = PmtSection {
+                version: 0,
+                program_number,
+                pcr_pid: program.pcr_pid,
+                section_number: 0,
+                last_section: 0,
+                descriptors: Vec::new(),
+                streams: program.streams.iter()
+                    .map(|&(elementary_pid, stream_type)| crate::psi::pmt::StreamInfo { stream_type, elementary_pid, descriptors: Vec::new() })
+                    .collect(),
+            };
+            let pat = PatSection {
+                version: 0,
+                current_next: true,
+                transport_stream_id: 0,
+                section_number: 0,
+                last_section: 0,
+                network_pid: None,
+                programs: vec![PatEntry { program_number, pmt_pid: key }],
+            };
+
+            if let Some(ref mut tr101) = self.tr101 {
+                tr101.register_known_pid(pmt.pcr_pid);
+                for stream in &pmt.streams {
+                    tr101.register_known_pid(stream.elementary_pid);
+                }
+            }
+            for stream in &pmt.streams {
+                self.declared_pids.entry(stream.elementary_pid).or_insert(now);
+            }
+            self.pcr_pid_map.insert(program_number, pmt.pcr_pid);
+            self.pat_map.insert(program_number, pat);
+            self.pmt_map.insert(key, pmt);
+        }
+    }
+
+    /// Whether `program_number` comes from `Options::manual_programs`
+    fn is_manual_program(&self, program_number: u16) -> bool {
+        self.pat_map.get(&program_number).is_some_and(|pat| {
+            pat.programs.iter().any(|e| e.program_number == program_number && e.pmt_pid >= MANUAL_PMT_KEY_BASE)
+        })
     }
 
     /// Get TR-101 metrics reference
@@ -508,6 +1563,96 @@ impl PacketProcessor {
     }
 }
 
+/// EIT schedule sections received for one service, per table_id (0x50-0x5F)
+#[derive(Default)]
+struct ScheduleSections {
+    last_table_id: u8,
+    tables: HashMap<u8, ScheduleTable>,
+}
+
+/// Sections of one schedule table (one table_id) of the current version
+struct ScheduleTable {
+    version: u8,
+    last_section: u8,
+    received: [u64; 4],                // Bit per section_number
+    segment_last: [Option<u8>; 32],    // segment_last_section_number per 8-section segment
+}
+
+impl ScheduleSections {
+    fn record(&mut self, eit: &crate::psi::eit::EitScheduleSection) {
+        self.last_table_id = eit.last_table_id;
+        let table = self.tables.entry(eit.table_id).or_insert(ScheduleTable {
+            version: eit.version,
+            last_section: eit.last_section,
+            received: [0; 4],
+            segment_last: [None; 32],
+        });
+        if table.version != eit.version {
+            table.version = eit.version;
+            table.received = [0; 4];
+            table.segment_last = [None; 32];
+        }
+        table.last_section = eit.last_section;
+        let n = eit.section_number;
+        table.received[n as usize / 64] |= 1 << (n % 64);
+        table.segment_last[n as usize / 8] = Some(eit.segment_last_section);
+    }
+
+    /// (received, expected) sections over table_ids 0x50..=last_table_id. Each segment
+    /// is expected to run from its first section to its segment_last_section_number,
+    /// an empty segment still carrying one section (EN 300 468 §5.2.4)
+    fn counts(&self) -> (u32, u32) {
+        let (mut received, mut expected) = (0, 0);
+        for table_id in 0x50..=self.last_table_id.clamp(0x50, 0x5F) {
+            let Some(table) = self.tables.get(&table_id) else {
+                expected += 1;
+                continue;
+            };
+            for segment in 0..=table.last_section / 8 {
+                let first = segment * 8;
+                let last = table.segment_last[segment as usize]
+                    .map_or(first, |last| last.clamp(first, first + 7).min(table.last_section));
+                expected += (last - first) as u32 + 1;
+                received += (first..=last)
+                    .filter(|&n| table.received[n as usize / 64] & (1 << (n % 64)) != 0)
+                    .count() as u32;
+            }
+        }
+        (received, expected)
+    }
+}
+
+/// Datagrams received since `start`, with the spacing between successive arrivals
+struct ArrivalWindow {
+    start: Instant,
+    last: Instant,
+    datagrams: u64,
+    packets: u64,
+    sum_ms: f64,
+    sum_sq_ms: f64,
+}
+
+impl ArrivalWindow {
+    fn starting_at(now: Instant) -> Self {
+        ArrivalWindow { start: now, last: now, datagrams: 0, packets: 0, sum_ms: 0.0, sum_sq_ms: 0.0 }
+    }
+}
+
+/// Running PCR spacing statistics for one PCR PID
+struct PcrTiming {
+    last_ticks: u64,                // 27 MHz
+    last_seen: Instant,
+    max_ms: f64,
+    sum_ms: f64,
+    intervals: u64,
+    over_limit: u64,                // intervals above PCR_REPETITION_MS
+}
+
+/// stream_type whose parser handles a stream of PMT type `stream_type`, after overrides
+fn parser_stream_type(overrides: &HashMap<u8, CodecHint>, stream_type: u8) -> u8 {
+    overrides.get(&stream_type).map_or(stream_type, |hint| hint.stream_type())
+}
+
 /// Round estimated FPS to common frame rates for better accuracy
 /// Also handles interlaced video detection (field rate -> frame rate)
 fn round_to_common_fps(fps_est: f32) -> f32 {
@@ -542,4 +1687,265 @@ fn round_to_common_fps(fps_est: f32) -> f32 {
 
     // Round to 2 decimal places if no common rate found
     (fps_est * 100.0).round() / 100.0
-}
\ No newline at end of file
+}
+
+/// Offset of the first sync byte that is followed by another one a packet later
+/// (or that is too close to the end to verify)
+fn find_sync(data: &[u8]) -> Option<usize> {
+    (0..data.len()).find(|&i| {
+        data[i] == TS_SYNC_BYTE
+            && [TS_PACKET_SIZE, TS_PACKET_SIZE_RS].iter().any(|&size| data.get(i + size).is_none_or(|&b| b == TS_SYNC_BYTE))
+    })
+}
+
+/// Packet stride at the start of `data`, if it can be confirmed
+fn detect_packet_size(data: &[u8]) -> Option<usize> {
+    [TS_PACKET_SIZE, TS_PACKET_SIZE_RS]
+        .into_iter()
+        .find(|&size| data.get(size) == Some(&TS_SYNC_BYTE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::psi::section::tests::section_payload;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    const MODES: [AnalysisMode; 6] = [
+        AnalysisMode::Mux,
+        AnalysisMode::Tr101,
+        AnalysisMode::Tr101Priority1,
+        AnalysisMode::Tr101Priority12,
+        AnalysisMode::Tr101Priority3,
+        AnalysisMode::None,
+    ];
+
+    /// PIDs the random packets go to: PSI/SI, the PMT and the elementary streams it declares
+    const PIDS: [u16; 11] = [0x0000, 0x0001, 0x0010, 0x0011, 0x0012, 0x0014, 0x0100, 0x0101, 0x0102, 0x0103, 0x1FFF];
+
+    fn ts_packet(pid: u16, pusi: bool, byte3: u8, payload: &[u8]) -> Vec<u8> {
+        let mut pkt = vec![TS_SYNC_BYTE, (if pusi { 0x40 } else { 0 }) | (pid >> 8) as u8, pid as u8, byte3];
+        pkt.extend_from_slice(payload);
+        pkt.resize(TS_PACKET_SIZE, 0xFF);
+        pkt
+    }
+
+    /// PAT for program 1 on PMT PID 0x100, and a PMT with H.264, AAC and a private
+    /// stream carrying an AC-3 descriptor, so random packets reach the ES parsers
+    fn valid_psi() -> Vec<u8> {
+        let pat = section_payload(0x00, 1, 0, &[0x00, 0x01, 0xE1, 0x00]);
+        let pmt = section_payload(0x02, 1, 0, &[
+            0xE1, 0x01, 0xF0, 0x00,
+            0x1B, 0xE1, 0x01, 0xF0, 0x00,
+            0x0F, 0xE1, 0x02, 0xF0, 0x00,
+            0x06, 0xE1, 0x03, 0xF0, 0x03, 0x6A, 0x01, 0x00,
+        ]);
+        [ts_packet(0x0000, true, 0x10, &pat), ts_packet(0x0100, true, 0x10, &pmt)].concat()
+    }
+
+    #[test]
+    fn garbage_is_a_sync_loss_and_not_a_packet() {
+        let mut data = valid_psi();
+        data.extend(ts_packet(0x101, false, 0x10, &[]));
+        // Five packets' worth of garbage that would read as a TEI-flagged packet on 0x101
+        data.extend([0x46, 0x81, 0x01, 0x15].iter().cycle().take(5 * TS_PACKET_SIZE));
+        data.extend(ts_packet(0x101, false, 0x11, &[]));
+
+        let mut processor = PacketProcessor::new(true);
+        processor.error_capture_limit = 4;
+        processor.process_buffer(&data, Some(AnalysisMode::Tr101Priority1));
+        let tr101 = processor.get_tr101_metrics();
+        assert_eq!((tr101.ts_sync_loss, tr101.sync_loss_episodes), (1, 1));
+        assert_eq!(tr101.sync_byte_errors, 5);
+        assert_eq!(tr101.transport_error_indicator, 0);
+        assert_eq!(tr101.continuity_counter_errors, 0);
+        assert!(processor.error_packets.iter().all(|p| p.packet[0] == TS_SYNC_BYTE));
+        assert_eq!(processor.total_bytes_processed, data.len() as u64);
+
+        // A single corrupted sync byte is a sync_byte_error but no sync loss
+        let mut corrupted = ts_packet(0x101, false, 0x12, &[]);
+        corrupted[0] = 0x46;
+        let data = [corrupted, ts_packet(0x101, false, 0x13, &[])].concat();
+        processor.process_buffer(&data, Some(AnalysisMode::Tr101Priority1));
+        let tr101 = processor.get_tr101_metrics();
+        assert_eq!(tr101.sync_byte_errors, 6);
+        assert_eq!((tr101.ts_sync_loss, tr101.sync_loss_episodes), (1, 1));
+    }
+
+    #[test]
+    fn partial_packet_is_dropped_when_input_resumes() {
+        let mut processor = PacketProcessor::new(true);
+        processor.process_buffer(&valid_psi(), Some(AnalysisMode::Tr101Priority1));
+        // Stopped half-way through a packet; input resumes at a packet boundary
+        processor.process_buffer(&ts_packet(0x101, false, 0x15, &[])[..100], Some(AnalysisMode::Tr101Priority1));
+        processor.discard_partial_packet();
+        let resumed: Vec<u8> = (0..4).flat_map(|cc| ts_packet(0x101, false, 0x10 | cc, &[])).collect();
+        processor.process_buffer(&resumed, Some(AnalysisMode::Tr101Priority1));
+
+        let tr101 = processor.get_tr101_metrics();
+        assert_eq!(tr101.continuity_counter_errors, 0);
+        assert_eq!(processor.total_bytes_processed, 6 * TS_PACKET_SIZE as u64);
+    }
+
+    #[test]
+    fn program_leaving_the_pat_is_forgotten() {
+        let pat_v0 = section_payload(0x00, 1, 0, &[0x00, 0x01, 0xE1, 0x00, 0x00, 0x02, 0xE2, 0x00]);
+        // Program 2 has its own PCR PID and puts MPEG-1 audio on program 1's AAC PID
+        let pmt_2 = section_payload(0x02, 2, 0, &[
+            0xE2, 0x01, 0xF0, 0x00,
+            0x1B, 0xE2, 0x01, 0xF0, 0x00,
+            0x03, 0xE1, 0x02, 0xF0, 0x00,
+        ]);
+        let pcr = [0xB7, 0x10, 0, 0, 0, 0, 0, 0];
+        let mut data = valid_psi();
+        data.extend(ts_packet(0x0000, true, 0x11, &pat_v0));
+        data.extend(ts_packet(0x0200, true, 0x10, &pmt_2));
+        data.extend(ts_packet(0x0201, false, 0x20, &pcr));
+
+        let mut processor = PacketProcessor::new(true);
+        processor.process_buffer(&data, Some(AnalysisMode::Tr101));
+        assert!(processor.pmt_map.contains_key(&0x200));
+        assert!(processor.last_pcr.contains_key(&0x201));
+        assert!(!processor.program_pid_collisions().is_empty());
+
+        let pat_v1 = section_payload(0x00, 1, 1, &[0x00, 0x01, 0xE1, 0x00]);
+        processor.process_buffer(&ts_packet(0x0000, true, 0x12, &pat_v1), Some(AnalysisMode::Tr101));
+        assert!(!processor.pmt_map.contains_key(&0x200));
+        assert!(!processor.si_cache.pmts.contains_key(&0x200));
+        assert!(!processor.declared_pids.contains_key(&0x201));
+        assert!(processor.declared_pids.contains_key(&0x102));
+        assert!(!processor.last_pcr.contains_key(&0x201));
+        assert!(!processor.pcr_timing.contains_key(&0x201));
+        assert!(processor.program_pid_collisions().is_empty());
+        assert!(processor.pmt_map.contains_key(&0x100));
+    }
+
+    /// EIT-actual schedule section for `service_id` with one-hour events starting at the
+    /// given hours of MJD 60000 (2023-02-25)
+    fn eit_schedule(service_id: u16, start_hours: &[u8]) -> Vec<u8> {
+        let mut body = vec![0x00, 0x01, 0x00, 0x01, 0x00, 0x50];
+        for (event_id, &hour) in start_hours.iter().enumerate() {
+            let bcd = ((hour / 10) << 4) | (hour % 10);
+            body.extend_from_slice(&[0x00, event_id as u8, 0xEA, 0x60, bcd, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00]);
+        }
+        section_payload(0x50, service_id, 0, &body)
+    }
+
+    #[test]
+    fn epg_forgets_ended_events_and_removed_services() {
+        let pat_v0 = section_payload(0x00, 1, 0, &[0x00, 0x01, 0xE1, 0x00, 0x00, 0x02, 0xE2, 0x00]);
+        let mut data = ts_packet(0x0000, true, 0x10, &pat_v0);
+        data.extend(ts_packet(0x0012, true, 0x10, &eit_schedule(1, &[10, 12])));
+        data.extend(ts_packet(0x0012, true, 0x11, &eit_schedule(2, &[10])));
+        let mut processor = PacketProcessor::new(true);
+        processor.process_buffer(&data, Some(AnalysisMode::Mux));
+        assert_eq!(processor.epg_coverage(1).map(|c| c.events), Some(2));
+        assert_eq!(processor.epg_coverage(2).map(|c| c.events), Some(1));
+
+        // TDT at 11:30: the 10:00-11:00 events have ended
+        let tdt = [0x00, 0x70, 0x70, 0x05, 0xEA, 0x60, 0x11, 0x30, 0x00];
+        processor.process_buffer(&ts_packet(0x0014, true, 0x10, &tdt), Some(AnalysisMode::Mux));
+        assert_eq!(processor.epg_coverage(1).map(|c| c.events), Some(1));
+        assert!(processor.epg_coverage(2).is_none());
+
+        // An ended event arriving late is not added back
+        processor.process_buffer(&ts_packet(0x0012, true, 0x12, &eit_schedule(1, &[9, 12])), Some(AnalysisMode::Mux));
+        assert_eq!(processor.epg_coverage(1).map(|c| c.events), Some(1));
+
+        // Program 1 leaves the PAT
+        processor.process_buffer(&ts_packet(0x0012, true, 0x13, &eit_schedule(2, &[13])), Some(AnalysisMode::Mux));
+        let pat_v1 = section_payload(0x00, 1, 1, &[0x00, 0x02, 0xE2, 0x00]);
+        processor.process_buffer(&ts_packet(0x0000, true, 0x11, &pat_v1), Some(AnalysisMode::Mux));
+        assert!(processor.epg_coverage(1).is_none());
+        assert_eq!(processor.epg_coverage(2).map(|c| c.events), Some(1));
+    }
+
+    #[test]
+    fn stream_mismatches_count_classes_against_the_template() {
+        let mut processor = PacketProcessor::new(true);
+        processor.process_buffer(&valid_psi(), Some(AnalysisMode::Mux));
+        let pmt = processor.pmt_map[&0x100].clone();
+
+        // H.264, AAC and AC-3 in a private stream
+        processor.expected_streams.insert(1, StreamTemplate { video: Some(1), audio: Some(2), ..Default::default() });
+        assert!(processor.stream_mismatches(&pmt).is_empty());
+
+        processor.expected_streams.insert(1, StreamTemplate { audio: Some(3), subtitle: Some(0), ..Default::default() });
+        assert_eq!(processor.stream_mismatches(&pmt), vec![StreamCountMismatch { class: StreamClass::Audio, expected: 3, found: 2 }]);
+    }
+
+    #[test]
+    fn scte35_counts_as_data() {
+        let pmt = section_payload(0x02, 1, 1, &[
+            0xE1, 0x01, 0xF0, 0x00,
+            0x1B, 0xE1, 0x01, 0xF0, 0x00,
+            0x0F, 0xE1, 0x02, 0xF0, 0x00,
+            0x86, 0xE1, 0x04, 0xF0, 0x00,
+        ]);
+        let mut data = valid_psi();
+        data.extend(ts_packet(0x0100, true, 0x11, &pmt));
+        let mut processor = PacketProcessor::new(true);
+        processor.process_buffer(&data, Some(AnalysisMode::Mux));
+        let pmt = processor.pmt_map[&0x100].clone();
+
+        processor.expected_streams.insert(1, StreamTemplate { video: Some(1), audio: Some(1), data: Some(1), ..Default::default() });
+        assert!(processor.stream_mismatches(&pmt).is_empty());
+    }
+
+    #[test]
+    fn pts_estimate_of_25_over_1001_fps() {
+        // 25/1.001 fps frames are 3603.6 ticks apart; the median delta is 3603 or 3604
+        let signaled = 25000.0 / 1001.0;
+        for delta in [3603.6_f32, 3603.0, 3604.0] {
+            let fps_est = 90000.0 / delta;
+            assert_eq!(round_to_common_fps(fps_est), 25.0, "{fps_est}");
+            // Close enough that the PTS estimate leaves a signaled 25/1.001 alone
+            assert!((signaled - fps_est).abs() < DEFAULT_FPS_PTS_TOLERANCE, "{fps_est}");
+        }
+    }
+
+    /// Payload bytes that start like a PES header or a section half of the time
+    fn payload() -> impl Strategy<Value = Vec<u8>> {
+        prop_oneof![
+            vec(any::<u8>(), 184),
+            vec(any::<u8>(), 175).prop_map(|rest| [&[0, 0, 1, 0xE0, 0, 0, 0x80, 0xC0, 0x0A][..], &rest].concat()),
+            vec(any::<u8>(), 183).prop_map(|rest| [&[0][..], &rest].concat()),
+        ]
+    }
+
+    proptest! {
+        #[test]
+        fn arbitrary_bytes_do_not_panic(
+            data in vec(any::<u8>(), 0..4096),
+            split in any::<proptest::sample::Index>(),
+            mode in proptest::sample::select(&MODES[..]),
+        ) {
+            let mut processor = PacketProcessor::new(true);
+            let (first, second) = data.split_at(split.index(data.len() + 1));
+            processor.process_buffer(first, Some(mode));
+            processor.process_buffer(second, Some(mode));
+        }
+
+        /// Packets with a sync byte but random flags, adaptation fields and payloads on
+        /// PIDs the PSI declared, ending in a partial packet
+        #[test]
+        fn random_packets_after_valid_psi_do_not_panic(
+            packets in vec((proptest::sample::select(&PIDS[..]), any::<bool>(), any::<u8>(), payload()), 1..64),
+            cut in 0..TS_PACKET_SIZE,
+            mode in proptest::sample::select(&MODES[..]),
+        ) {
+            let mut data = valid_psi();
+            for (pid, pusi, byte3, payload) in &packets {
+                data.extend(ts_packet(*pid, *pusi, *byte3, payload));
+            }
+            data.truncate(data.len() - cut);
+
+            let mut processor = PacketProcessor::new(true);
+            processor.error_capture_limit = 4;
+            for chunk in data.chunks(7 * TS_PACKET_SIZE + 13) {
+                processor.process_buffer(chunk, Some(mode));
+            }
+        }
+    }
+}
@@ -1,12 +1,14 @@
+use crate::psi::descriptor::{parse_descriptors, Descriptor};
 use crate::psi::section::SectionReader;
 #[derive(Clone)]
 pub struct CatSection {
     pub version: u8,
+    pub descriptors: Vec<Descriptor>,   // CA_descriptors (0x09) naming the EMM PIDs
 }
 pub fn parse_cat(payload: &[u8]) -> anyhow::Result<(u8, CatSection)> {
-    let sec = SectionReader::new(payload)?;          // CRC verified
+    let sec = SectionReader::new(payload, true)?;          // CRC verified
     if sec.table_id != 0x01 {
         anyhow::bail!("not CAT");
     }
-    Ok((sec.table_id, CatSection { version: sec.version }))
-}
\ No newline at end of file
+    Ok((sec.table_id, CatSection { version: sec.version, descriptors: parse_descriptors(sec.body) }))
+}
@@ -0,0 +1,350 @@
+// psi/descriptor.rs
+//! Generic descriptor loop reader (tag / length / payload).
+
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::types::{LogicalChannel, SmoothingBuffer, SubtitlePage};
+
+/// Registration descriptor format_identifier for AV1 ("AV01")
+pub const FORMAT_ID_AV1: [u8; 4] = *b"AV01";
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Descriptor {
+    pub tag:  u8,
+    pub data: Vec<u8>,
+    /// private_data_specifier in effect at this point of the loop: set by the last
+    /// private_data_specifier_descriptor (0x5F) before it, which also carries its own
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private_data_specifier: Option<u32>,
+}
+
+impl Descriptor {
+    /// Whether this is the private descriptor `tag` defined by one of `specifiers`.
+    /// Private tags (0x80-0xFE) are only meaningful under their specifier; a loop
+    /// without any private_data_specifier_descriptor is taken at face value.
+    pub fn is_private(&self, tag: u8, specifiers: &[u32]) -> bool {
+        self.tag == tag && self.private_data_specifier.is_none_or(|pds| specifiers.contains(&pds))
+    }
+}
+
+/// Splits a descriptor loop; stops at the first descriptor whose length overruns the loop.
+/// Each descriptor records the private_data_specifier in effect for it.
+pub fn parse_descriptors(buf: &[u8]) -> Vec<Descriptor> {
+    let mut out = Vec::new();
+    let mut idx = 0;
+    let mut specifier = None;
+    while idx + 2 <= buf.len() {
+        let tag = buf[idx];
+        let len = buf[idx + 1] as usize;
+        if idx + 2 + len > buf.len() { break; }   // graceful exit on malformed len
+        let data = buf[idx + 2 .. idx + 2 + len].to_vec();
+        if tag == 0x5F && data.len() >= 4 {
+            specifier = Some(u32::from_be_bytes([data[0], data[1], data[2], data[3]]));
+        }
+        out.push(Descriptor { tag, data, private_data_specifier: specifier });
+        idx += 2 + len;
+    }
+    out
+}
+
+/// format_identifier of the first registration_descriptor (tag 0x05), if any.
+pub fn registration(descriptors: &[Descriptor]) -> Option<[u8; 4]> {
+    descriptors
+        .iter()
+        .find(|d| d.tag == 0x05 && d.data.len() >= 4)
+        .map(|d| [d.data[0], d.data[1], d.data[2], d.data[3]])
+}
+
+/// CA_system_ids of all CA_descriptors (tag 0x09), in loop order.
+pub fn ca_system_ids(descriptors: &[Descriptor]) -> Vec<u16> {
+    descriptors
+        .iter()
+        .filter(|d| d.tag == 0x09 && d.data.len() >= 2)
+        .map(|d| u16::from_be_bytes([d.data[0], d.data[1]]))
+        .collect()
+}
+
+/// maximum_bitrate (tag 0x0E) in kbps; the field counts units of 50 bytes/s
+pub fn max_bitrate_kbps(descriptors: &[Descriptor]) -> Option<f64> {
+    descriptors
+        .iter()
+        .find(|d| d.tag == 0x0E && d.data.len() >= 3)
+        .map(|d| u22(&d.data[0..3]) as f64 * 50.0 * 8.0 / 1000.0)
+}
+
+/// smoothing_buffer (tag 0x10): sb_leak_rate in units of 400 bit/s, sb_size in bytes
+pub fn smoothing_buffer(descriptors: &[Descriptor]) -> Option<SmoothingBuffer> {
+    descriptors
+        .iter()
+        .find(|d| d.tag == 0x10 && d.data.len() >= 6)
+        .map(|d| SmoothingBuffer {
+            leak_rate_kbps: u22(&d.data[0..3]) as f64 * 400.0 / 1000.0,
+            size_bytes: u22(&d.data[3..6]),
+        })
+}
+
+/// private_data_specifier values under which 0x83/0x88 are LCN descriptors:
+/// EACEM/EICTA, NorDig, UK DTG, Free TV Australia
+const LCN_PRIVATE_DATA_SPECIFIERS: [u32; 4] = [0x0000_0028, 0x0000_0029, 0x0000_233A, 0x0000_3200];
+const NORDIG_PRIVATE_DATA_SPECIFIER: u32 = 0x0000_0029;
+
+/// service_id → logical channel from the LCN descriptors with `tag` (0x83, or 0x88 for
+/// HD simulcast) in a NIT transport descriptor loop. These are private descriptors, so
+/// they only count under a private_data_specifier naming one of the LCN specs, or when
+/// the loop has none at all. NorDig uses 14-bit channel numbers, the others 10 bits.
+pub fn logical_channels(descriptors: &[Descriptor], tag: u8) -> HashMap<u16, LogicalChannel> {
+    let mut channels = HashMap::new();
+    for d in descriptors.iter().filter(|d| d.is_private(tag, &LCN_PRIVATE_DATA_SPECIFIERS)) {
+        let mask = if d.private_data_specifier == Some(NORDIG_PRIVATE_DATA_SPECIFIER) { 0x3FFF } else { 0x03FF };
+        for e in d.data.chunks_exact(4) {
+            channels.insert(u16::from_be_bytes([e[0], e[1]]), LogicalChannel {
+                number: u16::from_be_bytes([e[2], e[3]]) & mask,
+                visible: e[2] & 0x80 != 0,
+                private_data_specifier: d.private_data_specifier,
+            });
+        }
+    }
+    channels
+}
+
+/// 22-bit value after 2 reserved bits
+fn u22(b: &[u8]) -> u32 {
+    (((b[0] & 0x3F) as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32
+}
+
+/// Printable form of a format_identifier, e.g. "AC-3" or "Opus"; non-ASCII bytes become '.'
+pub fn format_identifier_str(id: [u8; 4]) -> String {
+    id.iter()
+        .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+        .collect()
+}
+
+/// Language/page entries of all DVB subtitling_descriptors (tag 0x59), in loop order.
+pub fn subtitle_pages(descriptors: &[Descriptor]) -> Vec<SubtitlePage> {
+    descriptors
+        .iter()
+        .filter(|d| d.tag == 0x59)
+        .flat_map(|d| d.data.chunks_exact(8))
+        .map(|e| SubtitlePage {
+            language: String::from_utf8_lossy(&e[0..3]).into_owned(),
+            subtitling_type: e[3],
+            kind: subtitling_type_name(e[3]),
+            hard_of_hearing: (0x20..=0x25).contains(&e[3]),
+            composition_page_id: u16::from_be_bytes([e[4], e[5]]),
+            ancillary_page_id: u16::from_be_bytes([e[6], e[7]]),
+        })
+        .collect()
+}
+
+/// subtitling_type names (EN 300 468, component_type for stream_content 0x03)
+fn subtitling_type_name(subtitling_type: u8) -> &'static str {
+    match subtitling_type {
+        0x01 => "EBU Teletext",
+        0x02 => "Associated EBU Teletext",
+        0x03 => "VBI data",
+        0x10 => "Normal",
+        0x11 => "Normal (4:3)",
+        0x12 => "Normal (16:9)",
+        0x13 => "Normal (2.21:1)",
+        0x14 => "Normal (HD)",
+        0x15 => "Normal (stereoscopic)",
+        0x20 => "Hard of hearing",
+        0x21 => "Hard of hearing (4:3)",
+        0x22 => "Hard of hearing (16:9)",
+        0x23 => "Hard of hearing (2.21:1)",
+        0x24 => "Hard of hearing (HD)",
+        0x25 => "Hard of hearing (stereoscopic)",
+        0x30 => "Open sign language",
+        0x31 => "Closed sign language",
+        _ => "Reserved",
+    }
+}
+
+/// DVB AC-3_descriptor (tag 0x6A) or enhanced_AC-3_descriptor (tag 0x7A), EN 300 468
+/// Annex D; each field is present only when its flag is set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ac3Descriptor {
+    pub enhanced: bool,              // Tag 0x7A
+    pub component_type: Option<u8>,  // Table D.1: E-AC-3 flag, full service, service type, channels
+    pub bsid: Option<u8>,
+    pub mainid: Option<u8>,
+    pub asvc: Option<u8>,
+}
+
+/// The first AC-3 or enhanced_AC-3 descriptor in a PMT ES loop
+pub fn ac3_descriptor(descriptors: &[Descriptor]) -> Option<Ac3Descriptor> {
+    let d = descriptors.iter().find(|d| matches!(d.tag, 0x6A | 0x7A) && !d.data.is_empty())?;
+    let flags = d.data[0];
+    // Optional bytes follow in flag order: component_type, bsid, mainid, asvc
+    let mut bytes = d.data[1..].iter().copied();
+    let mut field = |mask: u8| if flags & mask != 0 { bytes.next() } else { None };
+    Some(Ac3Descriptor {
+        enhanced: d.tag == 0x7A,
+        component_type: field(0x80),
+        bsid: field(0x40),
+        mainid: field(0x20),
+        asvc: field(0x10),
+    })
+}
+
+/// component_tag of the stream_identifier_descriptor (tag 0x52) in a PMT ES loop
+pub fn component_tag(descriptors: &[Descriptor]) -> Option<u8> {
+    descriptors.iter().find(|d| d.tag == 0x52 && !d.data.is_empty()).map(|d| d.data[0])
+}
+
+/// One component_descriptor (tag 0x50) from an EIT event or SDT service loop
+#[derive(Debug, Clone, Serialize)]
+pub struct Component {
+    pub stream_content: u8,
+    pub stream_content_ext: u8,
+    pub component_type: u8,
+    pub component_tag: u8,
+    pub language: String,
+    pub text: String,
+}
+
+impl Component {
+    /// Human-readable description, e.g. "Audio: Dolby 5.1" or "Video: HD 16:9". The
+    /// broadcaster's text wins over the EN 300 468 Table 26 name of component_type.
+    pub fn description(&self) -> Option<String> {
+        let (kind, name) = component_type_name(self.stream_content, self.stream_content_ext, self.component_type);
+        let text = if self.text.trim().is_empty() { name?.to_string() } else { self.text.trim().to_string() };
+        Some(match kind {
+            Some(kind) => format!("{kind}: {text}"),
+            None => text,
+        })
+    }
+}
+
+/// All component_descriptors (tag 0x50), in loop order
+pub fn components(descriptors: &[Descriptor]) -> Vec<Component> {
+    descriptors
+        .iter()
+        .filter(|d| d.tag == 0x50 && d.data.len() >= 6)
+        .map(|d| Component {
+            stream_content: d.data[0] & 0x0F,
+            stream_content_ext: d.data[0] >> 4,
+            component_type: d.data[1],
+            component_tag: d.data[2],
+            language: String::from_utf8_lossy(&d.data[3..6]).into_owned(),
+            text: dvb_text(&d.data[6..]),
+        })
+        .collect()
+}
+
+/// DVB string with a leading character table selector (EN 300 468 Annex A) skipped,
+/// the rest decoded lossily
+pub fn dvb_text(mut b: &[u8]) -> String {
+    match b.first() {
+        Some(0x10) => b = b.get(3..).unwrap_or_default(),
+        Some(0x1F) => b = b.get(2..).unwrap_or_default(),
+        Some(&x) if x < 0x20 => b = &b[1..],
+        _ => {}
+    }
+    String::from_utf8_lossy(b).into_owned()
+}
+
+/// Kind and name of a component (EN 300 468 Table 26); the 25/30 Hz and pan-vector
+/// variants are folded together
+fn component_type_name(content: u8, ext: u8, ty: u8) -> (Option<&'static str>, Option<&'static str>) {
+    match (content, ext) {
+        (0x01, _) => (Some("Video"), match ty {
+            0x01 | 0x05 => Some("SD 4:3"),
+            0x02 | 0x03 | 0x06 | 0x07 => Some("SD 16:9"),
+            0x04 | 0x08 => Some("SD >16:9"),
+            0x09 | 0x0D => Some("HD 4:3"),
+            0x0A | 0x0B | 0x0E | 0x0F => Some("HD 16:9"),
+            0x0C | 0x10 => Some("HD >16:9"),
+            _ => None,
+        }),
+        (0x05, _) => (Some("Video"), match ty {
+            0x01 | 0x05 => Some("SD 4:3"),
+            0x03 | 0x07 => Some("SD 16:9"),
+            0x04 | 0x08 => Some("SD >16:9"),
+            0x0B | 0x0F => Some("HD 16:9"),
+            0x0C | 0x10 => Some("HD >16:9"),
+            0x80..=0x84 => Some("HD 3D"),
+            _ => None,
+        }),
+        (0x09, 0x00) => (Some("Video"), match ty {
+            0x00 | 0x01 => Some("HEVC HD"),
+            0x02..=0x06 => Some("HEVC UHD"),
+            _ => None,
+        }),
+        (0x02 | 0x06, _) => (Some("Audio"), match ty {
+            0x01 => Some("mono"),
+            0x02 => Some("dual mono"),
+            0x03 | 0x43 => Some("stereo"),
+            0x04 => Some("multi-lingual, multi-channel"),
+            0x05 => Some("surround"),
+            0x40 | 0x47 | 0x48 => Some("audio description"),
+            0x41 => Some("hard of hearing"),
+            _ => None,
+        }),
+        // AC-3: component_type is a bit field (Table D.1); bit 7 marks E-AC-3, bit 6 a full
+        // service. Layout 4 is any multichannel mix of more than two channels up to 5.1,
+        // named for its usual 5.1 case; layout 5 is more than 5.1.
+        (0x04, _) => (Some("Audio"), Some(match (ty & 0x80 != 0, ty & 0x07) {
+            (false, 0) => "Dolby mono",
+            (false, 1) => "Dolby dual mono",
+            (false, 2 | 3) => "Dolby stereo",
+            (false, 4) => "Dolby 5.1",
+            (false, 5) => "Dolby >5.1",
+            (false, _) => "Dolby",
+            (true, 0) => "Dolby Digital Plus mono",
+            (true, 1) => "Dolby Digital Plus dual mono",
+            (true, 2 | 3) => "Dolby Digital Plus stereo",
+            (true, 4) => "Dolby Digital Plus 5.1",
+            (true, 5) => "Dolby Digital Plus >5.1",
+            (true, _) => "Dolby Digital Plus",
+        })),
+        (0x07, _) => (Some("Audio"), Some("DTS")),
+        (0x09, 0x01) => (Some("Audio"), Some("AC-4")),
+        (0x03, _) => (Some("Subtitles"), Some(subtitling_type_name(ty))),
+        _ => (None, None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parsers::ac3_descriptor_info;
+
+    fn descriptor(tag: u8, data: &[u8]) -> Descriptor {
+        Descriptor { tag, data: data.to_vec(), private_data_specifier: None }
+    }
+
+    #[test]
+    fn ac3_descriptor_fields_follow_their_flags() {
+        let only_bsid = ac3_descriptor(&[descriptor(0x6A, &[0x40, 0x08])]).expect("AC-3 descriptor");
+        assert_eq!(only_bsid, Ac3Descriptor { enhanced: false, component_type: None, bsid: Some(8), mainid: None, asvc: None });
+
+        let eac3 = ac3_descriptor(&[descriptor(0x52, &[0x01]), descriptor(0x7A, &[0xC0, 0xC2, 0x10])]).expect("E-AC-3 descriptor");
+        assert_eq!(eac3, Ac3Descriptor { enhanced: true, component_type: Some(0xC2), bsid: Some(0x10), mainid: None, asvc: None });
+
+        let no_fields = ac3_descriptor(&[descriptor(0x6A, &[0x00])]).expect("AC-3 descriptor");
+        assert_eq!((no_fields.component_type, no_fields.bsid), (None, None));
+        // Not even the flags byte
+        assert_eq!(ac3_descriptor(&[descriptor(0x6A, &[])]), None);
+    }
+
+    #[test]
+    fn ac3_descriptor_info_agrees_with_the_component_name() {
+        for ty in [0x40, 0x41, 0x42, 0x44, 0x45, 0xC0, 0xC2, 0xC4, 0x4A, 0x82] {
+            let info = ac3_descriptor_info(&Ac3Descriptor { enhanced: false, component_type: Some(ty), bsid: None, mainid: None, asvc: None });
+            let (_, name) = component_type_name(0x04, 0, ty);
+            let name = name.expect("AC-3 component name");
+            assert_eq!(info.codec == "E-AC-3", name.starts_with("Dolby Digital Plus"), "{ty:#04x} {name}");
+            assert_eq!(info.channels == Some(2), name.ends_with("stereo") || name.ends_with("dual mono"), "{ty:#04x} {name}");
+            assert_eq!(info.channels == Some(1), name.ends_with(" mono") && !name.ends_with("dual mono"), "{ty:#04x} {name}");
+        }
+    }
+
+    #[test]
+    fn ac3_component_type_tells_e_ac3_from_full_service() {
+        assert_eq!(component_type_name(0x04, 0, 0x44), (Some("Audio"), Some("Dolby 5.1")));
+        assert_eq!(component_type_name(0x04, 0, 0xC4), (Some("Audio"), Some("Dolby Digital Plus 5.1")));
+        assert_eq!(component_type_name(0x04, 0, 0x42), (Some("Audio"), Some("Dolby stereo")));
+        assert_eq!(component_type_name(0x04, 0, 0x85), (Some("Audio"), Some("Dolby Digital Plus >5.1")));
+    }
+}
@@ -1,15 +1,126 @@
 // psi/eit.rs
-//! Minimal EIT p/f (table_ids 0x4E / 0x4F) CRC validation.
+//! EIT p/f (table_ids 0x4E / 0x4F) CRC validation and EIT schedule
+//! (0x50-0x5F actual, 0x60-0x6F other) event parsing.
 
+use super::descriptor::{components, dvb_text, parse_descriptors, Component};
 use super::section::SectionReader;
 
+/// One EIT p/f section: section 0 carries the present event, section 1 the following one
 #[derive(Clone)]
-pub struct EitPfSection { pub version: u8, }
+pub struct EitPfSection {
+    pub version: u8,
+    pub service_id: u16,
+    pub section_number: u8,
+    pub events: Vec<EitEvent>,
+}
 
 pub fn parse_eit_pf(payload: &[u8]) -> anyhow::Result<(u8, EitPfSection)> {
-    let sec = SectionReader::new(payload)?;
+    let sec = SectionReader::new(payload, true)?;
     if sec.table_id != 0x4E && sec.table_id != 0x4F {
         anyhow::bail!("not EIT p/f");
     }
-    Ok((sec.table_id, EitPfSection { version: sec.version }))
-}
\ No newline at end of file
+    Ok((sec.table_id, EitPfSection {
+        version: sec.version,
+        service_id: sec.program_number,   // table_id_extension
+        section_number: sec.section_number,
+        events: parse_events(sec.body.get(6..).unwrap_or_default()),
+    }))
+}
+
+/// One EIT schedule section
+#[derive(Clone)]
+pub struct EitScheduleSection {
+    pub table_id: u8,
+    pub service_id: u16,
+    pub version: u8,
+    pub section_number: u8,
+    pub last_section: u8,
+    pub segment_last_section: u8,   // Last section_number of this section's 8-section segment
+    pub last_table_id: u8,
+    pub transport_stream_id: u16,
+    pub original_network_id: u16,
+    pub events: Vec<EitEvent>,
+}
+
+#[derive(Clone)]
+pub struct EitEvent {
+    pub event_id: u16,
+    pub start: Option<i64>,       // Unix seconds, None if undefined (all bits set)
+    pub duration_secs: u32,
+    pub name: Option<String>,     // short_event_descriptor (0x4D) event_name
+    pub components: Vec<Component>,
+}
+
+pub fn parse_eit_schedule(payload: &[u8]) -> anyhow::Result<EitScheduleSection> {
+    let sec = SectionReader::new(payload, true)?;
+    if !(0x50..=0x6F).contains(&sec.table_id) {
+        anyhow::bail!("not EIT schedule");
+    }
+
+    let b = sec.body;
+    if b.len() < 6 {
+        anyhow::bail!("EIT body too short");
+    }
+    let transport_stream_id = u16::from_be_bytes([b[0], b[1]]);
+    let original_network_id = u16::from_be_bytes([b[2], b[3]]);
+    let events = parse_events(&b[6..]);
+
+    Ok(EitScheduleSection {
+        table_id: sec.table_id,
+        service_id: sec.program_number,   // table_id_extension
+        version: sec.version,
+        section_number: sec.section_number,
+        last_section: sec.last_section,
+        segment_last_section: b[4],
+        last_table_id: b[5],
+        transport_stream_id,
+        original_network_id,
+        events,
+    })
+}
+
+/// Event loop shared by p/f and schedule sections
+fn parse_events(b: &[u8]) -> Vec<EitEvent> {
+    let mut events = Vec::new();
+    let mut idx = 0;
+    while idx + 12 <= b.len() {
+        let e = &b[idx..idx + 12];
+        let desc_len = (((e[10] & 0x0F) as usize) << 8) | e[11] as usize;
+        let desc_end = idx + 12 + desc_len;
+        if desc_end > b.len() { break; }   // graceful exit on malformed len
+        let descriptors = parse_descriptors(&b[idx + 12..desc_end]);
+        events.push(EitEvent {
+            event_id: u16::from_be_bytes([e[0], e[1]]),
+            start: dvb_time(&e[2..7]),
+            duration_secs: bcd_hms(&e[7..10]).unwrap_or(0),
+            name: descriptors.iter().find(|d| d.tag == 0x4D).and_then(|d| short_event_name(&d.data)),
+            components: components(&descriptors),
+        });
+        idx = desc_end;
+    }
+    events
+}
+
+/// EN 300 468 Annex C: 16-bit MJD + 6 BCD digits (hhmmss), as Unix seconds
+pub(crate) fn dvb_time(b: &[u8]) -> Option<i64> {
+    if b.iter().all(|&x| x == 0xFF) {
+        return None;
+    }
+    let mjd = u16::from_be_bytes([b[0], b[1]]) as i64;
+    Some((mjd - 40587) * 86_400 + bcd_hms(&b[2..5])? as i64)
+}
+
+/// hh:mm:ss in BCD → seconds
+fn bcd_hms(b: &[u8]) -> Option<u32> {
+    let digit = |x: u8| -> Option<u32> {
+        let (hi, lo) = (x >> 4, x & 0x0F);
+        (hi < 10 && lo < 10).then_some((hi * 10 + lo) as u32)
+    };
+    Some(digit(b[0])? * 3600 + digit(b[1])? * 60 + digit(b[2])?)
+}
+
+/// event_name of a short_event_descriptor, see [`dvb_text`]
+fn short_event_name(d: &[u8]) -> Option<String> {
+    let len = *d.get(3)? as usize;
+    Some(dvb_text(d.get(4..4 + len)?))
+}
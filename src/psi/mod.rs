@@ -6,12 +6,65 @@ pub mod cat;
 pub mod section;
 pub mod pat;
 pub mod pmt;
+pub mod descriptor;
 
 pub use nit::parse_nit;
-pub use eit::parse_eit_pf;
+pub use eit::{parse_eit_pf, parse_eit_schedule};
 pub use tdt::parse_tdt_tot;
 pub use sdt::parse_sdt;
 pub use cat::parse_cat;
 // pub use cat::CatSection;  // Currently unused
 pub use pat::{parse_pat, PatSection};
-pub use pmt::{parse_pmt, PmtSection};
\ No newline at end of file
+pub use pmt::{parse_pmt, PmtSection};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::psi::section::tests::section_payload;
+    use proptest::collection::vec;
+    use proptest::prelude::*;
+
+    /// Run every PSI/SI parser over `payload`; only panics matter here
+    fn parse_all(payload: &[u8]) {
+        let _ = parse_pat(payload);
+        let _ = parse_pmt(payload);
+        let _ = parse_cat(payload);
+        let _ = parse_nit(payload);
+        let _ = parse_sdt(payload);
+        let _ = parse_eit_pf(payload);
+        let _ = parse_eit_schedule(payload);
+        let _ = parse_tdt_tot(payload);
+        let _ = section::peek_section(payload);
+    }
+
+    const TABLE_IDS: [u8; 12] = [0x00, 0x01, 0x02, 0x40, 0x41, 0x42, 0x46, 0x4E, 0x4F, 0x50, 0x70, 0x73];
+
+    proptest! {
+        #[test]
+        fn arbitrary_payloads_do_not_panic(payload in vec(any::<u8>(), 0..512)) {
+            parse_all(&payload);
+        }
+
+        /// Sections with a valid CRC and a random body get past the CRC check, so every
+        /// length field and descriptor loop inside the parsers is exercised; cutting them
+        /// short covers the truncated case
+        #[test]
+        fn random_and_truncated_sections_do_not_panic(
+            table_id in proptest::sample::select(&TABLE_IDS[..]),
+            extension in any::<u16>(),
+            body in vec(any::<u8>(), 0..400),
+            cut in any::<proptest::sample::Index>(),
+        ) {
+            let payload = section_payload(table_id, extension, 0, &body);
+            parse_all(&payload);
+            parse_all(&payload[..cut.index(payload.len())]);
+
+            let mut assembler = section::SectionAssembler::default();
+            for (i, chunk) in payload.chunks(184).enumerate() {
+                for section in assembler.push(i == 0, chunk) {
+                    parse_all(&section);
+                }
+            }
+        }
+    }
+}
@@ -1,46 +1,60 @@
 // psi/nit.rs
 //! Very-light Network Information Table parser (actual network, tid 0x40)
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::psi::descriptor::{logical_channels, parse_descriptors};
 use crate::psi::section::SectionReader;
+use crate::types::LogicalChannel;
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct NitSection {
     pub version: u8,
     pub network_id: u16,
     pub transports: Vec<Transport>,
 }
 
-#[derive(Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct Transport {
     pub ts_id: u16,
     pub orig_net_id: u16,
+    pub logical_channels: HashMap<u16, LogicalChannel>,    // service_id → LCN (0x83)
+    pub hd_logical_channels: HashMap<u16, LogicalChannel>, // service_id → HD simulcast LCN (0x88)
 }
 
 pub fn parse_nit(payload: &[u8]) -> anyhow::Result<(u8, NitSection)> {
 
-    let sec = SectionReader::new(payload)?;
+    let sec = SectionReader::new(payload, true)?;
     if sec.table_id != 0x40 && sec.table_id != 0x41 {
         anyhow::bail!("not NIT");
     }
 
-    let b = sec.body;                 // shorthand – already stripped CRC
-    if b.len() < 8 {
+    let b = sec.body;                 // shorthand – already stripped header and CRC
+    if b.len() < 4 {
         anyhow::bail!("NIT body too short");
     }
 
-    let network_id = u16::from_be_bytes([b[0], b[1]]);
-    let net_desc_len = (((b[2] & 0x0F) as usize) << 8) | b[3] as usize;
+    let network_id = sec.program_number;  // table_id_extension
+    let net_desc_len = (((b[0] & 0x0F) as usize) << 8) | b[1] as usize;
 
-    let mut idx = 4 + net_desc_len;   // skip network-descriptors
-    if idx > b.len() { anyhow::bail!("truncated network descriptors"); }
+    let mut idx = 2 + net_desc_len;   // skip network-descriptors
+    if idx + 2 > b.len() { anyhow::bail!("truncated network descriptors"); }
+    idx += 2;                         // transport_stream_loop_length
 
     let mut transports = Vec::new();
     while idx + 6 <= b.len() {
         let ts_id       = u16::from_be_bytes([b[idx], b[idx + 1]]);
         let orig_net_id = u16::from_be_bytes([b[idx + 2], b[idx + 3]]);
         let desc_len    = (((b[idx + 4] & 0x0F) as usize) << 8) | b[idx + 5] as usize;
-        idx += 6 + desc_len;
+        let desc_start = idx + 6;
+        idx = desc_start + desc_len;
         if idx > b.len() { break; }   // graceful exit on malformed len
-        transports.push(Transport { ts_id, orig_net_id });
+        let descriptors = parse_descriptors(&b[desc_start..idx]);
+        transports.push(Transport {
+            ts_id,
+            orig_net_id,
+            logical_channels: logical_channels(&descriptors, 0x83),
+            hd_logical_channels: logical_channels(&descriptors, 0x88),
+        });
     }
 
     Ok((
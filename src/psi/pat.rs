@@ -1,31 +1,52 @@
-use crc::{Crc, CRC_32_MPEG_2};
+use serde::Serialize;
 use crate::psi::section::SectionReader;
-const CRC: Crc<u32> = Crc::<u32>::new(&CRC_32_MPEG_2);
 
 /// ─────────── PAT ───────────
-#[derive(Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PatSection {
     pub version:      u8,
     pub current_next: bool,
+    pub transport_stream_id: u16,
+    pub section_number: u8,
+    pub last_section:   u8,
+    pub network_pid:  Option<u16>,   // PID of the program_number 0 entry (NIT)
     pub programs:     Vec<PatEntry>,
 }
-#[derive(Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PatEntry {
     pub program_number: u16,
     pub pmt_pid:        u16,
 }
 
 pub fn parse_pat(payload:&[u8]) -> anyhow::Result<PatSection> {
-    let sec = SectionReader::new(payload)?;
+    let sec = SectionReader::new(payload, true)?;
     if sec.table_id != 0x00 { anyhow::bail!("not PAT"); }
 
     let mut idx = 0;
     let mut programs = Vec::new();
+    let mut network_pid = None;
     while idx + 4 <= sec.body.len() {
         let pn  = u16::from_be_bytes(sec.body[idx..idx+2].try_into()?);
         let pid = (((sec.body[idx+2] & 0x1F) as u16) << 8) | (sec.body[idx+3] as u16);
         idx += 4;
-        if pn != 0 { programs.push(PatEntry{ program_number:pn, pmt_pid:pid }); }
+        if pn == 0 { network_pid = Some(pid); }
+        else { programs.push(PatEntry{ program_number:pn, pmt_pid:pid }); }
+    }
+    Ok(PatSection{ version:sec.version, current_next:sec.current_next,
+                   transport_stream_id:sec.program_number,
+                   section_number:sec.section_number, last_section:sec.last_section,
+                   network_pid, programs })
+}
+
+impl PatSection {
+    /// Join the sections of a multi-section PAT (in section_number order)
+    pub fn merge(sections: Vec<PatSection>) -> PatSection {
+        let mut iter = sections.into_iter();
+        let mut pat = iter.next().expect("at least one PAT section");
+        for sec in iter {
+            pat.network_pid = pat.network_pid.or(sec.network_pid);
+            pat.programs.extend(sec.programs);
+        }
+        pat
     }
-    Ok(PatSection{ version:sec.version, current_next:sec.current_next, programs })
 }
\ No newline at end of file
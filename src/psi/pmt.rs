@@ -1,29 +1,35 @@
-use crc::{Crc, CRC_32_MPEG_2};
+use serde::Serialize;
 use crate::psi::section::SectionReader;
-const CRC: Crc<u32> = Crc::<u32>::new(&CRC_32_MPEG_2);
+use crate::psi::descriptor::{parse_descriptors, registration, Descriptor};
 /// ─────────── PMT ───────────
-#[derive(Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct PmtSection {
     pub version:        u8,
     pub program_number: u16,
     pub pcr_pid:        u16,
+    pub section_number: u8,
+    pub last_section:   u8,
+    pub descriptors:    Vec<Descriptor>,   // program_info loop
     pub streams:        Vec<StreamInfo>,
 }
-#[derive(Clone)]
+#[derive(Debug, Clone, Serialize)]
 pub struct StreamInfo {
     pub stream_type:   u8,
     pub elementary_pid:u16,
+    pub descriptors:   Vec<Descriptor>,
 }
 
 pub fn parse_pmt(payload:&[u8]) -> anyhow::Result<PmtSection> {
-    let sec = SectionReader::new(payload)?;
+    let sec = SectionReader::new(payload, true)?;
     if sec.table_id != 0x02 { anyhow::bail!("not PMT"); }
     let b = sec.body;
+    if b.len() < 4 { anyhow::bail!("PMT body too short"); }
 
     /* ── cabecera fija dentro del cuerpo ── */
     let pcr_pid       = (((b[0] & 0x1F) as u16) << 8) | (b[1] as u16);
     let prog_info_len = (((b[2] & 0x0F) as usize) << 8) | (b[3] as usize);
-    let mut idx       = 4 + prog_info_len;          // saltamos descriptors
+    let descriptors   = parse_descriptors(&b[4 .. (4 + prog_info_len).min(b.len())]);
+    let mut idx       = 4 + prog_info_len;
 
     /* ── bucle de ES ── */
     let mut streams = Vec::new();
@@ -31,12 +37,91 @@ pub fn parse_pmt(payload:&[u8]) -> anyhow::Result<PmtSection> {
         let stype = b[idx];
         let pid   = (((b[idx+1] & 0x1F) as u16) << 8) | (b[idx+2] as u16);
         let eslen = (((b[idx+3] & 0x0F) as usize) << 8) | (b[idx+4] as usize);
-        streams.push(StreamInfo{ stream_type:stype, elementary_pid:pid });
+        let desc_end = idx + 5 + eslen;
+        if desc_end > b.len() { break; }           // graceful exit on malformed ES_info_length
+        let descriptors = parse_descriptors(&b[idx+5 .. desc_end]);
+        streams.push(StreamInfo{ stream_type:stype, elementary_pid:pid, descriptors });
         idx += 5 + eslen;                          // saltamos descriptors ES
     }
 
     Ok(PmtSection{ version:sec.version,
                    program_number:sec.program_number,
                    pcr_pid,
+                   section_number:sec.section_number,
+                   last_section:sec.last_section,
+                   descriptors,
                    streams })
-}
\ No newline at end of file
+}
+
+impl PmtSection {
+    /// Registration format_identifier for an ES: its own descriptor, else the program-level one
+    pub fn stream_registration(&self, stream: &StreamInfo) -> Option<[u8; 4]> {
+        registration(&stream.descriptors).or_else(|| registration(&self.descriptors))
+    }
+
+    /// The elementary PID that also carries the PCR, if PCR_PID isn't a dedicated PID
+    pub fn pcr_shared_with_pid(&self) -> Option<u16> {
+        self.streams.iter().map(|s| s.elementary_pid).find(|&pid| pid == self.pcr_pid)
+    }
+
+    /// Join the sections of a multi-section PMT (in section_number order)
+    pub fn merge(sections: Vec<PmtSection>) -> PmtSection {
+        let mut iter = sections.into_iter();
+        let mut pmt = iter.next().expect("at least one PMT section");
+        for sec in iter {
+            pmt.streams.extend(sec.streams);
+        }
+        pmt
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::psi::section::tests::section_payload;
+
+    const PCR_PID_AND_NO_PROGRAM_INFO: [u8; 4] = [0xE1, 0x01, 0xF0, 0x00];
+
+    /// ES loop entry: stream_type, elementary PID, ES_info_length and the descriptors
+    fn es(stream_type: u8, pid: u16, es_info_length: u16, descriptors: &[u8]) -> Vec<u8> {
+        let mut entry = vec![stream_type];
+        entry.extend_from_slice(&(0xE000 | pid).to_be_bytes());
+        entry.extend_from_slice(&(0xF000 | es_info_length).to_be_bytes());
+        entry.extend_from_slice(descriptors);
+        entry
+    }
+
+    fn pmt(es_loop: &[u8]) -> Vec<u8> {
+        section_payload(0x02, 1, 0, &[&PCR_PID_AND_NO_PROGRAM_INFO[..], es_loop].concat())
+    }
+
+    #[test]
+    fn truncated_section_is_an_error() {
+        let payload = pmt(&[es(0x1B, 0x101, 0, &[]), es(0x0F, 0x102, 0, &[])].concat());
+        assert!(parse_pmt(&payload).is_ok());
+        for len in [0, 1, 4, 12, payload.len() - 1] {
+            assert!(parse_pmt(&payload[..len]).is_err(), "{len} bytes parsed");
+        }
+    }
+
+    #[test]
+    fn truncated_es_loop_keeps_complete_entries() {
+        // The last entry stops after its elementary PID
+        let es_loop = [es(0x1B, 0x101, 0, &[]), es(0x0F, 0x102, 0, &[])[..3].to_vec()].concat();
+        let pmt = parse_pmt(&pmt(&es_loop)).unwrap();
+        assert_eq!(pmt.pcr_pid, 0x101);
+        assert_eq!(pmt.streams.len(), 1);
+        assert_eq!(pmt.streams[0].elementary_pid, 0x101);
+    }
+
+    #[test]
+    fn es_info_length_past_the_section_drops_the_entry() {
+        // ES_info_length 20 with only a 6-byte descriptor before the CRC: no phantom
+        // stream is read out of the descriptor bytes
+        let iso_639 = [0x0A, 0x04, b'e', b'n', b'g', 0x00];
+        let es_loop = [es(0x1B, 0x101, 0, &[]), es(0x03, 0x102, 20, &iso_639)].concat();
+        let pmt = parse_pmt(&pmt(&es_loop)).unwrap();
+        assert_eq!(pmt.streams.len(), 1);
+        assert_eq!(pmt.streams[0].stream_type, 0x1B);
+    }
+}
@@ -1,46 +1,94 @@
 // psi/sdt.rs
+use serde::Serialize;
+use super::descriptor::{components, dvb_text, parse_descriptors, Component};
 use super::section::SectionReader;
+#[derive(Debug, Clone, Serialize)]
 pub struct SdtSection { 
     pub version:  u8,
+    pub transport_stream_id: u16,
+    pub original_network_id: u16,
     pub services: Vec<Service> 
 }
+#[derive(Debug, Clone, Serialize)]
 pub struct Service { 
-    pub service_id: u16 
+    pub service_id: u16,
+    pub running_status: u8,   // 3 bits, see running_status_name
+    pub free_ca_mode: bool,   // true → one or more components are scrambled
+    pub components: Vec<Component>,   // component_descriptors (0x50), if the SDT carries any
+    pub provider_name: Option<String>, // service_descriptor (0x48)
+    pub service_name: Option<String>,
+}
+
+/// EN 300 468 Table 6 running_status
+pub fn running_status_name(status: u8) -> &'static str {
+    match status {
+        1 => "not running",
+        2 => "starts in a few seconds",
+        3 => "pausing",
+        4 => "running",
+        5 => "service off-air",
+        _ => "undefined",
+    }
 }
 
 /// SDT (table_id 0x42 actual / 0x46 other-TS) – minimal fields + CRC check.
 pub fn parse_sdt(payload: &[u8]) -> anyhow::Result<(u8, SdtSection)> {
-    let sec = SectionReader::new(payload)?;
+    let sec = SectionReader::new(payload, true)?;
     if sec.table_id != 0x42 && sec.table_id != 0x46 {
         anyhow::bail!("not SDT");
     }
 
-    let b = sec.body;
-    if b.len() < 8 {
+    let b = sec.body;                             // starts after last_section_number
+    if b.len() < 3 {
         anyhow::bail!("SDT body too short");
     }
 
-    // Fixed header inside SDT body
-    let _transport_stream_id = u16::from_be_bytes([b[0], b[1]]);
-    let _original_net_id     = u16::from_be_bytes([b[6], b[7]]);
-    // We don’t need them now, but kept for completeness
+    // transport_stream_id sits in the extended section header
+    let original_network_id = u16::from_be_bytes([b[0], b[1]]);
 
-    let mut idx = 8;                              // start of service loop
+    let mut idx = 3;                              // start of service loop
     let mut services = Vec::new();
 
     while idx + 5 <= b.len() {
         let service_id = u16::from_be_bytes([b[idx], b[idx + 1]]);
+        let running_status = b[idx + 3] >> 5;
+        let free_ca_mode   = b[idx + 3] & 0x10 != 0;
         let desc_len   = (((b[idx + 3] & 0x0F) as usize) << 8) | b[idx + 4] as usize;
-        idx += 5 + desc_len;
+        let desc_start = idx + 5;
+        idx = desc_start + desc_len;
         if idx > b.len() { break; }               // graceful exit on malformed len
-        services.push(Service { service_id });
+        let descriptors = parse_descriptors(&b[desc_start..idx]);
+        let (provider_name, service_name) = descriptors
+            .iter()
+            .find(|d| d.tag == 0x48)
+            .and_then(|d| service_names(&d.data))
+            .unzip();
+        services.push(Service {
+            service_id,
+            running_status,
+            free_ca_mode,
+            components: components(&descriptors),
+            provider_name,
+            service_name,
+        });
     }
 
     Ok((
         sec.table_id,
         SdtSection {
             version: sec.version,
+            transport_stream_id: sec.program_number,
+            original_network_id,
             services,
         },
     ))
-}
\ No newline at end of file
+}
+
+/// provider_name and service_name of a service_descriptor (after service_type)
+fn service_names(d: &[u8]) -> Option<(String, String)> {
+    let provider_len = *d.get(1)? as usize;
+    let provider = d.get(2..2 + provider_len)?;
+    let name_len = *d.get(2 + provider_len)? as usize;
+    let name = d.get(3 + provider_len..3 + provider_len + name_len)?;
+    Some((dvb_text(provider), dvb_text(name)))
+}
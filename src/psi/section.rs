@@ -1,6 +1,8 @@
 // psi/section.rs
 //! Generic PSI / SI section reader with CRC-32 (MPEG-2) validation.
+//! DVB SI tables (EN 300 468 Annex B) use the same CRC-32/MPEG-2 as PSI.
 
+use std::collections::{BTreeMap, HashMap};
 use crc::{Crc, CRC_32_MPEG_2};
 
 /// Returned by [`SectionReader::new`].
@@ -16,27 +18,54 @@ pub struct SectionReader<'a> {
 
 const CRC_MPEG: Crc<u32> = Crc::<u32>::new(&CRC_32_MPEG_2);
 
+/// table_id value of stuffing bytes: no section starts here
+pub const STUFFING_TABLE_ID: u8 = 0xFF;
+
 impl<'a> SectionReader<'a> {
-    /// Validates pointer, length and (if present) CRC-32.
-    pub fn new(payload: &'a [u8]) -> anyhow::Result<Self> {
+    /// Validates pointer, length and, when `crc_present`, the trailing CRC-32.
+    /// Sections without one (TDT, RST, ST, DIT) pass `false`. Short-form sections
+    /// (section_syntax_indicator = 0) have no extension header: version,
+    /// section numbers and program_number read as 0 and `body` follows section_length.
+    /// A pointer_field past the payload, or one landing on 0xFF stuffing, is an error.
+    pub fn new(payload: &'a [u8], crc_present: bool) -> anyhow::Result<Self> {
         if payload.is_empty() { anyhow::bail!("payload empty") }
         let pointer = payload[0] as usize;
         let start   = 1 + pointer;
-        if payload.len() < start + 8 { anyhow::bail!("short section") }
+        if start >= payload.len() {
+            anyhow::bail!("pointer_field {pointer} points past the {}-byte payload", payload.len())
+        }
+        if payload[start] == STUFFING_TABLE_ID { anyhow::bail!("stuffing, no section at pointer_field {pointer}") }
+        if payload.len() < start + 3 { anyhow::bail!("short section") }
 
         let table_id = payload[start];
+        let long_form = payload[start+1] & 0x80 != 0;
         let sec_len  = (((payload[start+1] & 0x0F) as usize) << 8) | (payload[start+2] as usize);
-        if sec_len < 5 { anyhow::bail!("invalid section_length") }
+        let header_len = if long_form { 5 } else { 0 };
+        let crc_len    = if crc_present { 4 } else { 0 };
+        if sec_len < header_len + crc_len { anyhow::bail!("invalid section_length") }
         let end      = start + 3 + sec_len;
         if end > payload.len() { anyhow::bail!("truncated section") }
 
-        // If the spec says CRC present ⇒ last 4 bytes of section
-        let crc_calc = CRC_MPEG.checksum(&payload[start..end-4]);
-        let crc_pkt  = u32::from_be_bytes(payload[end-4..end].try_into()?);
-        if crc_calc != crc_pkt {
-            anyhow::bail!("CRC-32 mismatch");
+        if crc_present {
+            let crc_calc = CRC_MPEG.checksum(&payload[start..end-4]);
+            let crc_pkt  = u32::from_be_bytes(payload[end-4..end].try_into()?);
+            if crc_calc != crc_pkt {
+                anyhow::bail!("CRC-32 mismatch");
+            }
         }
 
+        let body = &payload[start + 3 + header_len .. end - crc_len];
+        if !long_form {
+            return Ok(Self {
+                table_id,
+                version: 0,
+                current_next: true,
+                section_number: 0,
+                last_section: 0,
+                program_number: 0,
+                body,
+            });
+        }
         Ok(Self {
             table_id,
             version:       (payload[start+5] & 0x3E) >> 1,
@@ -44,7 +73,188 @@ impl<'a> SectionReader<'a> {
             section_number:payload[start+6],
             last_section:  payload[start+7],
             program_number: u16::from_be_bytes(payload[start+3..start+5].try_into()?),
-            body:          &payload[start+8 .. end-4],
+            body,
         })
     }
-}
\ No newline at end of file
+}
+
+/// table_id of the section starting in this payload, and whether the whole
+/// section fits in it (so a parse failure can only mean a bad CRC/body).
+/// None when the pointer_field runs past the payload or lands on stuffing.
+pub fn peek_section(payload: &[u8]) -> Option<(u8, bool)> {
+    let start = 1 + *payload.first()? as usize;
+    let table_id = *payload.get(start).filter(|&&id| id != STUFFING_TABLE_ID)?;
+    let complete = match payload.get(start + 1..start + 3) {
+        Some(len) => {
+            let sec_len = (((len[0] & 0x0F) as usize) << 8) | len[1] as usize;
+            start + 3 + sec_len <= payload.len()
+        }
+        None => false,
+    };
+    Some((table_id, complete))
+}
+
+/// Collects the sections of a multi-section table until all of
+/// `0..=last_section_number` have arrived. Keyed by (PID, table_id,
+/// table_id_extension); a version change discards the partial table.
+pub struct SectionAccumulator<T> {
+    pending: HashMap<(u16, u8, u16), PendingTable<T>>,
+}
+
+struct PendingTable<T> {
+    version: u8,
+    last_section: u8,
+    sections: BTreeMap<u8, T>,
+}
+
+impl<T> SectionAccumulator<T> {
+    pub fn new() -> Self {
+        Self { pending: HashMap::new() }
+    }
+
+    /// Add a section; returns every section of the table, in order, once complete
+    pub fn push(
+        &mut self,
+        key: (u16, u8, u16),
+        version: u8,
+        section_number: u8,
+        last_section: u8,
+        section: T,
+    ) -> Option<Vec<T>> {
+        let table = self.pending.entry(key).or_insert_with(|| PendingTable {
+            version,
+            last_section,
+            sections: BTreeMap::new(),
+        });
+        if table.version != version || table.last_section != last_section {
+            table.version = version;
+            table.last_section = last_section;
+            table.sections.clear();
+        }
+        table.sections.insert(section_number, section);
+
+        if table.sections.len() > last_section as usize {
+            let table = self.pending.remove(&key)?;
+            return Some(table.sections.into_values().collect());
+        }
+        None
+    }
+}
+
+impl<T> Default for SectionAccumulator<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reassembles sections that span several TS packets of one PID (EIT schedule
+/// sections run up to 4 KB). Complete sections are returned with a zero
+/// pointer_field prepended, ready for [`SectionReader::new`].
+#[derive(Default)]
+pub struct SectionAssembler {
+    buf: Vec<u8>,
+    in_section: bool, // buf holds the start of a section
+}
+
+impl SectionAssembler {
+    /// Feed one packet payload; returns the sections completed by it
+    pub fn push(&mut self, payload_unit_start: bool, payload: &[u8]) -> Vec<Vec<u8>> {
+        let mut complete = Vec::new();
+        let data = if payload_unit_start {
+            let Some(&pointer) = payload.first() else { return complete };
+            let pointer = pointer as usize;
+            // Bytes before the pointer finish the section in progress
+            if self.in_section && let Some(tail) = payload.get(1..1 + pointer) {
+                self.buf.extend_from_slice(tail);
+                self.drain(&mut complete);
+            }
+            self.buf.clear();
+            self.in_section = true;
+            payload.get(1 + pointer..).unwrap_or(&[])
+        } else if self.in_section {
+            payload
+        } else {
+            return complete; // joined mid-section, wait for the next PUSI
+        };
+        self.buf.extend_from_slice(data);
+        self.drain(&mut complete);
+        complete
+    }
+
+    /// Move every complete section out of `buf`; 0xFF stuffing ends the packet's sections
+    fn drain(&mut self, complete: &mut Vec<Vec<u8>>) {
+        while self.buf.len() >= 3 {
+            if self.buf[0] == 0xFF {
+                self.buf.clear();
+                self.in_section = false;
+                return;
+            }
+            let len = 3 + ((((self.buf[1] & 0x0F) as usize) << 8) | self.buf[2] as usize);
+            if self.buf.len() < len {
+                return;
+            }
+            let mut section = Vec::with_capacity(1 + len);
+            section.push(0); // pointer_field
+            section.extend(self.buf.drain(..len));
+            complete.push(section);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// Packet payload carrying one long-form section: pointer_field 0, the section
+    /// with `body` after last_section_number, and a valid CRC-32
+    pub(crate) fn section_payload(table_id: u8, table_id_extension: u16, version: u8, body: &[u8]) -> Vec<u8> {
+        let section_length = 5 + body.len() + 4;
+        let mut sec = vec![
+            table_id,
+            0xB0 | (section_length >> 8) as u8 & 0x0F,
+            section_length as u8,
+            (table_id_extension >> 8) as u8,
+            table_id_extension as u8,
+            0xC1 | ((version & 0x1F) << 1),
+            0,
+            0,
+        ];
+        sec.extend_from_slice(body);
+        let crc = CRC_MPEG.checksum(&sec);
+        sec.extend_from_slice(&crc.to_be_bytes());
+        [vec![0], sec].concat()
+    }
+
+    /// A PAT followed by stuffing up to the end of a 184-byte payload, with the
+    /// pointer_field moved to `pointer`
+    fn pat_then_stuffing(pointer: u8) -> Vec<u8> {
+        let mut payload = section_payload(0x00, 1, 0, &[0x00, 0x01, 0xE1, 0x00]);
+        payload.resize(184, 0xFF);
+        payload[0] = pointer;
+        payload
+    }
+
+    #[test]
+    fn pointer_into_stuffing_is_no_section() {
+        let payload = pat_then_stuffing(0);
+        assert_eq!(peek_section(&payload), Some((0x00, true)));
+        assert!(SectionReader::new(&payload, true).is_ok());
+
+        // The PAT ends at offset 17; 40 lands in the 0xFF stuffing after it
+        let payload = pat_then_stuffing(40);
+        assert_eq!(peek_section(&payload), None);
+        let err = SectionReader::new(&payload, true).err().unwrap();
+        assert!(err.to_string().contains("stuffing"), "{err}");
+
+        let mut assembler = SectionAssembler::default();
+        assert!(assembler.push(true, &payload).is_empty());
+    }
+
+    #[test]
+    fn pointer_past_the_payload_is_no_section() {
+        let payload = pat_then_stuffing(183);
+        assert_eq!(peek_section(&payload), None);
+        let err = SectionReader::new(&payload, true).err().unwrap();
+        assert!(err.to_string().contains("points past"), "{err}");
+    }
+}
@@ -2,34 +2,40 @@
 //! TDT (0x70, no CRC)  &  TOT (0x73, CRC present) checker.
 
 use anyhow::bail;
+use super::descriptor::{parse_descriptors, Descriptor};
+use super::eit::dvb_time;
+use super::section::SectionReader;
 
 pub enum TdtTot<'a> {
     Tdt(&'a [u8]),          // UTC time only (5 bytes BCD)
     Tot(&'a [u8]),          // UTC time + descriptors
 }
 
-pub fn parse_tdt_tot(payload: &[u8]) -> anyhow::Result<(u8, TdtTot)> {
-    if payload.is_empty() { bail!("payload empty"); }
-    let pointer = payload[0] as usize;
-    let start   = 1 + pointer;
-    if payload.len() < start + 3 { bail!("short TDT/TOT"); }
+impl TdtTot<'_> {
+    /// UTC_time as Unix seconds; None if short or undefined
+    pub fn utc(&self) -> Option<i64> {
+        let (TdtTot::Tdt(body) | TdtTot::Tot(body)) = self;
+        dvb_time(body.get(..5)?)
+    }
 
-    let tid      = payload[start];
-    let sec_len  = (((payload[start+1] & 0x0F) as usize) << 8) | (payload[start+2] as usize);
-    let end      = start + 3 + sec_len;
-    if end > payload.len() { bail!("truncated"); }
+    /// TOT descriptor loop (local_time_offset_descriptor 0x58); empty for a TDT
+    pub fn descriptors(&self) -> Vec<Descriptor> {
+        let TdtTot::Tot(body) = self else { return Vec::new() };
+        let Some(len) = body.get(5..7).map(|b| (((b[0] & 0x0F) as usize) << 8) | b[1] as usize) else {
+            return Vec::new();
+        };
+        body.get(7..7 + len).map(parse_descriptors).unwrap_or_default()
+    }
+}
+
+pub fn parse_tdt_tot(payload: &[u8]) -> anyhow::Result<(u8, TdtTot<'_>)> {
+    let Some(&tid) = payload.first().and_then(|&pointer| payload.get(1 + pointer as usize)) else {
+        bail!("short TDT/TOT");
+    };
 
     match tid {
-        0x70 => Ok((tid, TdtTot::Tdt(&payload[start+3 .. end]))),      // no CRC
-        0x73 => {
-            // TOT has CRC-32 at end
-            use crc::{Crc, CRC_32_MPEG_2};
-            let crc_calc = Crc::<u32>::new(&CRC_32_MPEG_2)
-                .checksum(&payload[start .. end-4]);
-            let crc_pkt = u32::from_be_bytes(payload[end-4..end].try_into()?);
-            if crc_calc != crc_pkt { bail!("TOT CRC mismatch"); }
-            Ok((tid, TdtTot::Tot(&payload[start+3 .. end-4])))
-        }
+        0x70 => Ok((tid, TdtTot::Tdt(SectionReader::new(payload, false)?.body))),   // no CRC
+        0x73 => Ok((tid, TdtTot::Tot(SectionReader::new(payload, true)?.body))),    // CRC-32 at end
         _ => bail!("not TDT/TOT"),
     }
-}
\ No newline at end of file
+}
@@ -1,14 +1,19 @@
 //! Report generation for MPEG-TS inspection results
 
+use std::collections::BTreeMap;
 use serde::Serialize;
-use crate::types::{InspectorReport, ProgramInfo, StreamInfo, CodecInfo};
+use crate::types::{InspectorReport, ReportDiff, ReportChange, ProgramInfo, StreamInfo, CodecInfo, PesTimestampInfo, PidInfo, ScramblingState, SubtitlePage, Verdict, BitrateClock, LogicalChannel, EpgCoverage, PcrIntervalInfo, LastPcrInfo, TransportStats, PidCollision, SmoothingBuffer, StreamCountMismatch, stream_type_name};
 use crate::tr101::Tr101Metrics;
+use crate::constants::NULL_PID;
+use crate::psi::sdt::running_status_name;
+use crate::psi::descriptor::{registration, ca_system_ids, format_identifier_str, max_bitrate_kbps, smoothing_buffer};
 
 /// JSON structure for elementary streams (internal serialization)
 #[derive(Serialize)]
 struct EsJson<'a> {
     pid: u16,
     stream_type: u8,
+    stream_type_name: &'static str,
     codec: &'a str,
     bitrate_kbps: f64,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -20,9 +25,63 @@ struct EsJson<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     chroma: Option<&'a str>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    profile: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    level: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tier: Option<&'a str>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    level_resolution_mismatch: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sar: Option<(u16, u16)>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aspect_ratio: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bit_depth: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    full_range: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    colour_primaries: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transfer: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    matrix: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    gop_length: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    keyframe_interval_secs: Option<f64>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    keyframe_overdue: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
     channels: Option<u8>,
     #[serde(skip_serializing_if = "Option::is_none")]
     sample_rate: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dialnorm: Option<i8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    declared_bitrate_kbps: Option<u32>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    atmos: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    service_type: Option<&'a str>,
+    #[serde(skip_serializing_if = "<[_]>::is_empty")]
+    subtitle_pages: &'a [SubtitlePage],
+    #[serde(skip_serializing_if = "Option::is_none")]
+    declared_max_bitrate_kbps: Option<f64>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    max_bitrate_exceeded: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    smoothing_buffer: Option<SmoothingBuffer>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    frames_delivered: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    measured_fps: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    audio_drift_ppm: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    component: Option<String>,
+    #[serde(flatten)]
+    pes: PesTimestampInfo,
 }
 
 /// JSON structure for programs (internal serialization)
@@ -33,21 +92,186 @@ struct ProgramJson<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pcr_pid: Option<u16>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    pcr_shared_with_pid: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    declared_max_bitrate_kbps: Option<f64>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    max_bitrate_exceeded: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pcr_interval: Option<PcrIntervalInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    last_pcr: Option<LastPcrInfo>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     pmt_version: Option<u8>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    running_status: Option<&'static str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    free_ca_mode: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    scrambling: Option<ScramblingState>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    registration: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    ca_system_ids: Vec<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    logical_channel: Option<LogicalChannel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    hd_logical_channel: Option<LogicalChannel>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    epg: Option<EpgCoverage>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    provider: Option<&'a str>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    stream_mismatches: Vec<StreamCountMismatch>,
 }
 
 /// JSON structure for complete report (internal serialization)
 #[derive(Serialize)]
 struct ReportJson<'a> {
     ts_time: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transport_stream_id: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    original_network_id: Option<u16>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    transport_stream_id_mismatch: bool,
     programs: Vec<ProgramJson<'a>>,
+    pids: Vec<PidInfo>,
+    dangling_pids: Vec<u16>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pid_role_conflicts: Vec<u16>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    program_pid_collisions: Vec<PidCollision>,
+    mux_bitrate_kbps: f64,
+    null_bitrate_kbps: f64,
+    bitrate_clock: BitrateClock,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    socket_drops: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dropped_datagrams: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    misaligned_datagrams: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    transport: Option<TransportStats>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    providers: BTreeMap<String, Vec<u16>>,
     tr101: &'a Tr101Metrics,
+    verdict: Verdict,
 }
 
 /// Report generator for MPEG-TS inspection results
 pub struct Reporter;
 
 impl Reporter {
+    /// One-line status summary, e.g. `[OK] 3 progs, 18.2 Mbps, CC:0 PCR:0 sync:OK`.
+    ///
+    /// The status is ERROR if any Priority 1 counter grew since `previous`, WARN if
+    /// only Priority 2 counters did, OK otherwise. Without a previous snapshot any
+    /// non-zero counter counts as growth.
+    pub fn generate_status_line(report: &InspectorReport, previous: Option<&Tr101Metrics>) -> String {
+        let tr101 = &report.tr101_metrics;
+        let (p1_before, p2_before) = previous.map_or((0, 0), |p| (p.priority_1_errors(), p.priority_2_errors()));
+        let status = if tr101.priority_1_errors() > p1_before {
+            "ERROR"
+        } else if tr101.priority_2_errors() > p2_before {
+            "WARN"
+        } else {
+            "OK"
+        };
+
+        let total_kbps: f64 = report
+            .programs
+            .iter()
+            .flat_map(|p| &p.streams)
+            .map(|s| s.bitrate_kbps)
+            .sum();
+
+        let sync_errors = tr101.sync_byte_errors + tr101.ts_sync_loss;
+        let sync_lost = previous.map_or(sync_errors > 0, |p| sync_errors > p.sync_byte_errors + p.ts_sync_loss);
+        let pcr_errors = tr101.pcr_repetition_errors + tr101.pcr_accuracy_errors + tr101.pcr_discontinuity_errors;
+
+        format!(
+            "[{status}] {} progs, {:.1} Mbps, CC:{} PCR:{} sync:{}",
+            report.programs.len(),
+            total_kbps / 1000.0,
+            tr101.continuity_counter_errors,
+            pcr_errors,
+            if sync_lost { "LOST" } else { "OK" },
+        )
+    }
+
+    /// Pass/Warn/Fail for `report`. Counters not measured by the analysis mode are
+    /// already zeroed in the report, so e.g. Priority 1 mode can never WARN.
+    pub fn verdict(report: &InspectorReport) -> Verdict {
+        Self::verdict_for(&report.tr101_metrics)
+    }
+
+    fn verdict_for(tr101: &Tr101Metrics) -> Verdict {
+        if tr101.priority_1_errors() > 0 {
+            Verdict::Fail
+        } else if tr101.priority_2_errors() > 0 {
+            Verdict::Warn
+        } else {
+            Verdict::Pass
+        }
+    }
+
+    /// Audit-trail lines for what changed since `previous`: PAT/PMT version changes,
+    /// programs appearing or disappearing, and Priority 1 counters leaving zero.
+    /// Without a previous report everything already present is listed.
+    pub fn generate_event_lines(report: &InspectorReport, previous: Option<&InspectorReport>) -> Vec<String> {
+        let mut lines = Vec::new();
+        let tr101 = &report.tr101_metrics;
+        let now = &report.timestamp;
+
+        // version_changes keeps a bounded history; the counters say how many entries are new
+        // (none if they went down, e.g. after the metrics were reset)
+        let total = |m: &Tr101Metrics| m.pat_version_changes + m.pmt_version_changes;
+        let new_changes = total(tr101).saturating_sub(previous.map_or(0, |p| total(&p.tr101_metrics)));
+        let skip = tr101.version_changes.len().saturating_sub(new_changes as usize);
+        for change in tr101.version_changes.iter().skip(skip) {
+            let what = if change.table == "PAT" { "program" } else { "PID" };
+            lines.push(format!(
+                "{} {} version {} -> {} ({what} {})",
+                change.timestamp, change.table, change.old, change.new, change.id
+            ));
+        }
+
+        let programs = |r: &InspectorReport| {
+            let mut numbers: Vec<u16> = r.programs.iter().map(|p| p.program_number).collect();
+            numbers.sort_unstable();
+            numbers
+        };
+        let (before, after) = (previous.map(programs).unwrap_or_default(), programs(report));
+        for pn in after.iter().filter(|pn| !before.contains(pn)) {
+            lines.push(format!("{now} program {pn} added"));
+        }
+        for pn in before.iter().filter(|pn| !after.contains(pn)) {
+            lines.push(format!("{now} program {pn} removed"));
+        }
+
+        let counters_before = previous.map(|p| p.tr101_metrics.priority_1_counters());
+        for (i, (name, count)) in tr101.priority_1_counters().into_iter().enumerate() {
+            let was_zero = counters_before.is_none_or(|c| c[i].1 == 0);
+            if was_zero && count > 0 {
+                lines.push(format!("{now} {name} first error (count {count})"));
+            }
+        }
+        lines
+    }
+
+    /// Program numbers grouped by provider_name; programs without one are left out
+    fn providers<'a>(programs: impl Iterator<Item = (Option<&'a str>, u16)>) -> BTreeMap<String, Vec<u16>> {
+        let mut providers: BTreeMap<String, Vec<u16>> = BTreeMap::new();
+        for (provider, program_number) in programs {
+            if let Some(provider) = provider {
+                providers.entry(provider.to_string()).or_default().push(program_number);
+            }
+        }
+        providers.values_mut().for_each(|programs| programs.sort_unstable());
+        providers
+    }
+
     /// Generate a structured InspectorReport for API consumers
     pub fn create_report(
         processor: &crate::processor::PacketProcessor,
@@ -61,138 +285,418 @@ impl Reporter {
                 .iter()
                 .find(|p| p.program_number == *prog_num)
                 .map(|p| p.pmt_pid)
+                && let Some(pmt) = processor.pmt_map.get(&pmt_pid).filter(|pmt| processor.program_selected(*prog_num, pmt))
             {
-                if let Some(pmt) = processor.pmt_map.get(&pmt_pid) {
-                    let mut streams = Vec::new();
-                    for s in &pmt.streams {
-                        if let Some(stats) = processor.stats_manager.get(s.elementary_pid) {
-                            if let Some(bitrate_kbps) = processor.stats_manager.calculate_bitrate(s.elementary_pid) {
-                                streams.push(StreamInfo {
-                                    pid: s.elementary_pid,
-                                    stream_type: s.stream_type,
-                                    codec: stats.codec.clone(),
-                                    bitrate_kbps,
-                                });
-                            }
-                        }
+                let mut streams = Vec::new();
+                for s in &pmt.streams {
+                    if let Some(stats) = processor.stats_manager.get(s.elementary_pid)
+                        && let Some(bitrate_kbps) = processor.stats_manager.calculate_bitrate(s.elementary_pid)
+                    {
+                        let declared_max_bitrate_kbps = max_bitrate_kbps(&s.descriptors);
+                        streams.push(StreamInfo {
+                            pid: s.elementary_pid,
+                            stream_type: s.stream_type,
+                            stream_type_name: stream_type_name(s.stream_type),
+                            codec: processor.stream_codec(s),
+                            bitrate_kbps,
+                            declared_max_bitrate_kbps,
+                            max_bitrate_exceeded: declared_max_bitrate_kbps.is_some_and(|max| bitrate_kbps > max),
+                            smoothing_buffer: smoothing_buffer(&s.descriptors),
+                            frames_delivered: (stats.frames_delivered > 0).then_some(stats.frames_delivered),
+                            measured_fps: stats.measured_fps,
+                            audio_drift_ppm: stats.audio_drift_ppm,
+                            component: processor.stream_component(*prog_num, &s.descriptors),
+                            pes: processor.stats_manager.pes_timestamp_info(s.elementary_pid).unwrap_or_default(),
+                        });
                     }
-                    // Get PCR PID and PMT version for this program
-                    let pcr_pid = processor.get_pcr_pid(*prog_num);
-                    let pmt_version = processor.get_pmt_version(pmt_pid);
-
-                    programs.push(ProgramInfo {
-                        program_number: *prog_num,
-                        streams,
-                        pcr_pid,
-                        pmt_version,
-                    });
                 }
+                let program_max_kbps = max_bitrate_kbps(&pmt.descriptors);
+                let program_kbps: f64 = pmt.streams.iter()
+                    .filter_map(|s| processor.stats_manager.calculate_bitrate(s.elementary_pid))
+                    .sum();
+
+                // Get PCR PID and PMT version for this program
+                let pcr_pid = processor.get_pcr_pid(*prog_num);
+                let pmt_version = processor.get_pmt_version(pmt_pid);
+
+                let service = processor.sdt_service(*prog_num);
+                let (logical_channel, hd_logical_channel) = processor.si_cache.logical_channel(*prog_num);
+
+                programs.push(ProgramInfo {
+                    program_number: *prog_num,
+                    streams,
+                    pcr_pid,
+                    pcr_shared_with_pid: pmt.pcr_shared_with_pid(),
+                    declared_max_bitrate_kbps: program_max_kbps,
+                    max_bitrate_exceeded: program_max_kbps.is_some_and(|max| program_kbps > max),
+                    pcr_interval: processor.pcr_interval(pmt.pcr_pid),
+                    last_pcr: processor.last_pcr(pmt.pcr_pid),
+                    pmt_version,
+                    running_status: service.map(|s| running_status_name(s.running_status).to_string()),
+                    free_ca_mode: service.map(|s| s.free_ca_mode),
+                    scrambling: processor.program_scrambling(pmt),
+                    registration: registration(&pmt.descriptors).map(format_identifier_str),
+                    ca_system_ids: ca_system_ids(&pmt.descriptors),
+                    logical_channel,
+                    hd_logical_channel,
+                    epg: processor.epg_coverage(*prog_num),
+                    provider: service.and_then(|s| s.provider_name.clone()),
+                    stream_mismatches: processor.stream_mismatches(pmt),
+                });
             }
         }
 
         // Filter TR-101 metrics based on analysis mode
-        let filtered_tr101 = match analysis_mode {
-            Some(crate::types::AnalysisMode::Tr101Priority1) => tr101.priority_1_only(),
-            Some(crate::types::AnalysisMode::Tr101Priority12) => tr101.priority_1_and_2_only(),
-            _ => tr101,
-        };
+        let filtered_tr101 = tr101.for_mode(analysis_mode);
 
         InspectorReport {
+            verdict: Self::verdict_for(&filtered_tr101),
             timestamp: chrono::Utc::now().to_rfc3339(),
+            transport_stream_id: processor.si_cache.transport_stream_id(),
+            original_network_id: processor.si_cache.original_network_id(),
+            transport_stream_id_mismatch: processor.si_cache.check_transport_stream_id_mismatch(),
+            pids: processor.stats_manager.pid_inventory(),
+            dangling_pids: processor.dangling_pids(),
+            pid_role_conflicts: processor.pid_role_conflicts(),
+            program_pid_collisions: processor.program_pid_collisions(),
+            mux_bitrate_kbps: processor.stats_manager.mux_bitrate(processor.include_null_in_mux),
+            null_bitrate_kbps: processor.stats_manager.pid_bitrate(NULL_PID).unwrap_or(0.0),
+            bitrate_clock: processor.stats_manager.effective_bitrate_clock(),
+            socket_drops: processor.socket_drops,
+            dropped_datagrams: processor.dropped_datagrams,
+            misaligned_datagrams: processor.misaligned_datagrams,
+            transport: processor.transport.clone(),
+            providers: Self::providers(programs.iter().map(|p| (p.provider.as_deref(), p.program_number))),
             programs,
             tr101_metrics: filtered_tr101,
+            si_tables: processor.si_cache.snapshot(),
+            error_packets: processor.error_packets.iter().cloned().collect(),
         }
     }
 
-    /// Generate pretty-printed JSON string for CLI output
+    /// Generate the JSON report for CLI output, pretty-printed or on a single line
+    /// (same schema either way)
     pub fn generate_json_report(
         processor: &crate::processor::PacketProcessor,
         tr101: Tr101Metrics,
         analysis_mode: Option<crate::types::AnalysisMode>,
+        pretty: bool,
     ) -> String {
         let mut programs_out = Vec::new();
 
+        // Codecs so far only declared by an AC-3/E-AC-3 descriptor, kept here for the borrows
+        let declared: BTreeMap<u16, CodecInfo> = processor.pmt_map.values()
+            .flat_map(|pmt| &pmt.streams)
+            .filter(|s| processor.stats_manager.get(s.elementary_pid).is_some_and(|stats| stats.codec.is_none()))
+            .filter_map(|s| Some((s.elementary_pid, processor.stream_codec(s)?)))
+            .collect();
+
         for (prog_num, pat) in &processor.pat_map {
             if let Some(pmt_pid) = pat.programs
                 .iter()
                 .find(|p| p.program_number == *prog_num)
                 .map(|p| p.pmt_pid)
+                && let Some(pmt) = processor.pmt_map.get(&pmt_pid).filter(|pmt| processor.program_selected(*prog_num, pmt))
             {
-                if let Some(pmt) = processor.pmt_map.get(&pmt_pid) {
-                    let mut es_vec = Vec::new();
-                    for s in &pmt.streams {
-                        if let Some(stats) = processor.stats_manager.get(s.elementary_pid) {
-                            if let Some(bitrate_kbps) = processor.stats_manager.calculate_bitrate(s.elementary_pid) {
-                                match &stats.codec {
-                                    Some(CodecInfo::Video(v)) => es_vec.push(EsJson {
-                                        pid: s.elementary_pid,
-                                        stream_type: s.stream_type,
-                                        codec: &v.codec,
-                                        bitrate_kbps,
-                                        width: Some(v.width),
-                                        height: Some(v.height),
-                                        fps: if v.fps > 0.0 { Some(v.fps) } else { None },
-                                        chroma: Some(&v.chroma),
-                                        channels: None,
-                                        sample_rate: None,
-                                    }),
-                                    Some(CodecInfo::Audio(a)) => es_vec.push(EsJson {
-                                        pid: s.elementary_pid,
-                                        stream_type: s.stream_type,
-                                        codec: &a.codec,
-                                        bitrate_kbps,
-                                        width: None,
-                                        height: None,
-                                        fps: None,
-                                        chroma: None,
-                                        channels: a.channels,
-                                        sample_rate: a.sample_rate,
-                                    }),
-                                    Some(CodecInfo::Subtitle(sub)) => es_vec.push(EsJson {
-                                        pid: s.elementary_pid,
-                                        stream_type: s.stream_type,
-                                        codec: &sub.codec,
-                                        bitrate_kbps,
-                                        width: None,
-                                        height: None,
-                                        fps: None,
-                                        chroma: None,
-                                        channels: None,
-                                        sample_rate: None,
-                                    }),
-                                    None => {
-                                        // Skip streams without codec info
-                                    }
-                                }
+                let mut es_vec = Vec::new();
+                for s in &pmt.streams {
+                    if let Some(stats) = processor.stats_manager.get(s.elementary_pid)
+                        && let Some(bitrate_kbps) = processor.stats_manager.calculate_bitrate(s.elementary_pid)
+                    {
+                        let pes = processor.stats_manager.pes_timestamp_info(s.elementary_pid).unwrap_or_default();
+                        let declared_max_bitrate_kbps = max_bitrate_kbps(&s.descriptors);
+                        let max_bitrate_exceeded = declared_max_bitrate_kbps.is_some_and(|max| bitrate_kbps > max);
+                        let smoothing_buffer = smoothing_buffer(&s.descriptors);
+                        let frames_delivered = (stats.frames_delivered > 0).then_some(stats.frames_delivered);
+                        let component = processor.stream_component(*prog_num, &s.descriptors);
+                        match stats.codec.as_ref().or_else(|| declared.get(&s.elementary_pid)) {
+                            Some(CodecInfo::Video(v)) => es_vec.push(EsJson {
+                                pid: s.elementary_pid,
+                                stream_type: s.stream_type,
+                                stream_type_name: stream_type_name(s.stream_type),
+                                codec: &v.codec,
+                                bitrate_kbps,
+                                width: Some(v.width),
+                                height: Some(v.height),
+                                fps: if v.fps > 0.0 { Some(v.fps) } else { None },
+                                chroma: Some(&v.chroma),
+                                profile: v.profile.as_deref(),
+                                level: v.level,
+                                tier: v.tier.as_deref(),
+                                level_resolution_mismatch: v.level_resolution_mismatch,
+                                sar: v.sar,
+                                aspect_ratio: v.aspect_ratio.as_deref(),
+                                bit_depth: v.bit_depth,
+                                full_range: v.full_range,
+                                colour_primaries: v.colour_primaries.as_deref(),
+                                transfer: v.transfer.as_deref(),
+                                matrix: v.matrix.as_deref(),
+                                gop_length: v.gop_length,
+                                keyframe_interval_secs: v.keyframe_interval_secs,
+                                keyframe_overdue: v.keyframe_overdue,
+                                channels: None,
+                                sample_rate: None,
+                                dialnorm: None,
+                                declared_bitrate_kbps: None,
+                                atmos: false,
+                                service_type: None,
+                                subtitle_pages: &[],
+                                declared_max_bitrate_kbps,
+                                max_bitrate_exceeded,
+                                smoothing_buffer,
+                                frames_delivered,
+                                measured_fps: stats.measured_fps,
+                                audio_drift_ppm: stats.audio_drift_ppm,
+                                component,
+                                pes: pes.clone(),
+                            }),
+                            Some(CodecInfo::Audio(a)) => es_vec.push(EsJson {
+                                pid: s.elementary_pid,
+                                stream_type: s.stream_type,
+                                stream_type_name: stream_type_name(s.stream_type),
+                                codec: &a.codec,
+                                bitrate_kbps,
+                                width: None,
+                                height: None,
+                                fps: None,
+                                chroma: None,
+                                profile: None,
+                                level: None,
+                                tier: None,
+                                level_resolution_mismatch: false,
+                                sar: None,
+                                aspect_ratio: None,
+                                bit_depth: None,
+                                full_range: None,
+                                colour_primaries: None,
+                                transfer: None,
+                                matrix: None,
+                                gop_length: None,
+                                keyframe_interval_secs: None,
+                                keyframe_overdue: false,
+                                channels: a.channels,
+                                sample_rate: a.sample_rate,
+                                dialnorm: a.dialnorm,
+                                declared_bitrate_kbps: a.declared_bitrate_kbps,
+                                atmos: a.atmos,
+                                service_type: a.service_type.as_deref(),
+                                subtitle_pages: &[],
+                                declared_max_bitrate_kbps,
+                                max_bitrate_exceeded,
+                                smoothing_buffer,
+                                frames_delivered,
+                                measured_fps: stats.measured_fps,
+                                audio_drift_ppm: stats.audio_drift_ppm,
+                                component,
+                                pes: pes.clone(),
+                            }),
+                            Some(CodecInfo::Subtitle(sub)) => es_vec.push(EsJson {
+                                pid: s.elementary_pid,
+                                stream_type: s.stream_type,
+                                stream_type_name: stream_type_name(s.stream_type),
+                                codec: &sub.codec,
+                                bitrate_kbps,
+                                width: None,
+                                height: None,
+                                fps: None,
+                                chroma: None,
+                                profile: None,
+                                level: None,
+                                tier: None,
+                                level_resolution_mismatch: false,
+                                sar: None,
+                                aspect_ratio: None,
+                                bit_depth: None,
+                                full_range: None,
+                                colour_primaries: None,
+                                transfer: None,
+                                matrix: None,
+                                gop_length: None,
+                                keyframe_interval_secs: None,
+                                keyframe_overdue: false,
+                                channels: None,
+                                sample_rate: None,
+                                dialnorm: None,
+                                declared_bitrate_kbps: None,
+                                atmos: false,
+                                service_type: None,
+                                subtitle_pages: &sub.pages,
+                                declared_max_bitrate_kbps,
+                                max_bitrate_exceeded,
+                                smoothing_buffer,
+                                frames_delivered,
+                                measured_fps: stats.measured_fps,
+                                audio_drift_ppm: stats.audio_drift_ppm,
+                                component,
+                                pes: pes.clone(),
+                            }),
+                            None => {
+                                // Skip streams without codec info
                             }
                         }
                     }
-                    // Get PCR PID and PMT version for this program
-                    let pcr_pid = processor.get_pcr_pid(*prog_num);
-                    let pmt_version = processor.get_pmt_version(pmt_pid);
-
-                    programs_out.push(ProgramJson {
-                        program: *prog_num,
-                        streams: es_vec,
-                        pcr_pid,
-                        pmt_version,
-                    });
                 }
+                let program_max_kbps = max_bitrate_kbps(&pmt.descriptors);
+                let program_kbps: f64 = pmt.streams.iter()
+                    .filter_map(|s| processor.stats_manager.calculate_bitrate(s.elementary_pid))
+                    .sum();
+
+                // Get PCR PID and PMT version for this program
+                let pcr_pid = processor.get_pcr_pid(*prog_num);
+                let pmt_version = processor.get_pmt_version(pmt_pid);
+
+                let service = processor.sdt_service(*prog_num);
+                let (logical_channel, hd_logical_channel) = processor.si_cache.logical_channel(*prog_num);
+
+                programs_out.push(ProgramJson {
+                    program: *prog_num,
+                    streams: es_vec,
+                    pcr_pid,
+                    pcr_shared_with_pid: pmt.pcr_shared_with_pid(),
+                    declared_max_bitrate_kbps: program_max_kbps,
+                    max_bitrate_exceeded: program_max_kbps.is_some_and(|max| program_kbps > max),
+                    pcr_interval: processor.pcr_interval(pmt.pcr_pid),
+                    last_pcr: processor.last_pcr(pmt.pcr_pid),
+                    pmt_version,
+                    running_status: service.map(|s| running_status_name(s.running_status)),
+                    free_ca_mode: service.map(|s| s.free_ca_mode),
+                    scrambling: processor.program_scrambling(pmt),
+                    registration: registration(&pmt.descriptors).map(format_identifier_str),
+                    ca_system_ids: ca_system_ids(&pmt.descriptors),
+                    logical_channel,
+                    hd_logical_channel,
+                    epg: processor.epg_coverage(*prog_num),
+                    provider: service.and_then(|s| s.provider_name.as_deref()),
+                    stream_mismatches: processor.stream_mismatches(pmt),
+                });
             }
         }
 
         // Filter TR-101 metrics based on analysis mode
-        let filtered_tr101 = match analysis_mode {
-            Some(crate::types::AnalysisMode::Tr101Priority1) => tr101.priority_1_only(),
-            Some(crate::types::AnalysisMode::Tr101Priority12) => tr101.priority_1_and_2_only(),
-            _ => tr101,
-        };
+        let filtered_tr101 = tr101.for_mode(analysis_mode);
 
         let rep = ReportJson {
             ts_time: chrono::Utc::now().to_rfc3339(),
+            transport_stream_id: processor.si_cache.transport_stream_id(),
+            original_network_id: processor.si_cache.original_network_id(),
+            transport_stream_id_mismatch: processor.si_cache.check_transport_stream_id_mismatch(),
+            providers: Self::providers(programs_out.iter().map(|p| (p.provider, p.program))),
             programs: programs_out,
+            pids: processor.stats_manager.pid_inventory(),
+            dangling_pids: processor.dangling_pids(),
+            pid_role_conflicts: processor.pid_role_conflicts(),
+            program_pid_collisions: processor.program_pid_collisions(),
+            mux_bitrate_kbps: processor.stats_manager.mux_bitrate(processor.include_null_in_mux),
+            null_bitrate_kbps: processor.stats_manager.pid_bitrate(NULL_PID).unwrap_or(0.0),
+            bitrate_clock: processor.stats_manager.effective_bitrate_clock(),
+            socket_drops: processor.socket_drops,
+            dropped_datagrams: processor.dropped_datagrams,
+            misaligned_datagrams: processor.misaligned_datagrams,
+            transport: processor.transport.clone(),
             tr101: &filtered_tr101,
+            verdict: Self::verdict_for(&filtered_tr101),
         };
-        serde_json::to_string_pretty(&rep).unwrap_or_else(|_| "{\"error\": \"JSON serialization failed\"}".to_string())
+        let json = if pretty { serde_json::to_string_pretty(&rep) } else { serde_json::to_string(&rep) };
+        json.unwrap_or_else(|_| "{\"error\": \"JSON serialization failed\"}".to_string())
     }            
-}
\ No newline at end of file
+}
+
+impl StreamInfo {
+    /// The numeric stream metrics by field name (the JSON key), for generic monitoring
+    /// integrations. `bitrate_kbps`, `pts_present_ratio`, `missing_pts`,
+    /// `pts_pcr_delay_errors` and `pes_length_errors` are always present; the others
+    /// (`declared_max_bitrate_kbps`, `frames_delivered`, `measured_fps`, `audio_drift_ppm`,
+    /// `pts_dts_delta_ms`, `pts_pcr_delay_ms`) only once known. Keys are never renamed.
+    pub fn as_map(&self) -> BTreeMap<&'static str, f64> {
+        let mut map = BTreeMap::from([
+            ("bitrate_kbps", self.bitrate_kbps),
+            ("pts_present_ratio", self.pes.pts_present_ratio),
+            ("missing_pts", self.pes.missing_pts as f64),
+            ("pts_pcr_delay_errors", self.pes.pts_pcr_delay_errors as f64),
+            ("pes_length_errors", self.pes.pes_length_errors as f64),
+        ]);
+        let optional = [
+            ("declared_max_bitrate_kbps", self.declared_max_bitrate_kbps),
+            ("frames_delivered", self.frames_delivered.map(|n| n as f64)),
+            ("measured_fps", self.measured_fps),
+            ("audio_drift_ppm", self.audio_drift_ppm),
+            ("pts_dts_delta_ms", self.pes.pts_dts_delta_ms),
+            ("pts_pcr_delay_ms", self.pes.pts_pcr_delay_ms),
+        ];
+        map.extend(optional.into_iter().filter_map(|(key, value)| Some((key, value?))));
+        map
+    }
+}
+
+impl InspectorReport {
+    /// Write the captured `error_packets` back to back as a TS file for offline analysis
+    pub fn write_error_packets<W: std::io::Write>(&self, mut out: W) -> std::io::Result<()> {
+        for p in &self.error_packets {
+            out.write_all(&p.packet)?;
+        }
+        out.flush()
+    }
+
+    /// Changes since `previous`: programs and PIDs added/removed, codec, resolution and
+    /// fps changes of elementary streams, and TR 101 290 counters that grew
+    pub fn diff(&self, previous: &Self) -> ReportDiff {
+        let mut changes = Vec::new();
+
+        let programs = |r: &Self| r.programs.iter().map(|p| p.program_number).collect::<std::collections::BTreeSet<u16>>();
+        let (before, after) = (programs(previous), programs(self));
+        changes.extend(after.difference(&before).map(|&program_number| ReportChange::ProgramAdded { program_number }));
+        changes.extend(before.difference(&after).map(|&program_number| ReportChange::ProgramRemoved { program_number }));
+
+        let pids = |r: &Self| r.pids.iter().map(|p| p.pid).collect::<std::collections::BTreeSet<u16>>();
+        let (before, after) = (pids(previous), pids(self));
+        changes.extend(after.difference(&before).map(|&pid| ReportChange::PidAdded { pid }));
+        changes.extend(before.difference(&after).map(|&pid| ReportChange::PidRemoved { pid }));
+
+        // Only streams whose codec was identified in both reports can have changed
+        let codecs = |r: &Self| {
+            r.programs
+                .iter()
+                .flat_map(|p| &p.streams)
+                .filter_map(|s| Some((s.pid, s.codec.clone()?)))
+                .collect::<std::collections::BTreeMap<u16, CodecInfo>>()
+        };
+        let before = codecs(previous);
+        for (pid, codec) in codecs(self) {
+            let Some(old) = before.get(&pid) else { continue };
+            let name = |c: &CodecInfo| match c {
+                CodecInfo::Video(v) => v.codec.clone(),
+                CodecInfo::Audio(a) => a.codec.clone(),
+                CodecInfo::Subtitle(s) => s.codec.clone(),
+            };
+            if name(old) != name(&codec) {
+                changes.push(ReportChange::CodecChanged { pid, from: name(old), to: name(&codec) });
+            }
+            if let (CodecInfo::Video(old), CodecInfo::Video(new)) = (old, &codec) {
+                if (old.width, old.height) != (new.width, new.height) {
+                    changes.push(ReportChange::ResolutionChanged {
+                        pid,
+                        from: (old.width, old.height),
+                        to: (new.width, new.height),
+                    });
+                }
+                if old.fps != new.fps {
+                    changes.push(ReportChange::FpsChanged { pid, from: old.fps, to: new.fps });
+                }
+            }
+        }
+
+        let counters = |m: &Tr101Metrics| {
+            let mut all = m.priority_1_counters().to_vec();
+            all.extend(m.priority_2_counters());
+            all.extend(m.priority_3_counters());
+            all
+        };
+        for ((counter, to), (_, from)) in counters(&self.tr101_metrics).into_iter().zip(counters(&previous.tr101_metrics)) {
+            if to > from {
+                changes.push(ReportChange::CounterIncreased { counter, from, to });
+            }
+        }
+
+        ReportDiff { changes }
+    }
+}
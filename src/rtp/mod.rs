@@ -0,0 +1,186 @@
+//! RTP de-encapsulation and SMPTE 2022-7 style hitless merging
+
+use std::collections::BTreeMap;
+use crate::types::RtpLegStats;
+
+const RTP_VERSION: u8 = 2;
+const RTP_HEADER_LEN: usize = 12;
+
+/// Parsed RTP packet (RFC 3550); payload excludes CSRCs, extension and padding
+pub struct RtpPacket<'a> {
+    pub sequence: u16,
+    pub payload: &'a [u8],
+}
+
+/// Parse an RTP header, returning None if the datagram isn't RTP v2
+pub fn parse_rtp(buf: &[u8]) -> Option<RtpPacket<'_>> {
+    if buf.len() < RTP_HEADER_LEN || buf[0] >> 6 != RTP_VERSION {
+        return None;
+    }
+    let padding = buf[0] & 0x20 != 0;
+    let extension = buf[0] & 0x10 != 0;
+    let csrc_count = (buf[0] & 0x0F) as usize;
+
+    let sequence = u16::from_be_bytes([buf[2], buf[3]]);
+
+    let mut start = RTP_HEADER_LEN + csrc_count * 4;
+    if extension {
+        let ext = buf.get(start..start + 4)?;
+        let ext_words = u16::from_be_bytes([ext[2], ext[3]]) as usize;
+        start += 4 + ext_words * 4;
+    }
+    let mut end = buf.len();
+    if padding {
+        end = end.checked_sub(*buf.last()? as usize)?;
+    }
+
+    Some(RtpPacket {
+        sequence,
+        payload: buf.get(start..end)?,
+    })
+}
+
+/// Per-input sequence tracking
+struct Leg {
+    last_seq: Option<u16>,
+    packets_received: u64,
+    lost: u64,
+}
+
+/// Merges redundant RTP legs carrying identical sequence numbers.
+///
+/// Packets are held in a reorder buffer keyed by extended sequence number and
+/// released oldest-first once more than `depth` are queued, so the merge adds
+/// `depth` packet intervals of latency (e.g. depth 32 at 10 Mbps with 7 TS
+/// packets per datagram ≈ 34 ms). Anything arriving after its slot was
+/// released is dropped; gaps still open at release time count as merged loss.
+/// When the inputs end, [`flush`](Self::flush) releases what is still queued.
+pub struct HitlessMerger {
+    depth: usize,
+    legs: Vec<Leg>,
+    buffer: BTreeMap<u64, Vec<u8>>,
+    highest: Option<u64>,
+    next_out: Option<u64>,
+    merged_loss: u64,
+}
+
+impl HitlessMerger {
+    pub fn new(legs: usize, depth: usize) -> Self {
+        Self {
+            depth: depth.max(1),
+            legs: (0..legs)
+                .map(|_| Leg { last_seq: None, packets_received: 0, lost: 0 })
+                .collect(),
+            buffer: BTreeMap::new(),
+            highest: None,
+            next_out: None,
+            merged_loss: 0,
+        }
+    }
+
+    /// Feed a packet received on `leg`, returning payloads released in sequence order
+    pub fn push(&mut self, leg: usize, pkt: RtpPacket) -> Vec<Vec<u8>> {
+        if let Some(l) = self.legs.get_mut(leg) {
+            l.packets_received += 1;
+            match l.last_seq {
+                None => l.last_seq = Some(pkt.sequence),
+                Some(last) => {
+                    let delta = pkt.sequence.wrapping_sub(last) as i16;
+                    if delta > 0 {
+                        l.lost += (delta - 1) as u64;
+                        l.last_seq = Some(pkt.sequence);
+                    }
+                }
+            }
+        }
+
+        let ext = self.extend(pkt.sequence);
+        if self.next_out.is_some_and(|next| ext < next) {
+            return Vec::new(); // duplicate from the other leg, or too late
+        }
+        self.buffer.entry(ext).or_insert_with(|| pkt.payload.to_vec());
+        self.release(self.depth)
+    }
+
+    /// Release every queued payload in sequence order, e.g. when the inputs stop;
+    /// gaps between them count as merged loss
+    pub fn flush(&mut self) -> Vec<Vec<u8>> {
+        self.release(0)
+    }
+
+    /// Release the oldest payloads until at most `keep` are queued
+    fn release(&mut self, keep: usize) -> Vec<Vec<u8>> {
+        let mut released = Vec::new();
+        while self.buffer.len() > keep {
+            let Some((seq, payload)) = self.buffer.pop_first() else { break };
+            if let Some(next) = self.next_out {
+                self.merged_loss += seq - next;
+            }
+            self.next_out = Some(seq + 1);
+            released.push(payload);
+        }
+        released
+    }
+
+    /// Unwrap a 16-bit sequence number relative to the highest seen so far
+    fn extend(&mut self, seq: u16) -> u64 {
+        let ext = match self.highest {
+            // Start well above zero so early reordering can't underflow
+            None => (1 << 32) + seq as u64,
+            Some(high) => {
+                let delta = seq.wrapping_sub(high as u16) as i16;
+                high.wrapping_add_signed(delta as i64)
+            }
+        };
+        if self.highest.is_none_or(|high| ext > high) {
+            self.highest = Some(ext);
+        }
+        ext
+    }
+
+    pub fn merged_loss(&self) -> u64 {
+        self.merged_loss
+    }
+
+    pub fn leg_stats(&self, addrs: &[std::net::SocketAddr]) -> Vec<RtpLegStats> {
+        self.legs
+            .iter()
+            .zip(addrs)
+            .map(|(l, addr)| RtpLegStats {
+                addr: *addr,
+                packets_received: l.packets_received,
+                rtp_packet_loss: l.lost,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rtp(sequence: u16, payload: u8) -> Vec<u8> {
+        let mut buf = vec![0x80, 33, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1, payload];
+        buf[2..4].copy_from_slice(&sequence.to_be_bytes());
+        buf
+    }
+
+    #[test]
+    fn flush_releases_the_queued_packets_in_order() {
+        let mut merger = HitlessMerger::new(2, 4);
+        let mut released = Vec::new();
+        // Leg 1 is missing 3, leg 0 is missing 2 and 5; 6 is lost on both
+        for (leg, seq) in [(0, 1), (1, 1), (1, 2), (0, 3), (0, 4), (1, 4), (1, 5), (0, 7), (1, 7)] {
+            released.extend(merger.push(leg, parse_rtp(&rtp(seq, seq as u8)).unwrap()));
+        }
+        assert_eq!(released, [vec![1], vec![2]]);
+
+        released.extend(merger.flush());
+        assert_eq!(released, [vec![1], vec![2], vec![3], vec![4], vec![5], vec![7]]);
+        assert_eq!(merger.merged_loss(), 1);
+        assert!(merger.flush().is_empty());
+
+        // Late packets stay dropped after a flush
+        assert!(merger.push(0, parse_rtp(&rtp(6, 6)).unwrap()).is_empty());
+    }
+}
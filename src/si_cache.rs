@@ -1,4 +1,5 @@
-use crate::psi::{nit::NitSection, pat::PatSection, pmt::PmtSection, sdt::SdtSection};
+use crate::psi::{nit::{NitSection, Transport}, pat::PatSection, pmt::PmtSection, sdt::SdtSection};
+use crate::types::{LogicalChannel, SiTables};
 
 #[derive(Default)]
 pub struct SiCache {
@@ -15,6 +16,55 @@ impl SiCache {
     pub fn update_sdt(&mut self, sdt: SdtSection) { self.sdt = Some(sdt); }
     pub fn update_nit(&mut self, nit: NitSection) { self.nit = Some(nit); }
 
+    /// Copy of the cached tables for API consumers
+    pub fn snapshot(&self) -> SiTables {
+        SiTables {
+            pat: self.pat.clone(),
+            pmts: self.pmts.clone(),
+            sdt: self.sdt.clone(),
+            nit: self.nit.clone(),
+        }
+    }
+
+    /// transport_stream_id of this TS: the PAT's, else SDT-actual's
+    pub fn transport_stream_id(&self) -> Option<u16> {
+        self.pat.as_ref().map(|p| p.transport_stream_id)
+            .or_else(|| self.sdt.as_ref().map(|s| s.transport_stream_id))
+    }
+
+    /// original_network_id from SDT-actual, else from this TS's entry in NIT-actual
+    pub fn original_network_id(&self) -> Option<u16> {
+        if let Some(sdt) = &self.sdt {
+            return Some(sdt.original_network_id);
+        }
+        self.nit_transport().map(|t| t.orig_net_id)
+    }
+
+    /// This TS's entry in NIT-actual
+    fn nit_transport(&self) -> Option<&Transport> {
+        let tsid = self.transport_stream_id()?;
+        let onid = self.sdt.as_ref().map(|s| s.original_network_id);
+        self.nit.as_ref()?.transports.iter()
+            .find(|t| t.ts_id == tsid && onid.is_none_or(|onid| t.orig_net_id == onid))
+    }
+
+    /// (LCN, HD simulcast LCN) of a service of this TS from NIT-actual
+    pub fn logical_channel(&self, service_id: u16) -> (Option<LogicalChannel>, Option<LogicalChannel>) {
+        let Some(t) = self.nit_transport() else { return (None, None) };
+        (t.logical_channels.get(&service_id).copied(), t.hd_logical_channels.get(&service_id).copied())
+    }
+
+    /// PAT transport_stream_id differs from SDT-actual's, or NIT-actual lists
+    /// transport streams but not this one
+    pub fn check_transport_stream_id_mismatch(&self) -> bool {
+        let Some(pat) = &self.pat else { return false };
+        let tsid = pat.transport_stream_id;
+        let sdt_mismatch = self.sdt.as_ref().is_some_and(|s| s.transport_stream_id != tsid);
+        let nit_mismatch = self.nit.as_ref()
+            .is_some_and(|n| !n.transports.is_empty() && n.transports.iter().all(|t| t.ts_id != tsid));
+        sdt_mismatch || nit_mismatch
+    }
+
     /// 3.2-d Service_ID mismatch between SDT and PMT list
     pub fn check_service_id_mismatch(&self) -> bool {
         let sdt = match &self.sdt { Some(s) => s, None => return false };
@@ -1,32 +1,236 @@
-//! Statistics management for elementary streams
+//! Statistics management for elementary streams and PIDs
 
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
-use crate::types::{EsStats, CodecInfo};
+use crate::constants::{PCR_CLOCK_HZ, PCR_WRAP_THRESHOLD, PTS_CLOCK_HZ, SCRAMBLING_WINDOW_SECONDS, TS_PACKET_SIZE, NULL_PID};
+use crate::types::{EsStats, CodecInfo, PesTimestampInfo, PidStats, PidInfo, BitrateClock};
 
 /// Manages elementary stream statistics and cleanup
 pub struct StatsManager {
     pub es_stats: HashMap<u16, EsStats>,
+    pub pid_stats: HashMap<u16, PidStats>,
+    pub bitrate_clock: BitrateClock,
+    pub clock: Option<StreamClock>,
+}
+
+/// Stream time from the PCRs of one reference PID. Jumps, and a switch to another
+/// PCR PID, are bridged at the byte rate measured so far.
+pub struct StreamClock {
+    pub pid: Option<u16>, // Reference PCR PID; None once it has left every PMT
+    pub elapsed: u64,     // 27 MHz ticks since the first PCR
+    last_ticks: u64,
+    first_bytes: u64,     // Transport bytes at the first and the latest PCR
+    last_bytes: u64,
 }
 
 impl StatsManager {
     pub fn new() -> Self {
         Self {
             es_stats: HashMap::new(),
+            pid_stats: HashMap::new(),
+            bitrate_clock: BitrateClock::default(),
+            clock: None,
+        }
+    }
+
+    /// Advance the stream clock with a PCR (27 MHz) found at `total_bytes` into the transport
+    pub fn record_pcr(&mut self, pid: u16, pcr_ticks: u64, discontinuity: bool, total_bytes: u64) {
+        let Some(clock) = self.clock.as_mut() else {
+            self.clock = Some(StreamClock {
+                pid: Some(pid),
+                elapsed: 0,
+                last_ticks: pcr_ticks,
+                first_bytes: total_bytes,
+                last_bytes: total_bytes,
+            });
+            return;
+        };
+        if clock.pid.is_some_and(|reference| reference != pid) {
+            return;
+        }
+        let ticks = if pcr_ticks >= clock.last_ticks {
+            pcr_ticks - clock.last_ticks
+        } else {
+            PCR_WRAP_THRESHOLD - clock.last_ticks + pcr_ticks
+        };
+        let step = if clock.pid.is_some() && !discontinuity && ticks < PCR_CLOCK_HZ as u64 {
+            ticks
+        } else if clock.last_bytes > clock.first_bytes {
+            // New timebase or reference PID: assume the rate so far
+            ((total_bytes - clock.last_bytes) as u128 * clock.elapsed as u128
+                / (clock.last_bytes - clock.first_bytes) as u128) as u64
+        } else {
+            0
+        };
+        clock.pid = Some(pid);
+        clock.elapsed += step;
+        clock.last_ticks = pcr_ticks;
+        clock.last_bytes = total_bytes;
+    }
+
+    /// Clock the bitrates are measured against right now
+    pub fn effective_bitrate_clock(&self) -> BitrateClock {
+        match self.bitrate_clock {
+            BitrateClock::Pcr if self.clock.as_ref().is_some_and(|c| c.elapsed > 0) => BitrateClock::Pcr,
+            _ => BitrateClock::Wallclock,
+        }
+    }
+
+    /// Seconds something first seen at `since` (wallclock) / `since_clock` (stream clock) was measured over
+    fn measured_secs(&self, since: Instant, since_clock: u64) -> f64 {
+        let secs = match (self.effective_bitrate_clock(), &self.clock) {
+            (BitrateClock::Pcr, Some(clock)) => clock.elapsed.saturating_sub(since_clock) as f64 / PCR_CLOCK_HZ,
+            _ => since.elapsed().as_secs_f64(),
+        };
+        secs.max(0.1)
+    }
+
+    fn clock_now(&self) -> u64 {
+        self.clock.as_ref().map_or(0, |c| c.elapsed)
+    }
+
+    /// Count a packet against its PID's header statistics
+    pub fn record_packet(&mut self, pid: u16, adaptation_field_ctrl: u8, transport_priority: bool, scrambling_ctrl: u8) {
+        let now = Instant::now();
+        let clock_now = self.clock_now();
+        let stats = self.pid_stats.entry(pid).or_insert_with(|| PidStats {
+            packets: 0,
+            afc_counts: [0; 4],
+            transport_priority: 0,
+            adaptation_field_errors: 0,
+            first_seen: now,
+            first_clock: clock_now,
+            last_seen: now,
+            scrambled_in_window: 0,
+            clear_in_window: 0,
+            window_start: now,
+            scrambled: None,
+        });
+        stats.packets += 1;
+        stats.afc_counts[(adaptation_field_ctrl & 0x03) as usize] += 1;
+        if transport_priority {
+            stats.transport_priority += 1;
+        }
+        stats.last_seen = now;
+
+        // Adaptation-only packets always carry scrambling_control 00, so only payloads count
+        if adaptation_field_ctrl & 0x01 != 0 {
+            if scrambling_ctrl != 0 {
+                stats.scrambled_in_window += 1;
+            } else {
+                stats.clear_in_window += 1;
+            }
+        }
+        if now.duration_since(stats.window_start) >= Duration::from_secs(SCRAMBLING_WINDOW_SECONDS) {
+            if stats.scrambled_in_window + stats.clear_in_window > 0 {
+                stats.scrambled = Some(stats.scrambled_in_window > stats.clear_in_window);
+            }
+            stats.scrambled_in_window = 0;
+            stats.clear_in_window = 0;
+            stats.window_start = now;
+        }
+    }
+
+    /// Count a malformed adaptation_field_length; record_packet must have seen the PID
+    pub fn record_adaptation_field_error(&mut self, pid: u16) {
+        if let Some(stats) = self.pid_stats.get_mut(&pid) {
+            stats.adaptation_field_errors += 1;
         }
     }
 
+    /// Whether a PID is scrambled: the last completed window's verdict, or the
+    /// current window's majority until one has completed
+    pub fn pid_scrambled(&self, pid: u16) -> Option<bool> {
+        let stats = self.pid_stats.get(&pid)?;
+        stats.scrambled.or_else(|| {
+            (stats.scrambled_in_window + stats.clear_in_window > 0)
+                .then_some(stats.scrambled_in_window > stats.clear_in_window)
+        })
+    }
+
+    /// Per-PID packet statistics sorted by PID
+    pub fn pid_inventory(&self) -> Vec<PidInfo> {
+        let mut pids: Vec<PidInfo> = self
+            .pid_stats
+            .iter()
+            .map(|(&pid, s)| PidInfo {
+                pid,
+                packets: s.packets,
+                payload_only: s.afc_counts[1],
+                adaptation_only: s.afc_counts[2],
+                adaptation_and_payload: s.afc_counts[3],
+                reserved_afc: s.afc_counts[0],
+                transport_priority: s.transport_priority,
+                adaptation_field_errors: s.adaptation_field_errors,
+                bitrate_kbps: self.pid_stats_bitrate(s),
+                label: (pid == NULL_PID).then(|| "null".to_string()),
+            })
+            .collect();
+        pids.sort_by_key(|p| p.pid);
+        pids
+    }
+
+    /// Average bitrate of a PID in kbps since it was first seen
+    pub fn pid_bitrate(&self, pid: u16) -> Option<f64> {
+        self.pid_stats.get(&pid).map(|s| self.pid_stats_bitrate(s))
+    }
+
+    /// Sum of all PID bitrates in kbps, optionally leaving out null packets
+    pub fn mux_bitrate(&self, include_null: bool) -> f64 {
+        self.pid_stats
+            .iter()
+            .filter(|&(&pid, _)| include_null || pid != NULL_PID)
+            .map(|(_, s)| self.pid_stats_bitrate(s))
+            .sum()
+    }
+
+    fn pid_stats_bitrate(&self, stats: &PidStats) -> f64 {
+        let seconds = self.measured_secs(stats.first_seen, stats.first_clock);
+        (stats.packets as f64 * TS_PACKET_SIZE as f64 * 8.0 / 1000.0) / seconds
+    }
+
     /// Add a new elementary stream to track
     pub fn add_stream(&mut self, pid: u16, stream_type: u8) {
+        let now = Instant::now();
+        let start_clock = self.clock_now();
         self.es_stats.insert(
             pid,
             EsStats {
                 stream_type,
                 codec: None,
                 bytes: 0,
-                start: Instant::now(),
+                start: now,
+                start_clock,
+                last_seen: now,
                 last_pts: None,
                 pts_samples: Vec::new(),
+                pes_timed: 0,
+                pes_with_pts: 0,
+                pes_with_dts: 0,
+                last_pts_dts_delta: None,
+                last_pts_pcr_delay: None,
+                pts_pcr_delay_errors: 0,
+                pes_expected_bytes: None,
+                pes_bytes: 0,
+                pes_length_errors: 0,
+                aus_since_idr: None,
+                au_pts: None,
+                au_has_idr: false,
+                last_idr_pts: None,
+                last_idr_seen: now,
+                latm_config: None,
+                frames_delivered: 0,
+                pes_frames: 0,
+                frame_window: None,
+                pes_frame_ticks: 0.0,
+                audio_timing: None,
+                audio_drift_ppm: None,
+                measured_fps: None,
+                audio_synced: false,
+                audio_frame_skip: 0,
+                audio_header: Vec::new(),
+                video_header: Vec::new(),
+                video_header_skip: None,
             },
         );
     }
@@ -35,6 +239,7 @@ impl StatsManager {
     pub fn update_bytes(&mut self, pid: u16, bytes: usize) {
         if let Some(stats) = self.es_stats.get_mut(&pid) {
             stats.bytes += bytes;
+            stats.last_seen = Instant::now();
         }
     }
 
@@ -45,13 +250,6 @@ impl StatsManager {
         }
     }
 
-    /// Update PTS for a stream (used for FPS calculation)
-    pub fn update_pts(&mut self, pid: u16, pts: u64) {
-        if let Some(stats) = self.es_stats.get_mut(&pid) {
-            stats.last_pts = Some(pts);
-        }
-    }
-
     /// Get mutable reference to stream stats
     pub fn get_mut(&mut self, pid: u16) -> Option<&mut EsStats> {
         self.es_stats.get_mut(&pid)
@@ -67,26 +265,41 @@ impl StatsManager {
         self.es_stats.contains_key(&pid)
     }
 
-    /// Remove old/inactive streams (older than timeout)
+    /// Remove inactive streams and PIDs (no packets seen within timeout)
     pub fn cleanup_old_streams(&mut self, timeout: Duration) {
-        self.es_stats.retain(|_, stats| stats.start.elapsed() < timeout);
+        self.es_stats.retain(|_, stats| stats.last_seen.elapsed() < timeout);
+        self.pid_stats.retain(|_, stats| stats.last_seen.elapsed() < timeout);
     }
 
     /// Calculate bitrate for a stream in kbps
     pub fn calculate_bitrate(&self, pid: u16) -> Option<f64> {
         let stats = self.es_stats.get(&pid)?;
-        let seconds = stats.start.elapsed().as_secs_f64().max(0.1);
+        let seconds = self.measured_secs(stats.start, stats.start_clock);
         Some((stats.bytes as f64 * 8.0 / 1000.0) / seconds)
     }
 
-    /// Get all tracked PIDs
-    pub fn get_all_pids(&self) -> Vec<u16> {
-        self.es_stats.keys().copied().collect()
-    }
-
-    /// Get iterator over all stats
-    pub fn iter(&self) -> impl Iterator<Item = (&u16, &EsStats)> {
-        self.es_stats.iter()
+    /// Summarise PES timestamp presence for a stream
+    pub fn pes_timestamp_info(&self, pid: u16) -> Option<PesTimestampInfo> {
+        let stats = self.es_stats.get(&pid)?;
+        let pts_present_ratio = if stats.pes_timed > 0 {
+            stats.pes_with_pts as f64 / stats.pes_timed as f64
+        } else {
+            0.0
+        };
+        Some(PesTimestampInfo {
+            has_pts: stats.pes_with_pts > 0,
+            has_dts: stats.pes_with_dts > 0,
+            pts_present_ratio,
+            missing_pts: stats.pes_timed - stats.pes_with_pts,
+            pts_dts_delta_ms: stats
+                .last_pts_dts_delta
+                .map(|d| d as f64 * 1000.0 / PTS_CLOCK_HZ as f64),
+            pts_pcr_delay_ms: stats
+                .last_pts_pcr_delay
+                .map(|d| d as f64 * 1000.0 / PTS_CLOCK_HZ as f64),
+            pts_pcr_delay_errors: stats.pts_pcr_delay_errors,
+            pes_length_errors: stats.pes_length_errors,
+        })
     }
 }
 
@@ -1,9 +1,10 @@
 // src/tr101.rs
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::time::{Duration, Instant};
 
 use serde::Serialize;
-use crate::types::{PacketContext, CrcValidation};
+use serde::ser::{SerializeMap, SerializeStruct, Serializer};
+use crate::types::{AnalysisMode, PacketContext, CrcValidation};
 use crate::constants::*;
 
 // Local constants specific to TR-101 implementation
@@ -12,6 +13,21 @@ use crate::constants::*;
 /// We use a slightly larger value to account for measurement precision
 const PCR_ACCURACY_TICKS: i64 = 14;
 
+/// Without a bitrate baseline, a forward PCR step this large (or any backward
+/// step) can't be a missed PCR and is treated as a discontinuity
+const PCR_JUMP_FALLBACK_MS: u64 = 5000;
+
+/// Number of PAT/PMT version changes kept in `version_changes`
+const VERSION_HISTORY_LEN: usize = 32;
+
+/// Number of sync loss episodes kept in `sync_loss_history`
+const SYNC_LOSS_HISTORY_LEN: usize = 32;
+
+/// Counters reported with their priority but left out of the error sums: PAT/PMT
+/// version changes are normal stream events, and sync_loss_episodes regroups the
+/// losses already counted in ts_sync_loss
+const UNSUMMED_COUNTERS: [&str; 3] = ["sync_loss_episodes", "pat_version_changes", "pmt_version_changes"];
+
 /// PCR tracking information for accuracy validation
 #[derive(Debug, Clone)]
 struct PcrInfo {
@@ -21,11 +37,41 @@ struct PcrInfo {
     bitrate_samples: Vec<f64>, // bits per second
 }
 
-#[derive(Default, Debug, Clone,Serialize)]
+/// Error rates over the last reporting window (counter growth / window length)
+#[derive(Default, Debug, Clone, Serialize)]
+pub struct Tr101Rates {
+    pub window_secs: f64,
+    pub continuity_counter_errors_per_sec: f64,
+    pub sync_errors_per_sec: f64, // sync_byte_errors + ts_sync_loss
+    pub transport_error_indicator_per_sec: f64,
+    pub crc_errors_per_sec: f64,  // all PSI/SI CRC counters
+}
+
+/// A PAT/PMT version_number change, kept for debugging receiver re-scans
+#[derive(Debug, Clone, Serialize)]
+pub struct VersionChange {
+    pub table: &'static str, // "PAT" or "PMT"
+    pub id: u16,             // program_number (PAT) or PMT PID
+    pub old: u8,
+    pub new: u8,
+    pub timestamp: String,   // RFC 3339, UTC
+}
+
+/// One TS sync loss episode, kept for outage analysis
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncLossEpisode {
+    pub started: String,          // RFC 3339, UTC
+    pub duration_ms: Option<u64>, // None while sync is still lost
+}
+
+/// TR 101 290 counters plus detail. The JSON groups the counters into `priority_1`,
+/// `priority_2` and `priority_3` objects, leaving out the priorities not checked.
+#[derive(Default, Debug, Clone)]
 pub struct Tr101Metrics {
     // Priority-1 counters
     pub sync_byte_errors:            u64, // 1.1
-    pub ts_sync_loss:                u64, // 1.1b (TS synchronization loss)
+    pub ts_sync_loss:                u64, // 1.1b (TS synchronization loss, per bad packet)
+    pub sync_loss_episodes:          u64, // 1.1b (TS synchronization loss, once per episode)
     pub transport_error_indicator:   u64, // 1.2
     pub pat_crc_errors:              u64, // 1.3a
     pub pat_timeout:                 u64, // 1.3b
@@ -37,6 +83,7 @@ pub struct Tr101Metrics {
     /* ───────── Priority-2 (new) ───────── */
     pub pcr_repetition_errors:       u64, // 2.4
     pub pcr_accuracy_errors:         u64, // 2.5
+    pub pcr_discontinuity_errors:    u64, // 2.3b (unsignaled PCR jump)
     pub null_packet_rate_errors:    u64, // 2.6
     pub cat_crc_errors:             u64, // 2.7a
     pub cat_timeout:                u64, // 2.7b
@@ -53,52 +100,104 @@ pub struct Tr101Metrics {
      pub eit_crc_errors:             u64, // 3.3a
      pub eit_timeout:                u64, // 3.3b
      pub tdt_timeout:                u64, // 3.4   (TDT/TOT presence)
+     pub tot_crc_errors:             u64, // 3.4a  (TOT only; TDT has no CRC)
      pub splice_count_errors: u64, // 3.5
 
+    // Detail for transport_error_indicator: pid → TEI-flagged packets
+    pub tei_per_pid: HashMap<u16, u64>,
+
+    // Legal duplicates: a packet repeated once with the same CC and payload (not counted in 1.4)
+    pub duplicate_packets: u64,
+
+    // 33-bit clock wrap-arounds (~26.5 h), counted where the checks absorb them
+    pub pcr_wraps: u64,
+    pub last_pcr_wrap: Option<String>,        // RFC 3339, UTC
+    pub pts_wraps_per_pid: HashMap<u16, u64>, // pid → PTS wraps
+
+    // Detail for pat/pmt_version_changes, most recent VERSION_HISTORY_LEN only
+    pub version_changes: VecDeque<VersionChange>,
+
+    // Detail for sync_loss_episodes: time without sync over all ended episodes, and the
+    // most recent SYNC_LOSS_HISTORY_LEN episodes
+    pub total_sync_loss_duration_ms: u64,
+    pub sync_loss_history: VecDeque<SyncLossEpisode>,
+
+    // Rates since the previous report, refreshed by update_rates()
+    pub rates: Tr101Rates,
+
+    // Transport bitrate over the last 2.6 window, from PacketContext::total_bytes_processed
+    pub transport_bitrate_kbps: f64,
+
     // internal state
-    #[serde(skip)]
     last_pat_seen: Option<Instant>,
-    #[serde(skip)]
     last_pmt_seen: HashMap<u16, Instant>, // pmt_pid → last time seen
     last_cc: HashMap<u16, u8>,            // pid → last continuity counter
-    #[serde(skip)]
+    cc_repeats: HashMap<u16, u8>,         // pid → consecutive packets repeating last_cc
     pat_versions: HashMap<u16, u8>,       // program_number → last version
-    #[serde(skip)]
     pmt_versions: HashMap<u16, u8>,       // pmt_pid → last version
-    #[serde(skip)]
     last_pcr_info: HashMap<u16, PcrInfo>, // pid → PCR tracking info
-    #[serde(skip)]
-    bytes_in_1s:           u64,
-    #[serde(skip)]
+    rate_window_start_bytes: u64, // total_bytes_processed when the 2.6 window opened
     null_bytes_in_1s:      u64,
-    #[serde(skip)]
     last_rate_check:       Option<Instant>,
-    #[serde(skip)]
     last_cat_seen:         Option<Instant>,
-    #[serde(skip)]
     last_nit_seen:         Option<Instant>,
-    #[serde(skip)]
     last_sdt_seen:         Option<Instant>,
-    #[serde(skip)]
     last_eit_seen:         Option<Instant>,
-    #[serde(skip)]
     last_tdt_seen:         Option<Instant>,
-    #[serde(skip)]
     pub last_splice_value: Option<i8>,
-    #[serde(skip)]
     startup_time: Option<Instant>,
-    #[serde(skip)]
     pat_timeout_state: bool,  // Track if PAT is currently in timeout state
-    #[serde(skip)]
     pmt_timeout_state: HashMap<u16, bool>,  // Track PMT timeout state per PID
-    #[serde(skip)]
-    cat_timeout_state: bool,  // Track if CAT is currently in timeout state
-    #[serde(skip)]
     known_pids: std::collections::HashSet<u16>,  // PIDs that are authorized/expected
-    #[serde(skip)]
     last_pts_per_pid: HashMap<u16, u64>,  // Track last PTS per PID for discontinuity detection
-    #[serde(skip)]
     sync_loss_counter: u64,  // Track consecutive sync loss occurrences
+    sync_lost_at: Option<Instant>,     // Start of the current sync loss episode
+    sync_regained_at: Option<Instant>, // First good sync byte of the current recovery run
+    sync_good_run: u64,                // Consecutive good sync bytes while sync is lost
+    rate_snapshot: Option<(Instant, [u64; 4])>,  // Counters at the previous update_rates()
+    omitted_priorities: [bool; 3],  // Priority groups left out of the JSON (not checked by the mode)
+}
+
+impl Serialize for Tr101Metrics {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let groups = [
+            ("priority_1", self.priority_1_counters().to_vec()),
+            ("priority_2", self.priority_2_counters().to_vec()),
+            ("priority_3", self.priority_3_counters().to_vec()),
+        ];
+        let mut s = serializer.serialize_struct("Tr101Metrics", 13)?;
+        for ((key, counters), omitted) in groups.iter().zip(self.omitted_priorities) {
+            if omitted {
+                s.skip_field(key)?;
+            } else {
+                s.serialize_field(key, &CounterGroup(counters))?;
+            }
+        }
+        s.serialize_field("tei_per_pid", &self.tei_per_pid)?;
+        s.serialize_field("duplicate_packets", &self.duplicate_packets)?;
+        s.serialize_field("pcr_wraps", &self.pcr_wraps)?;
+        s.serialize_field("last_pcr_wrap", &self.last_pcr_wrap)?;
+        s.serialize_field("pts_wraps_per_pid", &self.pts_wraps_per_pid)?;
+        s.serialize_field("version_changes", &self.version_changes)?;
+        s.serialize_field("total_sync_loss_duration_ms", &self.total_sync_loss_duration_ms)?;
+        s.serialize_field("sync_loss_history", &self.sync_loss_history)?;
+        s.serialize_field("rates", &self.rates)?;
+        s.serialize_field("transport_bitrate_kbps", &self.transport_bitrate_kbps)?;
+        s.end()
+    }
+}
+
+/// Counters of one priority as a JSON object, in TR 101 290 order
+struct CounterGroup<'a>(&'a [(&'static str, u64)]);
+
+impl Serialize for CounterGroup<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut map = serializer.serialize_map(Some(self.0.len()))?;
+        for (name, count) in self.0 {
+            map.serialize_entry(name, count)?;
+        }
+        map.end()
+    }
 }
 
 impl Tr101Metrics {
@@ -108,134 +207,171 @@ impl Tr101Metrics {
             startup_time: Some(Instant::now()),
             pat_timeout_state: false,
             pmt_timeout_state: HashMap::new(),
-            cat_timeout_state: false,
+            rate_snapshot: Some((Instant::now(), [0; 4])), // first report's rates cover startup
             ..Self::default()
         }
     }
 
+    /// Recompute `rates` from counter growth since the previous call; call once per report
+    pub fn update_rates(&mut self) {
+        let now = Instant::now();
+        let counters = [
+            self.continuity_counter_errors,
+            self.sync_byte_errors + self.ts_sync_loss,
+            self.transport_error_indicator,
+            self.pat_crc_errors + self.pmt_crc_errors + self.cat_crc_errors
+                + self.nit_crc_errors + self.sdt_crc_errors + self.eit_crc_errors + self.tot_crc_errors,
+        ];
+        if let Some((then, previous)) = self.rate_snapshot {
+            let secs = now.duration_since(then).as_secs_f64();
+            if secs > 0.0 {
+                let rate = |i: usize| counters[i].saturating_sub(previous[i]) as f64 / secs;
+                self.rates = Tr101Rates {
+                    window_secs: secs,
+                    continuity_counter_errors_per_sec: rate(0),
+                    sync_errors_per_sec: rate(1),
+                    transport_error_indicator_per_sec: rate(2),
+                    crc_errors_per_sec: rate(3),
+                };
+            }
+        }
+        self.rate_snapshot = Some((now, counters));
+    }
+
+    /// Sum of the Priority 1 error counters (each sync loss counted once)
+    pub fn priority_1_errors(&self) -> u64 {
+        sum_errors(&self.priority_1_counters())
+    }
+
+    /// Priority 1 counters by field name
+    pub fn priority_1_counters(&self) -> [(&'static str, u64); 10] {
+        [
+            ("sync_byte_errors", self.sync_byte_errors),
+            ("ts_sync_loss", self.ts_sync_loss),
+            ("sync_loss_episodes", self.sync_loss_episodes),
+            ("transport_error_indicator", self.transport_error_indicator),
+            ("pat_crc_errors", self.pat_crc_errors),
+            ("pat_timeout", self.pat_timeout),
+            ("continuity_counter_errors", self.continuity_counter_errors),
+            ("pmt_crc_errors", self.pmt_crc_errors),
+            ("pmt_timeout", self.pmt_timeout),
+            ("pid_errors", self.pid_errors),
+        ]
+    }
+
+    /// Priority 2 counters by field name
+    pub fn priority_2_counters(&self) -> [(&'static str, u64); 9] {
+        [
+            ("pcr_repetition_errors", self.pcr_repetition_errors),
+            ("pcr_accuracy_errors", self.pcr_accuracy_errors),
+            ("pcr_discontinuity_errors", self.pcr_discontinuity_errors),
+            ("null_packet_rate_errors", self.null_packet_rate_errors),
+            ("cat_crc_errors", self.cat_crc_errors),
+            ("cat_timeout", self.cat_timeout),
+            ("pat_version_changes", self.pat_version_changes),
+            ("pmt_version_changes", self.pmt_version_changes),
+            ("pts_errors", self.pts_errors),
+        ]
+    }
+
+    /// Priority 3 counters by field name
+    pub fn priority_3_counters(&self) -> [(&'static str, u64); 10] {
+        [
+            ("service_id_mismatch", self.service_id_mismatch),
+            ("nit_crc_errors", self.nit_crc_errors),
+            ("nit_timeout", self.nit_timeout),
+            ("sdt_crc_errors", self.sdt_crc_errors),
+            ("sdt_timeout", self.sdt_timeout),
+            ("eit_crc_errors", self.eit_crc_errors),
+            ("eit_timeout", self.eit_timeout),
+            ("tdt_timeout", self.tdt_timeout),
+            ("tot_crc_errors", self.tot_crc_errors),
+            ("splice_count_errors", self.splice_count_errors),
+        ]
+    }
+
+    /// Every counter by field name (the JSON key), for forwarding to StatsD,
+    /// OpenTelemetry and the like without knowing the struct layout: the Priority 1-3
+    /// counters plus `duplicate_packets` and `pcr_wraps`. Keys are never renamed or
+    /// dropped; new counters only add keys.
+    pub fn as_map(&self) -> BTreeMap<&'static str, u64> {
+        let mut map: BTreeMap<_, _> = self.priority_1_counters().into_iter()
+            .chain(self.priority_2_counters())
+            .chain(self.priority_3_counters())
+            .collect();
+        map.insert("duplicate_packets", self.duplicate_packets);
+        map.insert("pcr_wraps", self.pcr_wraps);
+        map
+    }
+
+    /// Sum of the Priority 2 error counters (PAT/PMT version changes are not errors)
+    pub fn priority_2_errors(&self) -> u64 {
+        sum_errors(&self.priority_2_counters())
+    }
+
     /// Get a filtered version with only Priority 1 errors
     pub fn priority_1_only(&self) -> Self {
-        Self {
-            // Priority 1 errors
-            sync_byte_errors: self.sync_byte_errors,
-            ts_sync_loss: self.ts_sync_loss,
-            transport_error_indicator: self.transport_error_indicator,
-            pat_crc_errors: self.pat_crc_errors,
-            pat_timeout: self.pat_timeout,
-            continuity_counter_errors: self.continuity_counter_errors,
-            pmt_crc_errors: self.pmt_crc_errors,
-            pmt_timeout: self.pmt_timeout,
-            pid_errors: self.pid_errors,
-
-            // Zero out Priority 2 and 3
-            pcr_repetition_errors: 0,
-            pcr_accuracy_errors: 0,
-            null_packet_rate_errors: 0,
-            cat_crc_errors: 0,
-            cat_timeout: 0,
-            pat_version_changes: 0,
-            pmt_version_changes: 0,
-            pts_errors: 0,
-            service_id_mismatch: 0,
-            nit_crc_errors: 0,
-            nit_timeout: 0,
-            sdt_crc_errors: 0,
-            sdt_timeout: 0,
-            eit_crc_errors: 0,
-            eit_timeout: 0,
-            tdt_timeout: 0,
-            splice_count_errors: 0,
-
-            // Keep internal state
-            last_pat_seen: self.last_pat_seen,
-            last_pmt_seen: self.last_pmt_seen.clone(),
-            last_cc: self.last_cc.clone(),
-            pat_versions: self.pat_versions.clone(),
-            pmt_versions: self.pmt_versions.clone(),
-            last_pcr_info: self.last_pcr_info.clone(),
-            bytes_in_1s: self.bytes_in_1s,
-            null_bytes_in_1s: self.null_bytes_in_1s,
-            last_rate_check: self.last_rate_check,
-            last_cat_seen: self.last_cat_seen,
-            last_nit_seen: self.last_nit_seen,
-            last_sdt_seen: self.last_sdt_seen,
-            last_eit_seen: self.last_eit_seen,
-            last_tdt_seen: self.last_tdt_seen,
-            last_splice_value: self.last_splice_value,
-            startup_time: self.startup_time,
-            pat_timeout_state: self.pat_timeout_state,
-            pmt_timeout_state: self.pmt_timeout_state.clone(),
-            cat_timeout_state: self.cat_timeout_state,
-            known_pids: self.known_pids.clone(),
-            last_pts_per_pid: self.last_pts_per_pid.clone(),
-            sync_loss_counter: self.sync_loss_counter,
-        }
+        self.for_mode(Some(AnalysisMode::Tr101Priority1))
     }
 
     /// Get a filtered version with Priority 1+2 errors only
     pub fn priority_1_and_2_only(&self) -> Self {
-        Self {
-            // Priority 1 errors
-            sync_byte_errors: self.sync_byte_errors,
-            ts_sync_loss: self.ts_sync_loss,
-            transport_error_indicator: self.transport_error_indicator,
-            pat_crc_errors: self.pat_crc_errors,
-            pat_timeout: self.pat_timeout,
-            continuity_counter_errors: self.continuity_counter_errors,
-            pmt_crc_errors: self.pmt_crc_errors,
-            pmt_timeout: self.pmt_timeout,
-            pid_errors: self.pid_errors,
-
-            // Priority 2 errors
-            pcr_repetition_errors: self.pcr_repetition_errors,
-            pcr_accuracy_errors: self.pcr_accuracy_errors,
-            null_packet_rate_errors: self.null_packet_rate_errors,
-            cat_crc_errors: self.cat_crc_errors,
-            cat_timeout: self.cat_timeout,
-            pat_version_changes: self.pat_version_changes,
-            pmt_version_changes: self.pmt_version_changes,
-            pts_errors: self.pts_errors,
-
-            // Zero out Priority 3
-            service_id_mismatch: 0,
-            nit_crc_errors: 0,
-            nit_timeout: 0,
-            sdt_crc_errors: 0,
-            sdt_timeout: 0,
-            eit_crc_errors: 0,
-            eit_timeout: 0,
-            tdt_timeout: 0,
-            splice_count_errors: 0,
-
-            // Keep internal state
-            last_pat_seen: self.last_pat_seen,
-            last_pmt_seen: self.last_pmt_seen.clone(),
-            last_cc: self.last_cc.clone(),
-            pat_versions: self.pat_versions.clone(),
-            pmt_versions: self.pmt_versions.clone(),
-            last_pcr_info: self.last_pcr_info.clone(),
-            bytes_in_1s: self.bytes_in_1s,
-            null_bytes_in_1s: self.null_bytes_in_1s,
-            last_rate_check: self.last_rate_check,
-            last_cat_seen: self.last_cat_seen,
-            last_nit_seen: self.last_nit_seen,
-            last_sdt_seen: self.last_sdt_seen,
-            last_eit_seen: self.last_eit_seen,
-            last_tdt_seen: self.last_tdt_seen,
-            last_splice_value: self.last_splice_value,
-            startup_time: self.startup_time,
-            pat_timeout_state: self.pat_timeout_state,
-            pmt_timeout_state: self.pmt_timeout_state.clone(),
-            cat_timeout_state: self.cat_timeout_state,
-            known_pids: self.known_pids.clone(),
-            last_pts_per_pid: self.last_pts_per_pid.clone(),
-            sync_loss_counter: self.sync_loss_counter,
+        self.for_mode(Some(AnalysisMode::Tr101Priority12))
+    }
+
+    /// Copy for reporting under `mode`: the counters (and their detail) of priorities
+    /// the mode doesn't check are zeroed and their group is left out of the JSON.
+    /// Modes without TR-101 checks get everything.
+    pub fn for_mode(&self, mode: Option<AnalysisMode>) -> Self {
+        let mut metrics = self.clone();
+        let Some(mode) = mode.filter(|m| m.tr101_priority().is_some()) else {
+            return metrics;
+        };
+        for priority in 1..=3u8 {
+            if !mode.checks_priority(priority) {
+                metrics.clear_priority(priority);
+            }
         }
+        metrics
+    }
+
+    /// Zero one priority's counters and detail, and leave its group out of the JSON
+    fn clear_priority(&mut self, priority: u8) {
+        match priority {
+            1 => {
+                for c in [&mut self.sync_byte_errors, &mut self.ts_sync_loss, &mut self.sync_loss_episodes, &mut self.transport_error_indicator,
+                          &mut self.pat_crc_errors, &mut self.pat_timeout, &mut self.continuity_counter_errors,
+                          &mut self.pmt_crc_errors, &mut self.pmt_timeout, &mut self.pid_errors, &mut self.duplicate_packets,
+                          &mut self.total_sync_loss_duration_ms] {
+                    *c = 0;
+                }
+                self.tei_per_pid.clear();
+                self.sync_loss_history.clear();
+            }
+            2 => {
+                for c in [&mut self.pcr_repetition_errors, &mut self.pcr_accuracy_errors, &mut self.pcr_discontinuity_errors,
+                          &mut self.null_packet_rate_errors, &mut self.cat_crc_errors, &mut self.cat_timeout,
+                          &mut self.pat_version_changes, &mut self.pmt_version_changes, &mut self.pts_errors, &mut self.pcr_wraps] {
+                    *c = 0;
+                }
+                self.last_pcr_wrap = None;
+                self.pts_wraps_per_pid.clear();
+                self.version_changes.clear();
+            }
+            _ => {
+                for c in [&mut self.service_id_mismatch, &mut self.nit_crc_errors, &mut self.nit_timeout,
+                          &mut self.sdt_crc_errors, &mut self.sdt_timeout, &mut self.eit_crc_errors, &mut self.eit_timeout,
+                          &mut self.tdt_timeout, &mut self.tot_crc_errors, &mut self.splice_count_errors] {
+                    *c = 0;
+                }
+            }
+        }
+        self.omitted_priorities[(priority.clamp(1, 3) - 1) as usize] = true;
     }
 
     /// Check for PAT version change (Priority 2)
     pub fn check_pat_version_change(&mut self, program_number: u16, new_version: u8, priority_level: crate::types::AnalysisMode) -> bool {
-        if !matches!(priority_level, crate::types::AnalysisMode::Tr101 | crate::types::AnalysisMode::Tr101Priority12) {
+        if !priority_level.checks_priority(2) {
             return false;
         }
 
@@ -244,6 +380,7 @@ impl Tr101Metrics {
                 if old_version != new_version {
                     self.pat_version_changes = self.pat_version_changes.saturating_add(1);
                     self.pat_versions.insert(program_number, new_version);
+                    self.record_version_change("PAT", program_number, old_version, new_version);
                     true
                 } else {
                     false
@@ -257,9 +394,20 @@ impl Tr101Metrics {
         }
     }
 
+    /// Forget a program that left the PAT: its PAT version, and its PMT's version and
+    /// repetition/timeout state when `pmt_pid` is no longer in use
+    pub fn forget_program(&mut self, program_number: u16, pmt_pid: Option<u16>) {
+        self.pat_versions.remove(&program_number);
+        if let Some(pmt_pid) = pmt_pid {
+            self.pmt_versions.remove(&pmt_pid);
+            self.last_pmt_seen.remove(&pmt_pid);
+            self.pmt_timeout_state.remove(&pmt_pid);
+        }
+    }
+
     /// Check for PMT version change (Priority 2)
     pub fn check_pmt_version_change(&mut self, pmt_pid: u16, new_version: u8, priority_level: crate::types::AnalysisMode) -> bool {
-        if !matches!(priority_level, crate::types::AnalysisMode::Tr101 | crate::types::AnalysisMode::Tr101Priority12) {
+        if !priority_level.checks_priority(2) {
             return false;
         }
 
@@ -268,6 +416,7 @@ impl Tr101Metrics {
                 if old_version != new_version {
                     self.pmt_version_changes = self.pmt_version_changes.saturating_add(1);
                     self.pmt_versions.insert(pmt_pid, new_version);
+                    self.record_version_change("PMT", pmt_pid, old_version, new_version);
                     true
                 } else {
                     false
@@ -281,22 +430,67 @@ impl Tr101Metrics {
         }
     }
 
-    /// Check for TS sync loss (Priority 1)
+    fn record_version_change(&mut self, table: &'static str, id: u16, old: u8, new: u8) {
+        if self.version_changes.len() >= VERSION_HISTORY_LEN {
+            self.version_changes.pop_front();
+        }
+        self.version_changes.push_back(VersionChange {
+            table,
+            id,
+            old,
+            new,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+
+    /// Check for sync byte errors and TS sync loss (Priority 1). Every bad sync byte is a
+    /// sync_byte_error; an episode starts after SYNC_LOSS_THRESHOLD
+    /// consecutive bad sync bytes and ends after SYNC_RECOVERY_THRESHOLD consecutive good
+    /// ones; it lasts from its start to the first good sync byte of that run.
     pub fn check_ts_sync_loss(&mut self, sync_byte_valid: bool, priority_level: crate::types::AnalysisMode) {
-        if !matches!(priority_level, crate::types::AnalysisMode::Tr101 | crate::types::AnalysisMode::Tr101Priority12 | crate::types::AnalysisMode::Tr101Priority1) {
+        if !priority_level.checks_priority(1) {
             return;
         }
 
         if sync_byte_valid {
             // Reset sync loss counter on valid sync
             self.sync_loss_counter = 0;
+            let Some(lost_at) = self.sync_lost_at else { return };
+            let regained_at = *self.sync_regained_at.get_or_insert_with(Instant::now);
+            self.sync_good_run += 1;
+            if self.sync_good_run >= SYNC_RECOVERY_THRESHOLD {
+                let duration_ms = regained_at.duration_since(lost_at).as_millis() as u64;
+                self.total_sync_loss_duration_ms = self.total_sync_loss_duration_ms.saturating_add(duration_ms);
+                if let Some(episode) = self.sync_loss_history.back_mut() {
+                    episode.duration_ms = Some(duration_ms);
+                }
+                self.sync_lost_at = None;
+                self.sync_regained_at = None;
+                self.sync_good_run = 0;
+            }
         } else {
-            // Increment sync loss counter
+            // Increment sync loss counter; a bad sync byte also restarts any recovery
+            self.sync_byte_errors = self.sync_byte_errors.saturating_add(1);
             self.sync_loss_counter = self.sync_loss_counter.saturating_add(1);
+            self.sync_regained_at = None;
+            self.sync_good_run = 0;
 
-            // After consecutive sync losses, count as TS sync loss
-            if self.sync_loss_counter >= SYNC_LOSS_THRESHOLD {
-                self.ts_sync_loss = self.ts_sync_loss.saturating_add(1);
+            // After consecutive sync losses, count as TS sync loss; the first one
+            // begins a new episode
+            if self.sync_loss_counter < SYNC_LOSS_THRESHOLD {
+                return;
+            }
+            self.ts_sync_loss = self.ts_sync_loss.saturating_add(1);
+            if self.sync_lost_at.is_none() {
+                self.sync_lost_at = Some(Instant::now());
+                self.sync_loss_episodes = self.sync_loss_episodes.saturating_add(1);
+                if self.sync_loss_history.len() >= SYNC_LOSS_HISTORY_LEN {
+                    self.sync_loss_history.pop_front();
+                }
+                self.sync_loss_history.push_back(SyncLossEpisode {
+                    started: chrono::Utc::now().to_rfc3339(),
+                    duration_ms: None,
+                });
             }
         }
     }
@@ -304,7 +498,7 @@ impl Tr101Metrics {
     /// Check for PID errors (Priority 1)
     /// Only flags truly invalid PIDs per TR 101 290 spec, not undeclared PIDs
     pub fn check_pid_error(&mut self, pid: u16, priority_level: crate::types::AnalysisMode) {
-        if !matches!(priority_level, crate::types::AnalysisMode::Tr101 | crate::types::AnalysisMode::Tr101Priority12 | crate::types::AnalysisMode::Tr101Priority1) {
+        if !priority_level.checks_priority(1) {
             return;
         }
 
@@ -351,7 +545,7 @@ impl Tr101Metrics {
     /// Note: PTS discontinuities can be legitimate (ad insertion, stream switching)
     /// so we only flag severe backward jumps as errors
     pub fn check_pts_error(&mut self, pid: u16, pts: u64, priority_level: crate::types::AnalysisMode) {
-        if !matches!(priority_level, crate::types::AnalysisMode::Tr101 | crate::types::AnalysisMode::Tr101Priority12) {
+        if !priority_level.checks_priority(2) {
             return;
         }
 
@@ -375,7 +569,10 @@ impl Tr101Metrics {
                         self.pts_errors = self.pts_errors.saturating_add(1);
                     }
                 }
-                // else: wrap-around case - this is normal, not an error
+                else {
+                    // Wrap-around - normal, not an error
+                    *self.pts_wraps_per_pid.entry(pid).or_insert(0) += 1;
+                }
             } else {
                 let pts_diff = pts - last_pts;
                 // We no longer flag large forward jumps as errors since they can be
@@ -411,47 +608,67 @@ impl Tr101Metrics {
             return;
         }
 
-        /* ───── 1.2 TEI flag ───── */
-        if packet_ctx.chunk[1] & 0x80 != 0 {
-            self.transport_error_indicator = self.transport_error_indicator.saturating_add(1);
-        }
-
-        /* ───── 1.4 continuity-counter ───── */
-        // Skip continuity counter check for null packets (PID 0x1FFF)
-        if packet_ctx.pid != 0x1FFF {
-            let cc = packet_ctx.chunk[3] & 0x0F;
-            let adaptation_field_control = (packet_ctx.chunk[3] & 0x30) >> 4;
+        let now = Instant::now();
 
-            // CC should increment for packets with payload or adaptation field
-            // Only skip CC check for adaptation field only packets (0b10)
-            let should_increment_cc = adaptation_field_control != 0b10;
+        // Priority 1 checks (TEI, continuity, PAT/PMT) are skipped by the Priority 3-only mode
+        if packet_ctx.priority_level.checks_priority(1) {
+            /* ───── 1.2 TEI flag ───── */
+            if packet_ctx.chunk[1] & 0x80 != 0 {
+                self.transport_error_indicator = self.transport_error_indicator.saturating_add(1);
+                *self.tei_per_pid.entry(packet_ctx.pid).or_insert(0) += 1;
+            }
 
-            if let Some(prev) = self.last_cc.insert(packet_ctx.pid, cc) {
-                if should_increment_cc && ((prev + 1) & 0x0F) != cc {
-                    self.continuity_counter_errors = self.continuity_counter_errors.saturating_add(1);
+            /* ───── 1.4 continuity-counter ───── */
+            // The counter only advances on packets with a payload (adaptation_field_control
+            // 01/11, ISO/IEC 13818-1 §2.4.3.3); 10 (adaptation only) and 00 (reserved) neither
+            // advance nor get checked, so they don't move the baseline either. Null packets
+            // (PID 0x1FFF) are exempt.
+            let has_payload = matches!((packet_ctx.chunk[3] & 0x30) >> 4, 0b01 | 0b11);
+            if packet_ctx.pid != 0x1FFF && has_payload {
+                let cc = packet_ctx.chunk[3] & 0x0F;
+
+                // The first payload packet of a PID sets the baseline; a signaled
+                // discontinuity (§2.4.3.5) lets the counter restart anywhere
+                let prev = self.last_cc.insert(packet_ctx.pid, cc);
+                if let Some(prev) = prev.filter(|_| !packet_ctx.discontinuity_indicator) {
+                    // A packet may be sent twice in a row with the same CC and the same
+                    // payload bytes; a second repeat, or a repeat with other data, is an error
+                    let repeats = self.cc_repeats.entry(packet_ctx.pid).or_insert(0);
+                    if cc == prev {
+                        *repeats = repeats.saturating_add(1);
+                        if *repeats == 1 && packet_ctx.repeated_payload {
+                            self.duplicate_packets = self.duplicate_packets.saturating_add(1);
+                        } else {
+                            self.continuity_counter_errors = self.continuity_counter_errors.saturating_add(1);
+                        }
+                    } else {
+                        *repeats = 0;
+                        if ((prev + 1) & 0x0F) != cc {
+                            self.continuity_counter_errors = self.continuity_counter_errors.saturating_add(1);
+                        }
+                    }
+                } else {
+                    self.cc_repeats.remove(&packet_ctx.pid);
                 }
             }
-        }
 
-        /* ───── PAT / PMT handling ───── */
-        let now = Instant::now();
-        if packet_ctx.pid == packet_ctx.pat_pid {
-            if let Some(ok) = crc_validation.pat_crc_ok {
-                if !ok {
+            /* ───── PAT / PMT handling ───── */
+            if packet_ctx.pid == packet_ctx.pat_pid {
+                if crc_validation.pat_crc_ok == Some(false) {
                     self.pat_crc_errors = self.pat_crc_errors.saturating_add(1);
                 }
+                self.last_pat_seen = Some(now);
+            } else if let Some(ok) = crc_validation.pmt_crc_ok {
+                if !ok {
+                    self.pmt_crc_errors = self.pmt_crc_errors.saturating_add(1);
+                }
+                self.last_pmt_seen.insert(packet_ctx.pid, now);
             }
-            self.last_pat_seen = Some(now);
-        } else if let Some(ok) = crc_validation.pmt_crc_ok {
-            if !ok {
-                self.pmt_crc_errors = self.pmt_crc_errors.saturating_add(1);
-            }
-            self.last_pmt_seen.insert(packet_ctx.pid, now);
-        }
 
-        /* time-outs - increment only on state transitions */
-        if let Some(start_time) = self.startup_time {
-            if start_time.elapsed() > Duration::from_millis(1000) {
+            /* time-outs - increment only on state transitions */
+            if let Some(start_time) = self.startup_time
+                && start_time.elapsed() > Duration::from_millis(1000)
+            {
                 // Check PAT timeout
                 let was_timeout = self.pat_timeout_state;
                 let is_timeout = self.last_pat_seen.is_none_or(|last|
@@ -474,148 +691,163 @@ impl Tr101Metrics {
             }
         }
 
-        /* ───── PCR checks (2.4 / 2.5) - Priority 2 ───── */
-        if matches!(packet_ctx.priority_level, crate::types::AnalysisMode::Tr101 | crate::types::AnalysisMode::Tr101Priority12) {
-            if let Some((base, ext)) = packet_ctx.pcr_opt {
-                // Validate PCR values are within spec
-                if base > (1u64 << 33) || ext > 299 {
-                    // Invalid PCR values, skip processing
-                    return;
+        /* ───── PCR checks (2.3b / 2.4 / 2.5) - Priority 2 ───── */
+        if packet_ctx.priority_level.checks_priority(2) && let Some((base, ext)) = packet_ctx.pcr_opt {
+            // Validate PCR values are within spec
+            if base > (1u64 << 33) || ext > 299 {
+                // Invalid PCR values, skip processing
+                return;
+            }
+
+            // PCR base is in 90kHz units, extension in 27MHz units
+            // Convert to full 27MHz ticks: base * 300 + extension
+            let pcr_ticks = base.saturating_mul(300).saturating_add(ext as u64);
+
+            /* 2.3b discontinuity check */
+            // A signaled discontinuity starts a new timebase; an unsignaled jump is a
+            // fault. Either way the old baseline is meaningless, so restart it instead
+            // of letting the jump show up as repetition/accuracy errors.
+            let restart = match self.last_pcr_info.get(&packet_ctx.pid) {
+                Some(_) if packet_ctx.discontinuity_indicator => true,
+                Some(prev_info) if is_unsignaled_pcr_jump(prev_info, pcr_ticks, packet_ctx.total_bytes_processed) => {
+                    self.pcr_discontinuity_errors = self.pcr_discontinuity_errors.saturating_add(1);
+                    true
                 }
+                _ => false,
+            };
+            if restart {
+                self.last_pcr_info.remove(&packet_ctx.pid);
+            }
 
-                // PCR base is in 90kHz units, extension in 27MHz units
-                // Convert to full 27MHz ticks: base * 300 + extension
-                let pcr_ticks = base.saturating_mul(300).saturating_add(ext as u64);
-
-                match self.last_pcr_info.get_mut(&packet_ctx.pid) {
-                    None => {
-                        // Initialize: store PCR ticks and current byte position
-                        self.last_pcr_info.insert(packet_ctx.pid, PcrInfo {
-                            last_pcr_ticks: pcr_ticks,
-                            last_byte_pos: packet_ctx.total_bytes_processed,
-                            bitrate_samples: Vec::new(),
-                        });
+            match self.last_pcr_info.get_mut(&packet_ctx.pid) {
+                None => {
+                    // Initialize: store PCR ticks and current byte position
+                    self.last_pcr_info.insert(packet_ctx.pid, PcrInfo {
+                        last_pcr_ticks: pcr_ticks,
+                        last_byte_pos: packet_ctx.total_bytes_processed,
+                        bitrate_samples: Vec::new(),
+                    });
+                }
+                Some(prev_info) => {
+                    // Real backward jumps restarted the baseline above, so this is a wrap
+                    if pcr_ticks < prev_info.last_pcr_ticks {
+                        self.pcr_wraps = self.pcr_wraps.saturating_add(1);
+                        self.last_pcr_wrap = Some(chrono::Utc::now().to_rfc3339());
                     }
-                    Some(prev_info) => {
-                        // Handle PCR wrap-around (33-bit counter wraps every ~26.5 hours)
-                        const PCR_WRAP: u64 = (1u64 << 33) * 300; // PCR wraps at 2^33 in 90kHz units
-                        let ticks_delta = if pcr_ticks >= prev_info.last_pcr_ticks {
-                            pcr_ticks - prev_info.last_pcr_ticks
-                        } else {
-                            // Handle wrap-around
-                            (PCR_WRAP - prev_info.last_pcr_ticks) + pcr_ticks
-                        };
+                    let ticks_delta = pcr_ticks_delta(prev_info.last_pcr_ticks, pcr_ticks);
 
-                        /* 2.4 repetition check */
-                        // TR 101 290: PCR shall occur at least every 100ms in the stream timeline
-                        let pcr_time_delta_ms = (ticks_delta as f64 / PCR_CLOCK_HZ * 1000.0) as u64;
+                    /* 2.4 repetition check */
+                    // TR 101 290: PCR shall occur at least every 100ms in the stream timeline
+                    let pcr_time_delta_ms = (ticks_delta as f64 / PCR_CLOCK_HZ * 1000.0) as u64;
 
-                        // Only flag if PCR gap exceeds threshold AND it's not a wrap-around situation
-                        if pcr_time_delta_ms > PCR_REPETITION_MS && pcr_time_delta_ms < 5000 {
-                            self.pcr_repetition_errors = self.pcr_repetition_errors.saturating_add(1);
-                        }
+                    // Only flag if PCR gap exceeds threshold AND it's not a wrap-around situation
+                    if pcr_time_delta_ms > PCR_REPETITION_MS && pcr_time_delta_ms < 5000 {
+                        self.pcr_repetition_errors = self.pcr_repetition_errors.saturating_add(1);
+                    }
 
-                        /* 2.5 accuracy check - proper ±500ns validation */
-                        // TR 101 290: PCR accuracy should be ±500ns (±14 ticks at 27MHz)
-                        // Strategy: Establish baseline bitrate from first samples, then validate
-                        // that subsequent PCRs match the expected timeline within tolerance
-
-                        let bytes_transmitted = packet_ctx.total_bytes_processed - prev_info.last_byte_pos;
-
-                        // Only check accuracy if we have transmitted enough bytes (at least 1KB)
-                        // and the time delta is reasonable (10-100ms)
-                        if bytes_transmitted >= 1000 && pcr_time_delta_ms >= 10 && pcr_time_delta_ms <= 100 {
-                            // Calculate instantaneous bitrate from this PCR pair
-                            let bits_transmitted = bytes_transmitted * 8;
-                            let seconds_elapsed = ticks_delta as f64 / PCR_CLOCK_HZ;
-                            let measured_bitrate = bits_transmitted as f64 / seconds_elapsed;
-
-                            // Store bitrate sample (keep last 10 samples for median calculation)
-                            prev_info.bitrate_samples.push(measured_bitrate);
-                            if prev_info.bitrate_samples.len() > 10 {
-                                prev_info.bitrate_samples.remove(0);
-                            }
+                    /* 2.5 accuracy check - proper ±500ns validation */
+                    // TR 101 290: PCR accuracy should be ±500ns (±14 ticks at 27MHz)
+                    // Strategy: Establish baseline bitrate from first samples, then validate
+                    // that subsequent PCRs match the expected timeline within tolerance
+
+                    let bytes_transmitted = packet_ctx.total_bytes_processed - prev_info.last_byte_pos;
+
+                    // Only check accuracy if we have transmitted enough bytes (at least 1KB)
+                    // and the time delta is reasonable (10-100ms)
+                    if bytes_transmitted >= 1000 && (10..=100).contains(&pcr_time_delta_ms) {
+                        // Calculate instantaneous bitrate from this PCR pair
+                        let bits_transmitted = bytes_transmitted * 8;
+                        let seconds_elapsed = ticks_delta as f64 / PCR_CLOCK_HZ;
+                        let measured_bitrate = bits_transmitted as f64 / seconds_elapsed;
+
+                        // Store bitrate sample (keep last 10 samples for median calculation)
+                        prev_info.bitrate_samples.push(measured_bitrate);
+                        if prev_info.bitrate_samples.len() > 10 {
+                            prev_info.bitrate_samples.remove(0);
+                        }
 
-                            // Once we have enough samples (≥3), validate PCR accuracy
-                            if prev_info.bitrate_samples.len() >= 3 {
-                                // Calculate median bitrate to avoid outliers
-                                let mut sorted_bitrates = prev_info.bitrate_samples.clone();
-                                sorted_bitrates.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
-                                let median_bitrate = sorted_bitrates[sorted_bitrates.len() / 2];
-
-                                // Calculate expected PCR increment based on median bitrate
-                                // expected_ticks = (bytes * 8) / bitrate * PCR_CLOCK_HZ
-                                let expected_ticks = (bytes_transmitted as f64 * 8.0 / median_bitrate * PCR_CLOCK_HZ) as i64;
-                                let actual_ticks = ticks_delta as i64;
-
-                                // Check if deviation exceeds ±500ns tolerance (±14 ticks)
-                                let deviation = (actual_ticks - expected_ticks).abs();
-                                if deviation > PCR_ACCURACY_TICKS {
-                                    self.pcr_accuracy_errors = self.pcr_accuracy_errors.saturating_add(1);
-                                }
+                        // Once we have enough samples (≥3), validate PCR accuracy
+                        if let Some(median_bitrate) = median_bitrate(&prev_info.bitrate_samples) {
+                            // Calculate expected PCR increment based on median bitrate
+                            // expected_ticks = (bytes * 8) / bitrate * PCR_CLOCK_HZ
+                            let expected_ticks = (bytes_transmitted as f64 * 8.0 / median_bitrate * PCR_CLOCK_HZ) as i64;
+                            let actual_ticks = ticks_delta as i64;
+
+                            // Check if deviation exceeds ±500ns tolerance (±14 ticks)
+                            let deviation = (actual_ticks - expected_ticks).abs();
+                            if deviation > PCR_ACCURACY_TICKS {
+                                self.pcr_accuracy_errors = self.pcr_accuracy_errors.saturating_add(1);
                             }
                         }
-
-                        prev_info.last_pcr_ticks = pcr_ticks;
-                        prev_info.last_byte_pos = packet_ctx.total_bytes_processed;
                     }
+
+                    prev_info.last_pcr_ticks = pcr_ticks;
+                    prev_info.last_byte_pos = packet_ctx.total_bytes_processed;
                 }
             }
         }
 
         /* ───── byte rate / null packet rate check (2.6) ───── */
-        self.bytes_in_1s += packet_ctx.chunk.len() as u64;
+        // The processor counts every packet in total_bytes_processed, including those of
+        // PIDs filtered out before this handler, so measure the window with it
         if packet_ctx.pid == 0x1FFF {
             self.null_bytes_in_1s += packet_ctx.chunk.len() as u64;
         }
 
         if let Some(last_check) = self.last_rate_check {
-            if last_check.elapsed() >= Duration::from_secs(1) {
-                let total = self.bytes_in_1s;
+            let elapsed = last_check.elapsed();
+            if elapsed >= Duration::from_secs(1) {
+                let total = packet_ctx.total_bytes_processed.saturating_sub(self.rate_window_start_bytes);
                 let null_bytes = self.null_bytes_in_1s;
                 if total > 0 {
                     let rate = null_bytes as f64 / total as f64;
 
                     // Only increment error counter if we're monitoring Priority 2+
-                    if matches!(packet_ctx.priority_level, crate::types::AnalysisMode::Tr101 | crate::types::AnalysisMode::Tr101Priority12) && rate > NULL_RATE_THRESHOLD {
+                    if packet_ctx.priority_level.checks_priority(2) && rate > NULL_RATE_THRESHOLD {
                         self.null_packet_rate_errors = self.null_packet_rate_errors.saturating_add(1);
                     }
                 }
+                self.transport_bitrate_kbps = total as f64 * 8.0 / elapsed.as_secs_f64() / 1000.0;
 
                 // Reset counters and update timestamp
-                self.bytes_in_1s = 0;
+                self.rate_window_start_bytes = packet_ctx.total_bytes_processed;
                 self.null_bytes_in_1s = 0;
                 self.last_rate_check = Some(now);
             }
         } else {
+            self.rate_window_start_bytes = packet_ctx.total_bytes_processed;
             self.last_rate_check = Some(now);
         }
 
         /* ───── CAT / NIT / SDT / EIT timeout and CRC errors ───── */
-        if matches!(packet_ctx.priority_level, crate::types::AnalysisMode::Tr101 | crate::types::AnalysisMode::Tr101Priority12) && packet_ctx.pid == 0x0001 {          // CAT
-            if let Some(ok) = crc_validation.cat_crc_ok {
-                if !ok {
-                    self.cat_crc_errors = self.cat_crc_errors.saturating_add(1);
-                }
+        if packet_ctx.priority_level.checks_priority(2) && packet_ctx.pid == 0x0001 {          // CAT
+            if crc_validation.cat_crc_ok == Some(false) {
+                self.cat_crc_errors = self.cat_crc_errors.saturating_add(1);
             }
             self.last_cat_seen = Some(now);
         }
 
         /* ───── NIT / SDT / EIT / TDT detection - Priority 3 ───── */
-        if matches!(packet_ctx.priority_level, crate::types::AnalysisMode::Tr101) {
+        if packet_ctx.priority_level.checks_priority(3) {
             match packet_ctx.pid {
-                0x0010 => {          // NIT
-                    if let Some(ok) = crc_validation.nit_crc_ok { if !ok { self.nit_crc_errors += 1; } }
+                pid if pid == packet_ctx.nit_pid => {          // NIT
+                    if crc_validation.nit_crc_ok == Some(false) { self.nit_crc_errors += 1; }
                     self.last_nit_seen = Some(now);
                 }
-                0x0011 => {          // SDT / EIT
+                0x0011 | 0x0012 => {          // SDT / EIT (p/f also on 0x0012)
                     if packet_ctx.table_id == 0x42 || packet_ctx.table_id == 0x46 { // SDT
-                        if let Some(ok) = crc_validation.sdt_crc_ok { if !ok { self.sdt_crc_errors += 1; } }
+                        if crc_validation.sdt_crc_ok == Some(false) { self.sdt_crc_errors += 1; }
                         self.last_sdt_seen = Some(now);
                     } else if packet_ctx.table_id == 0x4E || packet_ctx.table_id == 0x4F { // EIT p/f
-                        if let Some(ok) = crc_validation.eit_crc_ok { if !ok { self.eit_crc_errors += 1; } }
+                        if crc_validation.eit_crc_ok == Some(false) { self.eit_crc_errors += 1; }
                         self.last_eit_seen = Some(now);
-                    } else if packet_ctx.table_id == 0x70 || packet_ctx.table_id == 0x73 { // TDT/TOT
+                    }
+                }
+                0x0014 => {          // TDT / TOT
+                    if packet_ctx.table_id == 0x73 && crc_validation.tdt_crc_ok == Some(false) {
+                        self.tot_crc_errors += 1;
+                    }
+                    if packet_ctx.table_id == 0x70 || packet_ctx.table_id == 0x73 {
                         self.last_tdt_seen = Some(now);
                     }
                 }
@@ -624,7 +856,7 @@ impl Tr101Metrics {
         }
 
         /* ───── NIT/SDT/EIT/TDT timeouts - Priority 3 ───── */
-        if matches!(packet_ctx.priority_level, crate::types::AnalysisMode::Tr101) {
+        if packet_ctx.priority_level.checks_priority(3) {
             if self.last_nit_seen.is_none_or(|t| t.elapsed()
                     > Duration::from_millis(NIT_TIMEOUT_MS)) {
                 self.nit_timeout += 1;
@@ -647,4 +879,104 @@ impl Tr101Metrics {
             }
         }
     }
-}
\ No newline at end of file
+}
+
+/// 27 MHz ticks between two PCR values, handling the 33-bit base wrap-around
+/// (every ~26.5 hours)
+fn pcr_ticks_delta(prev_ticks: u64, pcr_ticks: u64) -> u64 {
+    if pcr_ticks >= prev_ticks {
+        pcr_ticks - prev_ticks
+    } else {
+        (PCR_WRAP_THRESHOLD - prev_ticks) + pcr_ticks
+    }
+}
+
+/// Median of the recent bitrate samples, once at least 3 have been collected
+fn median_bitrate(samples: &[f64]) -> Option<f64> {
+    if samples.len() < 3 {
+        return None;
+    }
+    // Median avoids outliers
+    let mut sorted = samples.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    Some(sorted[sorted.len() / 2])
+}
+
+/// Sum of `counters`, leaving out the UNSUMMED_COUNTERS
+fn sum_errors(counters: &[(&'static str, u64)]) -> u64 {
+    counters.iter().filter(|(name, _)| !UNSUMMED_COUNTERS.contains(name)).map(|(_, count)| count).sum()
+}
+
+/// Whether a PCR step is a jump rather than normal progression.
+/// With a bitrate baseline, the bytes sent since the last PCR predict how far the
+/// PCR should have advanced; a mismatch beyond PCR_DISCONTINUITY_MS is a jump.
+fn is_unsignaled_pcr_jump(prev_info: &PcrInfo, pcr_ticks: u64, total_bytes_processed: u64) -> bool {
+    let ticks_delta = pcr_ticks_delta(prev_info.last_pcr_ticks, pcr_ticks);
+    let pcr_time_delta_ms = (ticks_delta as f64 / PCR_CLOCK_HZ * 1000.0) as u64;
+
+    match median_bitrate(&prev_info.bitrate_samples) {
+        Some(bitrate) => {
+            let bytes_transmitted = total_bytes_processed - prev_info.last_byte_pos;
+            let expected_ms = (bytes_transmitted as f64 * 8.0 / bitrate * 1000.0) as u64;
+            pcr_time_delta_ms.abs_diff(expected_ms) > PCR_DISCONTINUITY_MS
+        }
+        None => pcr_time_delta_ms >= PCR_JUMP_FALLBACK_MS,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_changes_are_not_priority_2_errors() {
+        let mut metrics = Tr101Metrics::new();
+        metrics.pat_version_changes = 1;
+        metrics.pmt_version_changes = 3;
+        assert_eq!(metrics.priority_2_errors(), 0);
+        metrics.cat_timeout = 1;
+        assert_eq!(metrics.priority_2_errors(), 1);
+    }
+
+    #[test]
+    fn sync_loss_counts_packets_and_episodes() {
+        let mut metrics = Tr101Metrics::new();
+        let feed = |metrics: &mut Tr101Metrics, ok: bool, packets: usize| {
+            for _ in 0..packets {
+                metrics.check_ts_sync_loss(ok, AnalysisMode::Tr101);
+            }
+        };
+
+        // 4 bad sync bytes are below the threshold
+        feed(&mut metrics, false, 4);
+        feed(&mut metrics, true, 1);
+        assert_eq!((metrics.ts_sync_loss, metrics.sync_loss_episodes), (0, 0));
+
+        // 7 bad ones: the 5th starts the episode, and 3 packets are counted as lost
+        feed(&mut metrics, false, 7);
+        assert_eq!((metrics.ts_sync_loss, metrics.sync_loss_episodes), (3, 1));
+        std::thread::sleep(Duration::from_millis(20));
+        // 4 good sync bytes don't end it yet, the 5th does
+        feed(&mut metrics, true, 4);
+        assert_eq!(metrics.sync_loss_history[0].duration_ms, None);
+        feed(&mut metrics, true, 1);
+        let duration = metrics.sync_loss_history[0].duration_ms.expect("episode ended");
+        assert!(duration >= 20, "{duration} ms");
+        assert_eq!(metrics.total_sync_loss_duration_ms, duration);
+
+        // A second episode, still ongoing
+        feed(&mut metrics, false, 6);
+        assert_eq!((metrics.ts_sync_loss, metrics.sync_loss_episodes), (5, 2));
+        assert_eq!(metrics.sync_loss_history.len(), 2);
+        assert_eq!(metrics.sync_loss_history[1].duration_ms, None);
+        assert_eq!(metrics.total_sync_loss_duration_ms, duration);
+
+        // Every bad sync byte is a sync_byte_error
+        assert_eq!(metrics.sync_byte_errors, 4 + 7 + 6);
+
+        // Both keys are reported; the episodes don't add to the error sum
+        let map = metrics.as_map();
+        assert_eq!((map["ts_sync_loss"], map["sync_loss_episodes"]), (5, 2));
+        assert_eq!(metrics.priority_1_errors(), 17 + 5);
+    }
+}
@@ -1,4 +1,5 @@
 use serde::Serialize;
+use std::collections::BTreeMap;
 use std::time::Instant;
 
 /// Context for SI table processing to reduce function parameters
@@ -20,7 +21,10 @@ pub struct PacketContext<'a> {
     pub pid: u16,
     pub payload_unit_start: bool,
     pub pat_pid: u16,
+    pub nit_pid: u16,
     pub pcr_opt: Option<(u64, u16)>,
+    pub discontinuity_indicator: bool,
+    pub repeated_payload: bool,      // Same CC and payload bytes as the PID's previous payload packet
     pub table_id: u8,
     pub priority_level: AnalysisMode,
     pub total_bytes_processed: u64,  // Total bytes processed since start
@@ -35,10 +39,11 @@ pub struct CrcValidation {
     pub nit_crc_ok: Option<bool>,
     pub sdt_crc_ok: Option<bool>,
     pub eit_crc_ok: Option<bool>,
+    pub tdt_crc_ok: Option<bool>, // TOT only; TDT carries no CRC
 }
 
 /// Video codec information
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct VideoInfo {
     pub codec: String,
     pub width: u16,
@@ -46,21 +51,92 @@ pub struct VideoInfo {
     pub fps: f32,
     pub chroma: String,
     pub interlaced: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub profile: Option<String>,
+    /// Level number, e.g. 5.1 (H.264 level_idc / 10, HEVC general_level_idc / 30; 1b reads as 1.0)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub level: Option<f32>,
+    /// Resolution (or resolution × fps) above what the signaled H.264/HEVC level allows
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub level_resolution_mismatch: bool,
+    /// HEVC tier ("Main" or "High")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tier: Option<String>,
+    /// Sample (pixel) aspect ratio as width:height
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub sar: Option<(u16, u16)>,
+    /// Display aspect ratio, e.g. "16:9"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub aspect_ratio: Option<String>,
+    /// Luma bit depth (8, 10, 12)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub bit_depth: Option<u8>,
+    /// VUI video_full_range_flag (H.264/HEVC): true for full (PC) range, false for limited
+    /// (TV) range; broadcast expects limited, so true on a broadcast service is an anomaly
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub full_range: Option<bool>,
+    /// VUI colour_primaries (ITU-T H.273), e.g. "BT.709"; None if unsignaled or unspecified
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub colour_primaries: Option<String>,
+    /// VUI transfer_characteristics, e.g. "BT.709", "PQ", "HLG"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transfer: Option<String>,
+    /// VUI matrix_coefficients, e.g. "BT.709", "BT.2020 NCL"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub matrix: Option<String>,
+    /// Access units between the last two IDR frames (H.264/HEVC)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gop_length: Option<u32>,
+    /// PTS distance between the last two IDR frames in seconds
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keyframe_interval_secs: Option<f64>,
+    /// No IDR frame seen for longer than KEYFRAME_TIMEOUT_SECONDS
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub keyframe_overdue: bool,
 }
 
 /// Audio codec information
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct AudioInfo {
     pub codec: String,
     pub profile: Option<String>,
     pub sample_rate: Option<u32>,
     pub channels: Option<u8>,
+    /// Dialogue normalization level in dB (AC-3 dialnorm, -1 to -31)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dialnorm: Option<i8>,
+    /// Bitstream mode / service type (AC-3 bsmod, e.g. "Complete Main")
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_type: Option<String>,
+    /// Bitrate from the MPEG audio header's bitrate_index (None for free format)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub declared_bitrate_kbps: Option<u32>,
+    /// E-AC-3 carrying Dolby Atmos (JOC object audio), best-effort from the bsi
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub atmos: bool,
 }
 
 /// Subtitle codec information
 #[derive(Debug, Clone, Serialize)]
 pub struct SubtitleInfo {
     pub codec: String,
+    /// Entries of the DVB subtitling_descriptor (0x59), one per language/page
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pages: Vec<SubtitlePage>,
+}
+
+/// One language entry of a DVB subtitling_descriptor
+#[derive(Debug, Clone, Serialize)]
+pub struct SubtitlePage {
+    /// ISO 639-2 language code, e.g. "eng"
+    pub language: String,
+    /// subtitling_type from EN 300 468 (0x10-0x15 normal, 0x20-0x25 hard of hearing)
+    pub subtitling_type: u8,
+    /// Human-readable subtitling_type, e.g. "Hard of hearing (16:9)"
+    pub kind: &'static str,
+    pub hard_of_hearing: bool,
+    pub composition_page_id: u16,
+    pub ancillary_page_id: u16,
 }
 
 /// Codec information for different stream types
@@ -71,13 +147,193 @@ pub enum CodecInfo {
     Subtitle(SubtitleInfo),
 }
 
+/// PES header diagnostics (timestamp presence, declared length) for an elementary stream
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct PesTimestampInfo {
+    pub has_pts: bool,
+    pub has_dts: bool,
+    /// Fraction of audio/video PES headers that carried a PTS
+    pub pts_present_ratio: f64,
+    /// Audio/video PES headers seen without a PTS
+    pub missing_pts: u64,
+    /// Most recent PTS - DTS delta in milliseconds (only when DTS is present)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pts_dts_delta_ms: Option<f64>,
+    /// Most recent PTS minus the program's latest PCR in milliseconds (decoder buffer delay)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pts_pcr_delay_ms: Option<f64>,
+    /// PES headers whose PTS was behind the PCR or more than 1 s ahead of it
+    pub pts_pcr_delay_errors: u64,
+    /// PES packets whose non-zero PES_packet_length differed from the bytes
+    /// carried before the next payload_unit_start (unbounded PES are not checked)
+    pub pes_length_errors: u64,
+}
+
 /// Elementary stream information (public API)
 #[derive(Debug, Clone, Serialize)]
 pub struct StreamInfo {
     pub pid: u16,
     pub stream_type: u8,
+    /// Registry name of `stream_type`, see [`stream_type_name`]
+    pub stream_type_name: &'static str,
     pub codec: Option<CodecInfo>,
     pub bitrate_kbps: f64,
+    /// From the PMT maximum_bitrate_descriptor (0x0E), transport overhead included
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub declared_max_bitrate_kbps: Option<f64>,
+    /// Measured bitrate above `declared_max_bitrate_kbps`
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub max_bitrate_exceeded: bool,
+    /// From the PMT smoothing_buffer_descriptor (0x10)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub smoothing_buffer: Option<SmoothingBuffer>,
+    /// Video access units or audio sync frames received so far
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub frames_delivered: Option<u64>,
+    /// Frames actually delivered per second of stream time (vs. the signaled `fps`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub measured_fps: Option<f64>,
+    /// Audio only: how far the PTS advance per frame is off the nominal frame duration
+    /// (samples per frame / sample rate), in ppm; positive means frames last too long
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub audio_drift_ppm: Option<f64>,
+    /// DVB component description joined via the stream_identifier component_tag,
+    /// e.g. "Audio: Dolby 5.1" (present EIT event, else SDT)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub component: Option<String>,
+    #[serde(flatten)]
+    pub pes: PesTimestampInfo,
+}
+
+/// smoothing_buffer_descriptor values
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct SmoothingBuffer {
+    pub leak_rate_kbps: f64,
+    pub size_bytes: u32,
+}
+
+/// EPG depth of a service from EIT-actual schedule tables
+#[derive(Debug, Clone, Serialize)]
+pub struct EpgCoverage {
+    /// Distinct scheduled events seen that had not ended by the latest TDT/TOT time
+    pub events: u32,
+    /// Sum of their durations
+    pub hours: f64,
+    /// End of the last scheduled event (RFC 3339, UTC)
+    pub until: String,
+    /// Schedule sections received of the current versions, across table_ids 0x50..=last_table_id
+    pub sections_received: u32,
+    /// Sections announced by last_section_number and segment_last_section_number; a
+    /// table or segment not seen yet counts as one
+    pub sections_expected: u32,
+    /// sections_received as a share of sections_expected; 100 once the whole schedule is in
+    pub complete_percent: f64,
+}
+
+/// Logical channel number of a service, from the NIT LCN descriptors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct LogicalChannel {
+    pub number: u16,
+    /// visible_service_flag; hidden services are kept out of the receiver's channel list
+    pub visible: bool,
+    /// private_data_specifier the LCN descriptor was read under (e.g. 0x28 EACEM, 0x29 NorDig)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub private_data_specifier: Option<u32>,
+}
+
+/// Name of a PMT stream_type: ISO/IEC 13818-1 Table 2-34 plus the common
+/// user-private assignments (ATSC A/53, Blu-ray, SCTE). 0x06 is generic PES
+/// private data; the actual codec comes from descriptors.
+pub fn stream_type_name(st: u8) -> &'static str {
+    match st {
+        0x01 => "MPEG-1 Video",
+        0x02 => "MPEG-2 Video",
+        0x03 => "MPEG-1 Audio",
+        0x04 => "MPEG-2 Audio",
+        0x05 => "Private Sections",
+        0x06 => "PES Private Data",
+        0x07 => "MHEG",
+        0x08 => "DSM-CC",
+        0x09 => "H.222.1",
+        0x0A => "DSM-CC Multiprotocol Encapsulation",
+        0x0B => "DSM-CC U-N Messages",
+        0x0C => "DSM-CC Stream Descriptors",
+        0x0D => "DSM-CC Sections",
+        0x0E => "Auxiliary",
+        0x0F => "AAC (ADTS)",
+        0x10 => "MPEG-4 Visual",
+        0x11 => "AAC (LATM)",
+        0x12 => "MPEG-4 SL/FlexMux (PES)",
+        0x13 => "MPEG-4 SL/FlexMux (Sections)",
+        0x14 => "DSM-CC Synchronized Download",
+        0x15 => "Metadata (PES)",
+        0x16 => "Metadata (Sections)",
+        0x17 => "Metadata (Data Carousel)",
+        0x18 => "Metadata (Object Carousel)",
+        0x19 => "Metadata (Synchronized Download)",
+        0x1A => "MPEG-2 IPMP",
+        0x1B => "H.264/AVC",
+        0x1C => "AAC (Raw)",
+        0x1D => "MPEG-4 Text",
+        0x1E => "MPEG-4 Auxiliary Video",
+        0x1F => "H.264/SVC",
+        0x20 => "H.264/MVC",
+        0x21 => "JPEG 2000",
+        0x22 => "MPEG-2 Video (Stereo Additional View)",
+        0x23 => "H.264 (Stereo Additional View)",
+        0x24 => "H.265/HEVC",
+        0x25 => "H.265/HEVC Temporal Subset",
+        0x2D => "MPEG-H 3D Audio",
+        0x33 => "H.266/VVC",
+        0x80 => "LPCM",
+        0x81 => "AC-3",
+        0x82 => "DTS",
+        0x83 => "Dolby TrueHD",
+        0x84 => "E-AC-3 (Blu-ray)",
+        0x85 => "DTS-HD",
+        0x86 => "SCTE-35",
+        0x87 => "E-AC-3",
+        0x88..=0xFF => "User Private",
+        _ => "Reserved",
+    }
+}
+
+/// Spacing of successive PCRs on a program's PCR PID (TR 101 290 2.4: at most 100 ms)
+#[derive(Debug, Clone, Serialize)]
+pub struct PcrIntervalInfo {
+    pub max_ms: f64,
+    pub mean_ms: f64,
+    /// PCR-to-PCR intervals measured
+    pub intervals: u64,
+    /// Intervals longer than 100 ms
+    pub over_limit: u64,
+}
+
+/// Latest PCR on a program's PCR PID and when it arrived. Comparing how the PCR and the
+/// arrival time advance between reports shows network delay variation or clock drift.
+#[derive(Debug, Clone, Serialize)]
+pub struct LastPcrInfo {
+    /// PCR in 27 MHz ticks (base * 300 + extension)
+    pub ticks: u64,
+    /// `ticks` in seconds
+    pub secs: f64,
+    /// Wallclock arrival of the packet carrying it, RFC 3339 UTC
+    pub arrival: String,
+}
+
+/// Delivery-network health from datagram arrival times (socket input only), over the
+/// last completed window. Independent of PCR jitter: it measures the network, not the encoder.
+#[derive(Debug, Clone, Serialize)]
+pub struct TransportStats {
+    pub window_secs: f64,
+    pub datagrams_per_sec: f64,
+    /// TS packets per second
+    pub packets_per_sec: f64,
+    /// Mean time between datagrams
+    pub mean_interarrival_ms: f64,
+    /// Standard deviation of the time between datagrams (the packets of a datagram
+    /// arrive together, so this is the packet arrival jitter)
+    pub packet_jitter_ms: f64,
 }
 
 /// Program information containing all its streams (public API)
@@ -88,17 +344,319 @@ pub struct ProgramInfo {
     /// PCR PID for this program (from PMT)
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pcr_pid: Option<u16>,
+    /// Elementary PID that also carries the PCR; None when the PCR PID is dedicated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pcr_shared_with_pid: Option<u16>,
+    /// From the program-level maximum_bitrate_descriptor (0x0E)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub declared_max_bitrate_kbps: Option<f64>,
+    /// Sum of the program's stream bitrates above `declared_max_bitrate_kbps`
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub max_bitrate_exceeded: bool,
+    /// PCR repetition interval statistics since start
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub pcr_interval: Option<PcrIntervalInfo>,
+    /// Latest PCR and its arrival time
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_pcr: Option<LastPcrInfo>,
     /// PMT version for change tracking
     #[serde(skip_serializing_if = "Option::is_none")]
     pub pmt_version: Option<u8>,
+    /// SDT running_status of the matching service, e.g. "running", "not running"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub running_status: Option<String>,
+    /// SDT free_CA_mode: service declares scrambled components
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub free_ca_mode: Option<bool>,
+    /// Observed scrambling of the program's elementary streams
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub scrambling: Option<ScramblingState>,
+    /// Program-level registration format_identifier, e.g. "HDMV" or "CUEI"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub registration: Option<String>,
+    /// CA_system_ids from the program-level CA descriptors
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ca_system_ids: Vec<u16>,
+    /// From this TS's NIT-actual logical_channel_descriptor (0x83)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logical_channel: Option<LogicalChannel>,
+    /// From the HD_simulcast_logical_channel_descriptor (0x88), used by HD receivers
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hd_logical_channel: Option<LogicalChannel>,
+    /// Schedule present in EIT-actual schedule tables (0x50-0x5F)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub epg: Option<EpgCoverage>,
+    /// provider_name of the SDT-actual service_descriptor (0x48)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub provider: Option<String>,
+    /// Stream classes whose PMT count differs from the `Options::expected_streams` template
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub stream_mismatches: Vec<StreamCountMismatch>,
+}
+
+/// Overall stream health derived from the TR 101 290 counters in a report.
+/// Only the priorities measured by the active analysis mode can contribute.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum Verdict {
+    /// No Priority 1 or Priority 2 errors
+    Pass,
+    /// Priority 2 errors only (PCR/PTS timing, CAT, null rate, version changes)
+    Warn,
+    /// At least one Priority 1 error
+    Fail,
+}
+
+/// Program-level scrambling derived from transport_scrambling_control of its ES PIDs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum ScramblingState {
+    /// Every elementary stream is in the clear
+    Clear,
+    /// Every elementary stream is scrambled
+    Scrambled,
+    /// Some elementary streams are scrambled, others clear
+    Partial,
+}
+
+/// Per-PID packet statistics (public API)
+#[derive(Debug, Clone, Serialize)]
+pub struct PidInfo {
+    pub pid: u16,
+    pub packets: u64,
+    /// adaptation_field_control = 01
+    pub payload_only: u64,
+    /// adaptation_field_control = 10
+    pub adaptation_only: u64,
+    /// adaptation_field_control = 11
+    pub adaptation_and_payload: u64,
+    /// adaptation_field_control = 00 (reserved, packet is discarded)
+    pub reserved_afc: u64,
+    /// Packets with transport_priority set
+    pub transport_priority: u64,
+    /// adaptation_field_length out of range (not 183 without payload, above 182 with payload)
+    pub adaptation_field_errors: u64,
+    /// Average bitrate since the PID was first seen
+    pub bitrate_kbps: f64,
+    /// "null" for stuffing (PID 0x1FFF)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+}
+
+/// What changed between two reports, from [`InspectorReport::diff`]
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ReportDiff {
+    pub changes: Vec<ReportChange>,
+}
+
+impl ReportDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+}
+
+/// How a PID is shared between programs in a way receivers don't expect
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PidCollisionKind {
+    /// The PAT points several programs at the same PMT PID
+    PmtPid,
+    /// The PMTs of several programs declare the same elementary PID with different
+    /// stream_types; a shared component with one stream_type is legal and not reported
+    ElementaryPid,
+    /// A program's PCR PID is an elementary stream of another program only, or a PMT PID
+    PcrPid,
+}
+
+/// A PID shared between programs, with the programs involved
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct PidCollision {
+    pub pid: u16,
+    pub kind: PidCollisionKind,
+    pub programs: Vec<u16>,
+}
+
+/// Broad class of an elementary stream, from its PMT stream_type and descriptors
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StreamClass {
+    Video,
+    Audio,
+    /// DVB subtitles (subtitling_descriptor 0x59)
+    Subtitle,
+    /// Anything else: teletext, SCTE-35, data carousels, unknown private data
+    Data,
+}
+
+/// Elementary stream counts a program is expected to carry, for `Options::expected_streams`;
+/// a class left at None is not checked
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StreamTemplate {
+    pub video: Option<u32>,
+    pub audio: Option<u32>,
+    pub subtitle: Option<u32>,
+    pub data: Option<u32>,
+}
+
+impl StreamTemplate {
+    /// Expected count for `class`
+    pub fn expected(&self, class: StreamClass) -> Option<u32> {
+        match class {
+            StreamClass::Video => self.video,
+            StreamClass::Audio => self.audio,
+            StreamClass::Subtitle => self.subtitle,
+            StreamClass::Data => self.data,
+        }
+    }
+}
+
+/// Program declared through `Options::manual_programs`, for input whose PAT/PMT is missing
+/// or broken but whose elementary PIDs are known
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ManualProgram {
+    /// PCR PID; 0x1FFF for a program without PCR
+    pub pcr_pid: u16,
+    /// (elementary PID, stream_type) in PMT order
+    pub streams: Vec<(u16, u8)>,
+}
+
+/// A stream class whose count in the PMT differs from the program's template
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct StreamCountMismatch {
+    pub class: StreamClass,
+    pub expected: u32,
+    pub found: u32,
+}
+
+/// One change in a [`ReportDiff`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "change", rename_all = "snake_case")]
+pub enum ReportChange {
+    ProgramAdded { program_number: u16 },
+    ProgramRemoved { program_number: u16 },
+    PidAdded { pid: u16 },
+    PidRemoved { pid: u16 },
+    /// Codec name of an elementary stream flipped, e.g. "MPEG-2" → "H.264"
+    CodecChanged { pid: u16, from: String, to: String },
+    ResolutionChanged { pid: u16, from: (u16, u16), to: (u16, u16) },
+    FpsChanged { pid: u16, from: f32, to: f32 },
+    /// A TR 101 290 counter grew (counters the report's priority level hides stay 0)
+    CounterIncreased { counter: &'static str, from: u64, to: u64 },
 }
 
 /// Complete inspection report with all discovered programs and TR-101 metrics
 #[derive(Debug, Clone, Serialize)]
 pub struct InspectorReport {
     pub timestamp: String,
+    /// From the PAT (falls back to SDT-actual)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transport_stream_id: Option<u16>,
+    /// From SDT-actual (falls back to this TS's NIT-actual entry)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub original_network_id: Option<u16>,
+    /// PAT transport_stream_id not matching SDT-actual or missing from NIT-actual
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub transport_stream_id_mismatch: bool,
     pub programs: Vec<ProgramInfo>,
+    pub pids: Vec<PidInfo>,
+    /// PIDs declared in a PMT that carry no packets
+    pub dangling_pids: Vec<u16>,
+    /// PSI PIDs (PAT/CAT/SI range, NIT, PMTs) that a PMT also declares as elementary
+    /// PIDs; they are only parsed as PSI, so the stream is missing from `programs`
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub pid_role_conflicts: Vec<u16>,
+    /// PMT PIDs shared by programs in the PAT, elementary PIDs declared by several programs,
+    /// and PCR PIDs borrowed from another program
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub program_pid_collisions: Vec<PidCollision>,
+    /// Total bitrate of all PIDs; null packets only count when `Options::include_null_in_mux` is set
+    pub mux_bitrate_kbps: f64,
+    /// Bitrate of null packets (PID 0x1FFF), i.e. stuffing
+    pub null_bitrate_kbps: f64,
+    /// Clock the bitrates above were measured against
+    pub bitrate_clock: BitrateClock,
+    /// Datagrams dropped by the kernel before we could read them (Linux UDP input only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub socket_drops: Option<u64>,
+    /// Datagrams a lagging broadcast receiver missed (broadcast channel input only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dropped_datagrams: Option<u64>,
+    /// Datagrams whose size was not a whole number of TS packets (datagram input only);
+    /// the remainder is carried into the next datagram
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub misaligned_datagrams: Option<u64>,
+    /// Datagram arrival rate and jitter (UDP input only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub transport: Option<TransportStats>,
+    /// provider_name → program numbers of the programs above, for muxes shared by providers
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub providers: BTreeMap<String, Vec<u16>>,
     pub tr101_metrics: crate::tr101::Tr101Metrics,
+    /// Pass/Warn/Fail from `tr101_metrics`, see [`Verdict`]
+    pub verdict: Verdict,
+    /// Last-known PAT, PMTs, SDT and NIT; left out of the JSON report
+    #[serde(skip)]
+    pub si_tables: SiTables,
+    /// Most recent packets that raised a Priority 1 error, oldest first (only with
+    /// `Options::error_capture`); left out of the JSON report
+    #[serde(skip)]
+    pub error_packets: Vec<ErrorPacket>,
+}
+
+/// A raw TS packet kept for offline analysis because it raised a Priority 1 error
+#[derive(Debug, Clone, Serialize)]
+pub struct ErrorPacket {
+    pub pid: u16,
+    /// Priority 1 counter the packet incremented, e.g. "continuity_counter_errors"
+    pub error: &'static str,
+    /// When it was processed (RFC 3339, UTC)
+    pub captured_at: String,
+    /// The 188 bytes as received
+    pub packet: Vec<u8>,
+}
+
+/// Per-input RTP statistics for hitless (SMPTE 2022-7) monitoring
+#[derive(Debug, Clone, Serialize)]
+pub struct RtpLegStats {
+    pub addr: std::net::SocketAddr,
+    pub packets_received: u64,
+    /// Gaps in this leg's RTP sequence numbers
+    pub rtp_packet_loss: u64,
+}
+
+/// Outcome of listening on one address during a scan (`inspector::scan`)
+#[derive(Debug, Clone, Serialize)]
+pub struct ScanResult {
+    pub addr: std::net::SocketAddr,
+    /// TS packets were received during the dwell time
+    pub active: bool,
+    /// Program numbers of the programs whose PMT was seen
+    pub programs: Vec<u16>,
+    /// Total bitrate over the dwell time
+    pub mux_bitrate_kbps: f64,
+}
+
+/// Report for a hitless-merged pair of inputs
+#[derive(Debug, Clone, Serialize)]
+pub struct HitlessReport {
+    pub legs: Vec<RtpLegStats>,
+    /// Packets missing on every leg (unrecoverable by the merge)
+    pub merged_loss: u64,
+    /// Inspection of the merged stream
+    pub report: InspectorReport,
+}
+
+/// Internal per-PID packet statistics (every PID, not only elementary streams)
+pub struct PidStats {
+    pub packets: u64,
+    pub afc_counts: [u64; 4], // Indexed by adaptation_field_control
+    pub transport_priority: u64,
+    pub adaptation_field_errors: u64,
+    pub first_seen: Instant,
+    pub first_clock: u64, // StreamClock::elapsed at the first packet
+    pub last_seen: Instant,
+    pub scrambled_in_window: u64, // Payload packets with transport_scrambling_control != 00
+    pub clear_in_window: u64,
+    pub window_start: Instant,
+    pub scrambled: Option<bool>, // Verdict of the last completed window
 }
 
 /// Internal elementary stream statistics
@@ -107,43 +665,281 @@ pub struct EsStats {
     pub codec: Option<CodecInfo>,
     pub bytes: usize,
     pub start: Instant,
+    pub start_clock: u64,       // StreamClock::elapsed when the stream was added
+    pub last_seen: Instant,     // Updated on every packet, used for inactivity cleanup
     pub last_pts: Option<u64>,
     pub pts_samples: Vec<u64>,  // Store recent PTS values for better FPS calculation
+    pub pes_timed: u64,         // Audio/video PES headers seen (PTS expected)
+    pub pes_with_pts: u64,      // ... of which carried a PTS
+    pub pes_with_dts: u64,      // ... of which carried a DTS
+    pub last_pts_dts_delta: Option<u64>, // PTS - DTS in 90 kHz ticks
+    pub last_pts_pcr_delay: Option<i64>, // PTS - program PCR in 90 kHz ticks
+    pub pts_pcr_delay_errors: u64,       // Delays below zero or above MAX_PTS_PCR_DELAY_TICKS
+    pub pes_expected_bytes: Option<usize>, // 6 + PES_packet_length of the current PES (None if unbounded)
+    pub pes_bytes: usize,                  // Payload bytes of the current PES so far
+    pub pes_length_errors: u64,
+    pub aus_since_idr: Option<u32>, // Access units since the last IDR (None until the first one)
+    pub au_pts: Option<u64>,        // PTS of the current access unit
+    pub au_has_idr: bool,           // Current access unit already counted as IDR
+    pub last_idr_pts: Option<u64>,
+    pub last_idr_seen: Instant,     // Starts at stream creation so a missing first IDR is flagged too
+    pub latm_config: Option<AudioInfo>, // Last decoded LATM StreamMuxConfig, reused for useSameStreamMux frames
+    pub frames_delivered: u64,      // Video access units (one per PES) or audio sync frames
+    pub pes_frames: u64,            // ... of which in the current PES
+    pub frame_window: Option<(u64, u64)>, // (DTS/PTS the window opened at, frames since)
+    pub measured_fps: Option<f64>,  // Frames per second of stream time over the last completed window
+    pub pes_frame_ticks: f64,       // Nominal duration (90 kHz) of the audio frames walked since the last PTS
+    pub audio_timing: Option<(u64, u64, f64)>, // (last PES PTS, PTS ticks since the run began, nominal ticks of its frames)
+    pub audio_drift_ppm: Option<f64>, // Measured vs nominal audio frame duration, in ppm
+    pub audio_synced: bool,         // Audio frame walk is on a frame boundary (until a bad header)
+    pub audio_frame_skip: usize,    // Bytes of the current audio frame still to come
+    pub audio_header: Vec<u8>,      // Frame header bytes split across packets
+    pub video_header: Vec<u8>,      // Start of the current PES while the video codec is still unknown
+    pub video_header_skip: Option<usize>, // Collecting `video_header`: PES header bytes still to skip
 }
 
-/// Analysis modes for different levels of processing
+/// Analysis modes for different levels of processing. Every TR-101 mode is Mux plus
+/// error counting: codec detection runs the same way, so one pass gives both the codec
+/// detail and the counters of the chosen priorities.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum AnalysisMode {
     /// Basic stream detection only (codec, bitrate, basic metadata)
     Mux,
-    /// Full TR 101 290 compliance analysis (all priorities)
+    /// Mux + full TR 101 290 compliance analysis (all priorities)
     Tr101,
-    /// TR 101 290 Priority 1 errors only (critical transport errors)
+    /// Mux + TR 101 290 Priority 1 errors only (critical transport errors)
     Tr101Priority1,
-    /// TR 101 290 Priority 1+2 errors (critical + recommended)
+    /// Mux + TR 101 290 Priority 1+2 errors (critical + recommended)
     Tr101Priority12,
+    /// Mux + TR 101 290 Priority 3 errors only (SI repetition, CRC and timing)
+    Tr101Priority3,
     /// No analysis, raw stream detection only
     None,
 }
 
+impl AnalysisMode {
+    /// Mode with codec detection plus the TR 101 290 checks up to `max_priority`
+    /// (0 → Mux only, 1, 2, 3 or more → all)
+    pub fn with_tr101_priority(max_priority: u8) -> Self {
+        match max_priority {
+            0 => AnalysisMode::Mux,
+            1 => AnalysisMode::Tr101Priority1,
+            2 => AnalysisMode::Tr101Priority12,
+            _ => AnalysisMode::Tr101,
+        }
+    }
+
+    /// Whether SI tables and elementary streams are parsed (codec, bitrate, timestamps)
+    pub fn detects_codecs(self) -> bool {
+        !matches!(self, AnalysisMode::None)
+    }
+
+    /// Highest TR 101 290 priority checked, None when no errors are counted
+    pub fn tr101_priority(self) -> Option<u8> {
+        match self {
+            AnalysisMode::Tr101Priority1 => Some(1),
+            AnalysisMode::Tr101Priority12 => Some(2),
+            AnalysisMode::Tr101 | AnalysisMode::Tr101Priority3 => Some(3),
+            AnalysisMode::Mux | AnalysisMode::None => None,
+        }
+    }
+
+    /// Whether the TR 101 290 checks of `priority` run in this mode
+    pub fn checks_priority(self, priority: u8) -> bool {
+        match self {
+            AnalysisMode::Tr101Priority3 => priority == 3,
+            _ => self.tr101_priority().is_some_and(|max| priority <= max),
+        }
+    }
+}
+
+/// Which frame rate wins when the codec-signaled fps and the PTS-derived estimate disagree.
+/// The PTS estimate always fills in when the codec signals none.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FpsSource {
+    /// Keep the signaled fps unless the PTS estimate differs by more than this many fps
+    PtsOverride(f32),
+    /// Always keep the signaled fps (VUI/sequence header timing)
+    Signaled,
+}
+
+impl Default for FpsSource {
+    fn default() -> Self {
+        FpsSource::PtsOverride(crate::constants::DEFAULT_FPS_PTS_TOLERANCE)
+    }
+}
+
+/// Codec parser forced for a stream_type through `Options::codec_overrides`, for private
+/// or colliding stream_type assignments (e.g. 0x80 carrying H.264 instead of LPCM)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum CodecHint {
+    Mpeg2Video,
+    H264,
+    Hevc,
+    Vvc,
+    Av1,
+    Mpeg1Audio, // MPEG-1/2 Audio (MP2)
+    AacAdts,
+    AacLatm,
+    Ac3,
+    Eac3,
+    DvbSubtitle,
+    /// Treat as data: no codec parsing at all
+    Data,
+}
+
+impl CodecHint {
+    /// stream_type whose built-in parser handles this hint; 0x00 (reserved) for `Data`,
+    /// which no parser accepts
+    pub(crate) fn stream_type(self) -> u8 {
+        match self {
+            CodecHint::Mpeg2Video => 0x02,
+            CodecHint::H264 => 0x1B,
+            CodecHint::Hevc => 0x24,
+            CodecHint::Vvc => 0x33,
+            CodecHint::Av1 | CodecHint::DvbSubtitle => 0x06,
+            CodecHint::Mpeg1Audio => 0x04,
+            CodecHint::AacAdts => 0x0F,
+            CodecHint::AacLatm => 0x11,
+            CodecHint::Ac3 => 0x81,
+            CodecHint::Eac3 => 0x87,
+            CodecHint::Data => 0x00,
+        }
+    }
+}
+
+/// Time base for bitrates: arrival time, or stream time from the PCR
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+pub enum BitrateClock {
+    /// Bytes per second of arrival time; right for live input only
+    #[default]
+    Wallclock,
+    /// Bytes per second of PCR time, independent of how fast the input is read.
+    /// Falls back to wallclock until a PCR has been seen.
+    Pcr,
+}
+
 /// Control commands for runtime analysis mode switching
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone)]
 pub enum AnalysisCommand {
     Start(AnalysisMode),
     Stop,
-    GetStatus,
+    /// The current [`AnalysisStatus`] is sent back on the given channel
+    GetStatus(tokio::sync::mpsc::Sender<AnalysisStatus>),
 }
 
-/// Response from analysis control commands
+/// Response to [`AnalysisCommand::GetStatus`]. `packets_processed == 0` means analysis
+/// has not seen any data yet, as opposed to having analysed packets without errors.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct AnalysisStatus {
     pub current_mode: Option<AnalysisMode>,
     pub is_running: bool,
+    pub uptime: std::time::Duration,  // since the inspector was started
+    pub packets_processed: u64,       // TS packets analysed so far
+}
+
+/// Notified when a PAT update adds or removes programs (e.g. part-time services).
+/// The first PAT reports every program it lists as added.
+pub trait ProgramListener: Send + Sync {
+    fn on_program_added(&self, _program_number: u16) {}
+    fn on_program_removed(&self, _program_number: u16) {}
+}
+
+/// Contents of a CRC-validated SI table, as handed to [`SiTableListener`]. PAT and PMT
+/// are delivered once all their sections have arrived; the others per section.
+pub enum SiTable<'a> {
+    Pat(&'a crate::psi::PatSection),
+    Pmt(&'a crate::psi::PmtSection),
+    Cat(&'a crate::psi::cat::CatSection),
+    /// NIT actual (0x40) or other (0x41)
+    Nit(&'a crate::psi::nit::NitSection),
+    /// SDT actual (0x42) or other (0x46)
+    Sdt(&'a crate::psi::sdt::SdtSection),
+    /// EIT present/following, actual (0x4E) or other (0x4F)
+    EitPf(&'a crate::psi::eit::EitPfSection),
+    /// EIT schedule, actual (0x50-0x5F) or other (0x60-0x6F)
+    EitSchedule(&'a crate::psi::eit::EitScheduleSection),
+    /// UTC_time as Unix seconds (the TDT carries no CRC, it is passed on as received)
+    Tdt { utc: Option<i64> },
+    /// UTC_time as Unix seconds and the descriptor loop (local_time_offset 0x58)
+    Tot { utc: Option<i64>, descriptors: &'a [crate::psi::descriptor::Descriptor] },
+}
+
+/// Notified of every SI table that passed its CRC check (PAT, PMT, CAT, NIT, SDT,
+/// EIT, TDT, TOT), with the PID it arrived on, its table_id and version_number
+/// (None for TDT/TOT, which have none). Called on the packet processing path, so
+/// implementations should hand the data off rather than block.
+pub trait SiTableListener: Send + Sync {
+    fn on_table(&self, _pid: u16, _table_id: u8, _version: Option<u8>, _table: &SiTable<'_>) {}
+}
+
+/// Snapshot of the last-known PSI/SI tables, as parsed
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SiTables {
+    pub pat: Option<crate::psi::PatSection>,
+    /// pmt_pid → PMT
+    pub pmts: std::collections::HashMap<u16, crate::psi::PmtSection>,
+    /// SDT-actual
+    pub sdt: Option<crate::psi::sdt::SdtSection>,
+    /// NIT-actual
+    pub nit: Option<crate::psi::nit::NitSection>,
 }
 
 /// Configuration options for the inspector
 pub struct Options {
     pub addr: std::net::SocketAddr,
+    /// Local interface address used to join IPv4 multicast groups (kernel default if None);
+    /// IPv6 groups are joined on the interface index in `addr`'s scope id
+    pub interface: Option<std::net::IpAddr>,
+    /// UDP socket receive buffer size in bytes (4 MiB if None)
+    pub recv_buffer_bytes: Option<usize>,
+    /// Bind 0.0.0.0 (or ::) on `addr`'s port instead of `addr` itself (multicast groups are still joined).
+    /// The destination address is not checked then, so any datagram to the port is accepted;
+    /// `source_filter` is the only filter
+    pub bind_any: bool,
+    /// Only accept datagrams sent from this address
+    pub source_filter: Option<std::net::IpAddr>,
     pub refresh_secs: u64,
+    /// Stop after this many seconds with a final report (`run`, `run_to_writer`, `run_hitless`
+    /// and the reader entry points); None runs until Ctrl-C, a socket error or end of stream
+    pub duration_secs: Option<u64>,
     pub analysis_mode: Option<AnalysisMode>,
+    /// Print a one-line status summary, updated in place, instead of JSON reports
+    pub compact: bool,
+    /// Print timestamped lines for PSI version changes, programs coming and going and
+    /// first Priority 1 errors, instead of JSON reports
+    pub events: bool,
+    /// Write each JSON report as one compact line (JSON Lines) instead of pretty-printed;
+    /// the schema is the same
+    pub jsonl: bool,
+    /// Only analyse and report these program numbers (PSI is always parsed)
+    pub program_filter: Option<Vec<u16>>,
+    /// Only analyse these elementary PIDs; reports keep programs carrying one of them
+    pub pid_filter: Option<Vec<u16>>,
+    /// Count null packets (PID 0x1FFF) towards `mux_bitrate_kbps`
+    pub include_null_in_mux: bool,
+    /// Program added/removed notifications; shared by every input of `run_multi`/`run_hitless`
+    pub program_listener: Option<std::sync::Arc<dyn ProgramListener>>,
+    /// Told of every CRC-validated PSI/SI table; None skips building the notifications
+    pub si_table_listener: Option<std::sync::Arc<dyn SiTableListener>>,
+    /// Precedence between signaled and PTS-derived fps (`FpsSource::default()` = 2 fps tolerance)
+    pub fps_source: FpsSource,
+    /// Time base for bitrates; None picks PCR for reader input and wallclock for sockets
+    pub bitrate_clock: Option<BitrateClock>,
+    /// Also report as soon as a PAT/PMT version changes or a Priority 1 counter first
+    /// leaves zero, at most once per this interval (bursts are folded into one report).
+    /// None reports on the `refresh_secs` cadence only.
+    pub event_debounce: Option<std::time::Duration>,
+    /// Keep the last this many packets that raised a Priority 1 error (`error_packets`
+    /// in reports); None keeps none. Costs about 250 bytes per packet.
+    pub error_capture: Option<usize>,
+    /// Parser to use per PMT stream_type, ahead of the built-in mapping and of descriptor-based
+    /// detection (registration, DVB AC-3 descriptors); empty keeps the defaults
+    pub codec_overrides: std::collections::HashMap<u8, CodecHint>,
+    /// Stream lineup expected per program number; programs whose PMT differs report
+    /// `stream_mismatches` (report-only, the verdict is unaffected). Empty checks nothing.
+    pub expected_streams: std::collections::HashMap<u16, StreamTemplate>,
+    /// Programs to report as if a PMT declared them, keyed by program number. Programs from
+    /// the PSI are still reported; a PAT/PMT entry for a program number declared here is ignored.
+    pub manual_programs: std::collections::HashMap<u16, ManualProgram>,
 }
\ No newline at end of file
@@ -0,0 +1,262 @@
+//! Deterministic builders for synthetic MPEG-TS input, shared by the integration tests.
+//!
+//! Everything here produces spec-valid bytes (ISO/IEC 13818-1): 188-byte packets with
+//! the sync byte, PSI sections with a correct CRC-32 and PES packets with PTS/DTS, so a
+//! test can assemble a stream, feed it through `inspector::run_from_reader` (or decode
+//! single packets with `inspector::inspect_packet`) and assert on the report.
+//!
+//! ```ignore
+//! mod common;
+//! use common::*;
+//!
+//! let mut ts = TsBuilder::new();
+//! ts.psi(0x0000, &build_pat(1, 0, &[(1, 0x100)]));
+//! ts.psi(0x0100, &build_pmt(1, 0, 0x101, &[], &[(0x1B, 0x101, vec![])]));
+//! ts.pes(0x101, &build_pes(0xE0, Some(90_000), None, &[0, 0, 0, 1, 0x09, 0xF0]), Some(0));
+//! let bytes = ts.into_bytes();
+//! ```
+
+#![allow(dead_code)] // each test binary uses its own subset
+
+use std::collections::HashMap;
+
+use crc::{Crc, CRC_32_MPEG_2};
+
+pub const TS_PACKET_SIZE: usize = 188;
+pub const NULL_PID: u16 = 0x1FFF;
+
+const CRC_MPEG: Crc<u32> = Crc::<u32>::new(&CRC_32_MPEG_2);
+
+/// One 188-byte packet carrying `payload` (adaptation_field_control 1), padded with
+/// 0xFF stuffing bytes. Panics if `payload` exceeds 184 bytes.
+pub fn build_ts_packet(pid: u16, cc: u8, payload_unit_start: bool, payload: &[u8]) -> Vec<u8> {
+    assert!(payload.len() <= TS_PACKET_SIZE - 4, "payload does not fit one packet");
+    let mut pkt = header(pid, cc, payload_unit_start, 0x10);
+    pkt.extend_from_slice(payload);
+    pkt.resize(TS_PACKET_SIZE, 0xFF);
+    pkt
+}
+
+/// One packet with an adaptation field carrying `pcr` (27 MHz ticks), followed by as
+/// much of `payload` as fits; the adaptation field is stuffed so the packet is exactly
+/// 188 bytes. Returns the packet and the number of payload bytes consumed.
+pub fn build_ts_packet_with_pcr(
+    pid: u16,
+    cc: u8,
+    payload_unit_start: bool,
+    pcr: Option<u64>,
+    random_access: bool,
+    payload: &[u8],
+) -> (Vec<u8>, usize) {
+    let mut af = vec![(if random_access { 0x40 } else { 0 }) | (if pcr.is_some() { 0x10 } else { 0 })];
+    if let Some(pcr) = pcr {
+        af.extend_from_slice(&encode_pcr(pcr));
+    }
+    // 4 header bytes + adaptation_field_length byte + flags/PCR
+    let room = TS_PACKET_SIZE - 5 - af.len();
+    let take = payload.len().min(room);
+    af.resize(af.len() + room - take, 0xFF);
+
+    let afc = if take > 0 { 0x30 } else { 0x20 };
+    let mut pkt = header(pid, cc, payload_unit_start, afc);
+    pkt.push(af.len() as u8);
+    pkt.extend_from_slice(&af);
+    pkt.extend_from_slice(&payload[..take]);
+    debug_assert_eq!(pkt.len(), TS_PACKET_SIZE);
+    (pkt, take)
+}
+
+/// A null packet (PID 0x1FFF)
+pub fn build_null_packet() -> Vec<u8> {
+    build_ts_packet(NULL_PID, 0, false, &[])
+}
+
+/// Long-form PSI section with section_syntax_indicator set and the CRC-32 appended.
+/// `body` is everything after last_section_number.
+pub fn build_section(table_id: u8, table_id_extension: u16, version: u8, section_number: u8, last_section: u8, body: &[u8]) -> Vec<u8> {
+    let section_length = 5 + body.len() + 4;
+    assert!(section_length <= 1021, "section too long");
+    let mut sec = vec![
+        table_id,
+        0xB0 | (section_length >> 8) as u8,
+        section_length as u8,
+        (table_id_extension >> 8) as u8,
+        table_id_extension as u8,
+        0xC1 | ((version & 0x1F) << 1), // current_next_indicator set
+        section_number,
+        last_section,
+    ];
+    sec.extend_from_slice(body);
+    let crc = CRC_MPEG.checksum(&sec);
+    sec.extend_from_slice(&crc.to_be_bytes());
+    sec
+}
+
+/// PAT section for `programs` as (program_number, PMT PID); program 0 maps the NIT PID
+pub fn build_pat(transport_stream_id: u16, version: u8, programs: &[(u16, u16)]) -> Vec<u8> {
+    let mut body = Vec::with_capacity(programs.len() * 4);
+    for &(program_number, pid) in programs {
+        body.extend_from_slice(&program_number.to_be_bytes());
+        body.extend_from_slice(&(0xE000 | pid).to_be_bytes());
+    }
+    build_section(0x00, transport_stream_id, version, 0, 0, &body)
+}
+
+/// PMT section; `streams` are (stream_type, elementary PID, ES_info descriptors)
+pub fn build_pmt(program_number: u16, version: u8, pcr_pid: u16, program_info: &[u8], streams: &[(u8, u16, Vec<u8>)]) -> Vec<u8> {
+    let mut body = Vec::new();
+    body.extend_from_slice(&(0xE000 | pcr_pid).to_be_bytes());
+    body.extend_from_slice(&(0xF000 | program_info.len() as u16).to_be_bytes());
+    body.extend_from_slice(program_info);
+    for (stream_type, pid, es_info) in streams {
+        body.push(*stream_type);
+        body.extend_from_slice(&(0xE000 | pid).to_be_bytes());
+        body.extend_from_slice(&(0xF000 | es_info.len() as u16).to_be_bytes());
+        body.extend_from_slice(es_info);
+    }
+    build_section(0x02, program_number, version, 0, 0, &body)
+}
+
+/// A descriptor: tag, length, data
+pub fn build_descriptor(tag: u8, data: &[u8]) -> Vec<u8> {
+    assert!(data.len() <= 255, "descriptor too long");
+    [vec![tag, data.len() as u8], data.to_vec()].concat()
+}
+
+/// PES packet with optional PTS (and DTS, which requires a PTS). Video stream_ids
+/// (0xE0-0xEF) get PES_packet_length 0 (unbounded), as broadcast encoders emit.
+pub fn build_pes(stream_id: u8, pts: Option<u64>, dts: Option<u64>, data: &[u8]) -> Vec<u8> {
+    assert!(dts.is_none() || pts.is_some(), "DTS without PTS");
+    let mut header = Vec::new();
+    let flags = match (pts, dts) {
+        (Some(_), Some(_)) => 0xC0,
+        (Some(_), None) => 0x80,
+        _ => 0x00,
+    };
+    if let Some(pts) = pts {
+        header.extend_from_slice(&encode_timestamp(if dts.is_some() { 0x3 } else { 0x2 }, pts));
+    }
+    if let Some(dts) = dts {
+        header.extend_from_slice(&encode_timestamp(0x1, dts));
+    }
+
+    let mut pes = vec![0x00, 0x00, 0x01, stream_id];
+    let length = 3 + header.len() + data.len();
+    let length = if (0xE0..=0xEF).contains(&stream_id) || length > 0xFFFF { 0 } else { length };
+    pes.extend_from_slice(&(length as u16).to_be_bytes());
+    pes.extend_from_slice(&[0x80, flags, header.len() as u8]);
+    pes.extend_from_slice(&header);
+    pes.extend_from_slice(data);
+    pes
+}
+
+/// 33-bit PTS/DTS field with its 4-bit prefix and marker bits
+fn encode_timestamp(prefix: u8, ts: u64) -> [u8; 5] {
+    let ts = ts & ((1 << 33) - 1);
+    [
+        (prefix << 4) | (((ts >> 30) as u8 & 0x07) << 1) | 1,
+        (ts >> 22) as u8,
+        (((ts >> 15) as u8) << 1) | 1,
+        (ts >> 7) as u8,
+        ((ts as u8) << 1) | 1,
+    ]
+}
+
+/// program_clock_reference_base (33 bits, 90 kHz) and extension (9 bits) from 27 MHz ticks
+fn encode_pcr(pcr: u64) -> [u8; 6] {
+    let base = (pcr / 300) & ((1 << 33) - 1);
+    let ext = (pcr % 300) as u16;
+    [
+        (base >> 25) as u8,
+        (base >> 17) as u8,
+        (base >> 9) as u8,
+        (base >> 1) as u8,
+        (((base & 1) as u8) << 7) | 0x7E | ((ext >> 8) as u8 & 0x01),
+        ext as u8,
+    ]
+}
+
+fn header(pid: u16, cc: u8, payload_unit_start: bool, adaptation_field_control: u8) -> Vec<u8> {
+    let mut pkt = Vec::with_capacity(TS_PACKET_SIZE);
+    pkt.push(0x47);
+    pkt.push((if payload_unit_start { 0x40 } else { 0 }) | ((pid >> 8) as u8 & 0x1F));
+    pkt.push(pid as u8);
+    pkt.push(adaptation_field_control | (cc & 0x0F));
+    pkt
+}
+
+/// Accumulates packets while keeping a continuity counter per PID, so a test only
+/// has to say what goes on which PID
+#[derive(Default)]
+pub struct TsBuilder {
+    packets: Vec<Vec<u8>>,
+    cc: HashMap<u16, u8>,
+}
+
+impl TsBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Next continuity_counter for `pid`; packets without payload don't advance it
+    fn next_cc(&mut self, pid: u16) -> u8 {
+        let cc = self.cc.entry(pid).or_insert(0);
+        let current = *cc;
+        *cc = (*cc + 1) & 0x0F;
+        current
+    }
+
+    /// A PSI section on `pid`, with pointer_field 0, split over as many packets as needed
+    pub fn psi(&mut self, pid: u16, section: &[u8]) -> &mut Self {
+        let data = [&[0u8][..], section].concat();
+        for (i, chunk) in data.chunks(TS_PACKET_SIZE - 4).enumerate() {
+            let cc = self.next_cc(pid);
+            self.packets.push(build_ts_packet(pid, cc, i == 0, chunk));
+        }
+        self
+    }
+
+    /// A PES packet on `pid`; the first packet carries `pcr` (27 MHz) and the
+    /// random_access_indicator when one is given
+    pub fn pes(&mut self, pid: u16, pes: &[u8], pcr: Option<u64>) -> &mut Self {
+        let mut rest = pes;
+        let mut first = true;
+        while first || !rest.is_empty() {
+            let cc = self.next_cc(pid);
+            let pkt = if first && pcr.is_some() || rest.len() < TS_PACKET_SIZE - 4 {
+                // Adaptation field for the PCR, or as stuffing in the last packet
+                let pcr = if first { pcr } else { None };
+                let (pkt, used) = build_ts_packet_with_pcr(pid, cc, first, pcr, pcr.is_some(), rest);
+                rest = &rest[used..];
+                pkt
+            } else {
+                let (chunk, tail) = rest.split_at(TS_PACKET_SIZE - 4);
+                rest = tail;
+                build_ts_packet(pid, cc, first, chunk)
+            };
+            self.packets.push(pkt);
+            first = false;
+        }
+        self
+    }
+
+    /// Any prebuilt packet, e.g. from [`build_null_packet`], left as is
+    pub fn packet(&mut self, packet: Vec<u8>) -> &mut Self {
+        self.packets.push(packet);
+        self
+    }
+
+    /// Skip one continuity_counter value on `pid`, as a lost packet would
+    pub fn drop_packet(&mut self, pid: u16) -> &mut Self {
+        self.next_cc(pid);
+        self
+    }
+
+    pub fn packets(&self) -> &[Vec<u8>] {
+        &self.packets
+    }
+
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.packets.concat()
+    }
+}
@@ -0,0 +1,410 @@
+//! End-to-end checks on synthetic streams built with `common`
+
+mod common;
+
+use std::collections::HashMap;
+
+use common::*;
+use mpegts_inspector::inspector::{run_from_reader, AnalysisMode, CodecInfo, FpsSource, InspectorReport, ManualProgram, Options, PidCollision, PidCollisionKind};
+
+fn options(analysis_mode: AnalysisMode) -> Options {
+    Options {
+        addr: "127.0.0.1:1234".parse().unwrap(),
+        interface: None,
+        recv_buffer_bytes: None,
+        bind_any: false,
+        source_filter: None,
+        refresh_secs: 3600, // only the end-of-stream report
+        duration_secs: None,
+        analysis_mode: Some(analysis_mode),
+        compact: false,
+        events: false,
+        jsonl: false,
+        program_filter: None,
+        pid_filter: None,
+        include_null_in_mux: false,
+        program_listener: None,
+        si_table_listener: None,
+        fps_source: FpsSource::default(),
+        bitrate_clock: None,
+        event_debounce: None,
+        error_capture: None,
+        codec_overrides: HashMap::new(),
+        expected_streams: HashMap::new(),
+        manual_programs: HashMap::new(),
+    }
+}
+
+/// Feed `bytes` through `run_from_reader` and return the final report
+async fn inspect(bytes: &[u8], analysis_mode: AnalysisMode) -> InspectorReport {
+    let mut last = None;
+    run_from_reader(bytes, options(analysis_mode), |report| last = Some(report)).await.unwrap();
+    last.expect("a report at end of stream")
+}
+
+/// Feed `bytes` through `run_from_reader` with `manual_programs` declared and return
+/// the final report
+async fn inspect_with_manual(bytes: &[u8], manual_programs: HashMap<u16, ManualProgram>) -> InspectorReport {
+    let mut last = None;
+    let options = Options { manual_programs, ..options(AnalysisMode::Tr101Priority1) };
+    run_from_reader(bytes, options, |report| last = Some(report)).await.unwrap();
+    last.expect("a report at end of stream")
+}
+
+/// Program 1 declared by hand: H.264 on 0x101 (also the PCR PID) and AAC on 0x102
+fn manual_program_1() -> HashMap<u16, ManualProgram> {
+    HashMap::from([(1, ManualProgram { pcr_pid: 0x101, streams: vec![(0x101, 0x1B), (0x102, 0x0F)] })])
+}
+
+/// Baseline 320x240 H.264 SPS on 0x101 and a 48 kHz stereo AAC ADTS frame on 0x102
+fn h264_and_aac(ts: &mut TsBuilder, i: u64) {
+    let pts = 90_000 + i * 3600;
+    let sps = [0, 0, 0, 1, 0x67, 0x42, 0xC0, 0x1E, 0xF4, 0x0A, 0x0F, 0xC8];
+    ts.pes(0x101, &build_pes(0xE0, Some(pts), None, &sps), Some(pts * 300));
+    let adts = [0xFF, 0xF1, 0x4C, 0x80, 0x02, 0x1F, 0xFC, 0, 0, 0, 0, 0, 0, 0, 0, 0];
+    ts.pes(0x102, &build_pes(0xC0, Some(pts), None, &adts), None);
+}
+
+/// Codec names of program 1's streams on 0x101 and 0x102
+fn manual_program_codecs(report: &InspectorReport) -> (Option<String>, Option<String>) {
+    let program = report.programs.iter().find(|p| p.program_number == 1).expect("program 1");
+    let codec = |pid: u16| match program.streams.iter().find(|s| s.pid == pid)?.codec.as_ref()? {
+        CodecInfo::Video(v) => Some(v.codec.clone()),
+        CodecInfo::Audio(a) => Some(a.codec.clone()),
+        CodecInfo::Subtitle(s) => Some(s.codec.clone()),
+    };
+    (codec(0x101), codec(0x102))
+}
+
+/// PAT and PMT for program 1 (PMT PID 0x100) with one H.264 stream on 0x101, which
+/// also carries the PCR
+fn psi(ts: &mut TsBuilder) {
+    ts.psi(0x0000, &build_pat(1, 0, &[(1, 0x100)]));
+    ts.psi(0x0100, &build_pmt(1, 0, 0x101, &[], &[(0x1B, 0x101, vec![])]));
+}
+
+#[tokio::test]
+async fn clean_stream_reports_program_without_errors() {
+    let mut ts = TsBuilder::new();
+    for i in 0..10u64 {
+        psi(&mut ts);
+        let pts = 90_000 + i * 3600;
+        ts.pes(0x101, &build_pes(0xE0, Some(pts), None, &[0, 0, 0, 1, 0x09, 0xF0]), Some(pts * 300));
+    }
+
+    let report = inspect(&ts.into_bytes(), AnalysisMode::Tr101Priority1).await;
+    let tr101 = &report.tr101_metrics;
+    assert_eq!(tr101.continuity_counter_errors, 0);
+    assert_eq!(tr101.pat_crc_errors, 0);
+    assert_eq!(tr101.pmt_crc_errors, 0);
+    assert_eq!(tr101.sync_byte_errors, 0);
+
+    let program = report.programs.iter().find(|p| p.program_number == 1).expect("program 1");
+    assert_eq!(program.pcr_pid, Some(0x101));
+    let stream = program.streams.iter().find(|s| s.pid == 0x101).expect("stream 0x101");
+    assert_eq!(stream.stream_type, 0x1B);
+    assert!(stream.pes.has_pts);
+}
+
+#[tokio::test]
+async fn lost_packet_is_a_continuity_error() {
+    let mut ts = TsBuilder::new();
+    psi(&mut ts);
+    for i in 0..6u64 {
+        if i == 3 {
+            ts.drop_packet(0x101);
+        }
+        ts.pes(0x101, &build_pes(0xE0, Some(i * 3600), None, &[0; 100]), None);
+    }
+
+    let report = inspect(&ts.into_bytes(), AnalysisMode::Tr101Priority1).await;
+    assert_eq!(report.tr101_metrics.continuity_counter_errors, 1);
+}
+
+#[tokio::test]
+async fn first_packet_on_a_pid_sets_the_cc_baseline() {
+    let mut ts = TsBuilder::new();
+    psi(&mut ts);
+    // A capture joined mid-stream: the first packet on 0x101 has CC 7, not 0
+    for cc in 7..12u8 {
+        ts.packet(build_ts_packet(0x101, cc, false, &[0; 184]));
+    }
+
+    let report = inspect(&ts.into_bytes(), AnalysisMode::Tr101Priority1).await;
+    assert_eq!(report.tr101_metrics.continuity_counter_errors, 0);
+    assert_eq!(report.tr101_metrics.duplicate_packets, 0);
+}
+
+#[tokio::test]
+async fn adaptation_only_packets_do_not_move_the_cc_baseline() {
+    let mut ts = TsBuilder::new();
+    psi(&mut ts);
+    ts.packet(build_ts_packet(0x101, 3, false, &[0; 184]));
+    // adaptation_field_control 10 with a stray CC: neither checked nor a new baseline
+    ts.packet(build_ts_packet_with_pcr(0x101, 9, false, Some(27_000_000), false, &[]).0);
+    ts.packet(build_ts_packet(0x101, 4, false, &[0; 184]));
+
+    let report = inspect(&ts.into_bytes(), AnalysisMode::Tr101Priority1).await;
+    assert_eq!(report.tr101_metrics.continuity_counter_errors, 0);
+}
+
+#[tokio::test]
+async fn pcr_on_adaptation_only_packets_is_checked() {
+    let mut ts = TsBuilder::new();
+    ts.psi(0x0000, &build_pat(1, 0, &[(1, 0x100)]));
+    ts.psi(0x0100, &build_pmt(1, 0, 0x1FF, &[], &[(0x1B, 0x101, vec![])]));
+    // A dedicated PCR PID: 40 ms apart, then a 200 ms gap
+    for pcr_ms in [0, 40, 80, 280, 320u64] {
+        ts.packet(build_ts_packet_with_pcr(0x1FF, 0, false, Some(pcr_ms * 27_000), false, &[]).0);
+    }
+    // TEI on an adaptation-only packet
+    let mut errored = build_ts_packet_with_pcr(0x1FF, 0, false, Some(360 * 27_000), false, &[]).0;
+    errored[1] |= 0x80;
+    ts.packet(errored);
+
+    let report = inspect(&ts.into_bytes(), AnalysisMode::Tr101Priority12).await;
+    assert_eq!(report.tr101_metrics.pcr_repetition_errors, 1);
+    assert_eq!(report.tr101_metrics.pcr_discontinuity_errors, 0);
+    assert_eq!(report.tr101_metrics.transport_error_indicator, 1);
+    let program = report.programs.iter().find(|p| p.program_number == 1).expect("program 1");
+    assert_eq!(program.pcr_interval.as_ref().map(|i| i.max_ms.round()), Some(200.0));
+}
+
+#[tokio::test]
+async fn eit_pf_on_pid_0x12_is_checked() {
+    // EIT p/f actual, no events: transport_stream_id, original_network_id,
+    // segment_last_section_number, last_table_id
+    let eit = build_section(0x4E, 1, 0, 0, 0, &[0x00, 0x01, 0x00, 0x01, 0x00, 0x4E]);
+    let mut corrupt = eit.clone();
+    *corrupt.last_mut().unwrap() ^= 0xFF;
+
+    let mut ts = TsBuilder::new();
+    psi(&mut ts);
+    ts.psi(0x0012, &eit);
+    ts.psi(0x0012, &corrupt);
+    ts.psi(0x0012, &eit);
+
+    let report = inspect(&ts.into_bytes(), AnalysisMode::Tr101).await;
+    assert_eq!(report.tr101_metrics.eit_crc_errors, 1);
+}
+
+#[tokio::test]
+async fn elementary_pid_declared_differently_by_two_programs_is_a_collision() {
+    let mut ts = TsBuilder::new();
+    for _ in 0..3 {
+        ts.psi(0x0000, &build_pat(1, 0, &[(1, 0x100), (2, 0x200)]));
+        ts.psi(0x0100, &build_pmt(1, 0, 0x101, &[], &[(0x1B, 0x101, vec![]), (0x03, 0x103, vec![])]));
+        ts.psi(0x0200, &build_pmt(2, 0, 0x201, &[], &[(0x1B, 0x201, vec![]), (0x0F, 0x103, vec![])]));
+    }
+
+    let report = inspect(&ts.into_bytes(), AnalysisMode::Tr101Priority1).await;
+    assert_eq!(
+        report.program_pid_collisions,
+        vec![PidCollision { pid: 0x103, kind: PidCollisionKind::ElementaryPid, programs: vec![1, 2] }]
+    );
+    assert!(report.pid_role_conflicts.is_empty());
+}
+
+#[tokio::test]
+async fn shared_component_is_not_a_collision() {
+    let mut ts = TsBuilder::new();
+    for _ in 0..3 {
+        ts.psi(0x0000, &build_pat(1, 0, &[(1, 0x100), (2, 0x200)]));
+        // One audio track shared by both programs
+        ts.psi(0x0100, &build_pmt(1, 0, 0x101, &[], &[(0x1B, 0x101, vec![]), (0x03, 0x103, vec![])]));
+        ts.psi(0x0200, &build_pmt(2, 0, 0x201, &[], &[(0x1B, 0x201, vec![]), (0x03, 0x103, vec![])]));
+    }
+
+    let report = inspect(&ts.into_bytes(), AnalysisMode::Tr101Priority1).await;
+    assert!(report.program_pid_collisions.is_empty());
+}
+
+#[tokio::test]
+async fn elementary_pid_on_a_pmt_pid_is_parsed_as_psi() {
+    let mut ts = TsBuilder::new();
+    for i in 0..3u64 {
+        ts.psi(0x0000, &build_pat(1, 0, &[(1, 0x100), (2, 0x200)]));
+        // Program 1 declares program 2's PMT PID as its audio stream
+        ts.psi(0x0100, &build_pmt(1, 0, 0x101, &[], &[(0x1B, 0x101, vec![]), (0x03, 0x200, vec![])]));
+        ts.psi(0x0200, &build_pmt(2, 0, 0x201, &[], &[(0x1B, 0x201, vec![])]));
+        ts.pes(0x101, &build_pes(0xE0, Some(i * 3600), None, &[0; 32]), Some(i * 3600 * 300));
+    }
+
+    let report = inspect(&ts.into_bytes(), AnalysisMode::Tr101Priority1).await;
+    assert_eq!(report.pid_role_conflicts, vec![0x200]);
+    assert!(report.program_pid_collisions.is_empty());
+    // PSI wins: program 2 keeps its PMT, and program 1 reports no stream on 0x200
+    let program_2 = report.programs.iter().find(|p| p.program_number == 2).expect("program 2");
+    assert_eq!(program_2.pcr_pid, Some(0x201));
+    let program_1 = report.programs.iter().find(|p| p.program_number == 1).expect("program 1");
+    assert!(program_1.streams.iter().all(|s| s.pid != 0x200));
+    assert_eq!(report.tr101_metrics.pmt_crc_errors, 0);
+}
+
+#[tokio::test]
+async fn identical_repeat_is_a_duplicate_and_other_payload_is_a_cc_error() {
+    let mut ts = TsBuilder::new();
+    psi(&mut ts);
+    ts.packet(build_ts_packet(0x101, 0, false, &[0x11; 184]));
+    ts.packet(build_ts_packet(0x101, 0, false, &[0x11; 184])); // legal duplicate
+    ts.packet(build_ts_packet(0x101, 1, false, &[0x22; 184]));
+    ts.packet(build_ts_packet(0x101, 1, false, &[0x33; 184])); // same CC, other data
+    ts.packet(build_ts_packet(0x101, 2, false, &[0x44; 184]));
+
+    let report = inspect(&ts.into_bytes(), AnalysisMode::Tr101Priority1).await;
+    assert_eq!(report.tr101_metrics.duplicate_packets, 1);
+    assert_eq!(report.tr101_metrics.continuity_counter_errors, 1);
+}
+
+#[tokio::test]
+async fn duplicate_packets_are_not_counted_twice_in_stream_statistics() {
+    let mut ts = TsBuilder::new();
+    psi(&mut ts);
+    ts.psi(0x0100, &build_pmt(1, 0, 0x101, &[], &[(0x1B, 0x101, vec![]), (0x03, 0x102, vec![])]));
+    for i in 0..5u64 {
+        let pts = 90_000 + i * 3600;
+        ts.pes(0x101, &build_pes(0xE0, Some(pts), None, &[0, 0, 0, 1, 0x09, 0xF0]), Some(pts * 300));
+        // Bounded audio PES over two packets
+        ts.pes(0x102, &build_pes(0xC0, Some(pts), None, &[0x55; 300]), None);
+        if i == 2 {
+            // Send the single-packet video PES and the audio tail twice
+            let packets = ts.packets();
+            let (video, audio_tail) = (packets[packets.len() - 3].clone(), packets[packets.len() - 1].clone());
+            ts.packet(video).packet(audio_tail);
+        }
+    }
+
+    let report = inspect(&ts.into_bytes(), AnalysisMode::Tr101Priority1).await;
+    assert_eq!(report.tr101_metrics.continuity_counter_errors, 0);
+    assert_eq!(report.tr101_metrics.duplicate_packets, 2);
+    let streams = &report.programs.iter().find(|p| p.program_number == 1).expect("program 1").streams;
+    let video = streams.iter().find(|s| s.pid == 0x101).expect("video stream");
+    // The first PES only registers the stream, the other four are counted once each
+    assert_eq!(video.frames_delivered, Some(4));
+    let audio = streams.iter().find(|s| s.pid == 0x102).expect("audio stream");
+    assert_eq!(audio.pes.pes_length_errors, 0);
+}
+
+/// MPEG-2 video (program 1, PID 0x101) with a 720x576 sequence header whose
+/// frame_rate_code is forbidden (0), so the fps comes from PTS deltas of `pts_step`
+async fn fps_from_pts_step(pts_step: u64) -> f32 {
+    let sequence_header = [0x00, 0x00, 0x01, 0xB3, 0x2D, 0x02, 0x40, 0x30, 0xFF, 0xFF, 0xE0, 0xA0];
+    let mut ts = TsBuilder::new();
+    ts.psi(0x0000, &build_pat(1, 0, &[(1, 0x100)]));
+    ts.psi(0x0100, &build_pmt(1, 0, 0x101, &[], &[(0x02, 0x101, vec![])]));
+    for i in 0..12u64 {
+        let pts = 90_000 + i * pts_step;
+        ts.pes(0x101, &build_pes(0xE0, Some(pts), None, &sequence_header), Some(pts * 300));
+    }
+
+    let report = inspect(&ts.into_bytes(), AnalysisMode::Mux).await;
+    let program = report.programs.iter().find(|p| p.program_number == 1).expect("program 1");
+    match &program.streams.iter().find(|s| s.pid == 0x101).expect("stream 0x101").codec {
+        Some(CodecInfo::Video(video)) => video.fps,
+        other => panic!("no video codec: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn fps_from_pts_deltas() {
+    assert_eq!(fps_from_pts_step(3003).await, 29.97); // 30000/1001
+    assert_eq!(fps_from_pts_step(3600).await, 25.0);
+}
+
+#[tokio::test]
+async fn pointer_into_stuffing_is_not_a_crc_error() {
+    let mut ts = TsBuilder::new();
+    psi(&mut ts);
+    // PAT packet whose pointer_field skips the section and lands in the stuffing
+    let mut payload = [&[0u8][..], &build_pat(1, 0, &[(1, 0x100)])].concat();
+    payload[0] = 100;
+    ts.drop_packet(0x0000).packet(build_ts_packet(0x0000, 1, true, &payload));
+    psi(&mut ts);
+
+    let report = inspect(&ts.into_bytes(), AnalysisMode::Tr101).await;
+    assert_eq!(report.tr101_metrics.pat_crc_errors, 0);
+    assert_eq!(report.tr101_metrics.continuity_counter_errors, 0);
+}
+
+/// Codec reported for an MPEG-2 video PES whose PES_header_data_length is
+/// `header_data_length`: a PTS followed by stuffing, then the sequence header
+async fn codec_behind_pes_header(header_data_length: u8) -> Option<CodecInfo> {
+    let sequence_header = [0x00, 0x00, 0x01, 0xB3, 0x2D, 0x02, 0x40, 0x33, 0xFF, 0xFF, 0xE0, 0xA0];
+    let mut ts = TsBuilder::new();
+    ts.psi(0x0000, &build_pat(1, 0, &[(1, 0x100)]));
+    ts.psi(0x0100, &build_pmt(1, 0, 0x101, &[], &[(0x02, 0x101, vec![])]));
+    for i in 0..4u64 {
+        let mut pes = build_pes(0xE0, Some(i * 3600), None, &[]);
+        pes[8] = header_data_length;
+        pes.resize(9 + header_data_length as usize, 0xFF);
+        pes.extend_from_slice(&sequence_header);
+        pes.resize(400, 0x00);
+        ts.pes(0x101, &pes, None);
+    }
+
+    let report = inspect(&ts.into_bytes(), AnalysisMode::Mux).await;
+    let program = report.programs.into_iter().find(|p| p.program_number == 1).expect("program 1");
+    program.streams.into_iter().find(|s| s.pid == 0x101).expect("stream 0x101").codec
+}
+
+#[tokio::test]
+async fn pes_header_filling_the_first_packet() {
+    // 9 + 175 = 184: the elementary stream starts with the second packet
+    match codec_behind_pes_header(175).await {
+        Some(CodecInfo::Video(video)) => assert_eq!((video.width, video.height, video.fps), (720, 576, 25.0)),
+        other => panic!("no video codec: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn pes_header_running_into_the_second_packet() {
+    // The header ends 40 bytes into the second packet
+    match codec_behind_pes_header(215).await {
+        Some(CodecInfo::Video(video)) => assert_eq!((video.width, video.height, video.fps), (720, 576, 25.0)),
+        other => panic!("no video codec: {other:?}"),
+    }
+}
+
+#[tokio::test]
+async fn manual_program_without_psi_reports_its_codecs() {
+    let mut ts = TsBuilder::new();
+    for i in 0..5u64 {
+        h264_and_aac(&mut ts, i);
+    }
+
+    let report = inspect_with_manual(&ts.into_bytes(), manual_program_1()).await;
+    assert_eq!(manual_program_codecs(&report), (Some("H.264".into()), Some("AAC".into())));
+    let program = report.programs.iter().find(|p| p.program_number == 1).expect("program 1");
+    assert_eq!(program.pcr_pid, Some(0x101));
+}
+
+#[tokio::test]
+async fn pat_without_the_manual_program_keeps_it() {
+    let mut ts = TsBuilder::new();
+    for i in 0..5u64 {
+        ts.psi(0x0000, &build_pat(1, i as u8 % 2, &[(2, 0x200)]));
+        ts.psi(0x0200, &build_pmt(2, 0, 0x201, &[], &[(0x1B, 0x201, vec![])]));
+        h264_and_aac(&mut ts, i);
+    }
+
+    let report = inspect_with_manual(&ts.into_bytes(), manual_program_1()).await;
+    assert_eq!(manual_program_codecs(&report), (Some("H.264".into()), Some("AAC".into())));
+    assert!(report.programs.iter().any(|p| p.program_number == 2));
+}
+
+#[tokio::test]
+async fn wire_pmt_does_not_replace_the_manual_program() {
+    let mut ts = TsBuilder::new();
+    for i in 0..5u64 {
+        ts.psi(0x0000, &build_pat(1, 0, &[(1, 0x100)]));
+        // The broken PMT on the wire only lists a stream that does not exist
+        ts.psi(0x0100, &build_pmt(1, 0, 0x105, &[], &[(0x02, 0x105, vec![])]));
+        h264_and_aac(&mut ts, i);
+    }
+
+    let report = inspect_with_manual(&ts.into_bytes(), manual_program_1()).await;
+    assert_eq!(manual_program_codecs(&report), (Some("H.264".into()), Some("AAC".into())));
+    let program = report.programs.iter().find(|p| p.program_number == 1).expect("program 1");
+    assert_eq!(program.pcr_pid, Some(0x101));
+    assert!(program.streams.iter().all(|s| s.pid != 0x105));
+}